@@ -0,0 +1,211 @@
+use std::{cell::RefCell, future::Future, pin::Pin, rc::Rc, time::Duration};
+
+use dioxus::prelude::server_fn::{client::{browser::BrowserClient, Client}, request::browser::BrowserRequest, response::browser::BrowserResponse, ServerFnError};
+use gloo_net::http::{Method, RequestBuilder, Response};
+use http::StatusCode;
+use serde::{de::DeserializeOwned, Serialize};
+use web_sys::{wasm_bindgen::JsCast, HtmlDocument, RequestCredentials};
+
+use crate::backend::{auths::csrf::{CSRF_COOKIE, CSRF_HEADER}, forms::{ApiError, CsrfToken}};
+
+/// API root used when none is given; set `API_BASE_URL` at build time to target another host,
+/// the default being the host that served the app.
+const DEFAULT_BASE_URL: &str = match option_env!("API_BASE_URL") {
+    Some(url) => url,
+    None => "/api/v1",
+};
+const DEFAULT_RETRIES: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+
+type RefreshHook = Rc<dyn Fn() -> Pin<Box<dyn Future<Output = bool>>>>;
+
+thread_local! {
+    /// CSRF token last returned by the server, for origins whose cookie scripts cannot read.
+    static CSRF_TOKEN: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Token to send in `CSRF_HEADER`: the one last returned by the server, else the cookie set for this origin.
+pub fn csrf_token() -> Option<String> {
+    CSRF_TOKEN.with_borrow(Clone::clone).or_else(csrf_cookie)
+}
+
+fn csrf_cookie() -> Option<String> {
+    let document = web_sys::window()?.document()?.dyn_into::<HtmlDocument>().ok()?;
+    document.cookie().ok()?
+        .split(';')
+        .find_map(|pair| pair.trim().strip_prefix(CSRF_COOKIE)?.strip_prefix('=').map(str::to_string))
+}
+
+fn remember_csrf_token(response: &Response) {
+    if let Some(token) = response.headers().get(CSRF_HEADER) {
+        CSRF_TOKEN.set(Some(token));
+    }
+}
+
+/// Server function client sending the CSRF token, which the server requires on every `/api` POST.
+/// Use with `#[server(client = CsrfClient)]`.
+pub struct CsrfClient;
+
+impl<E> Client<E> for CsrfClient {
+    type Request = BrowserRequest;
+    type Response = BrowserResponse;
+
+    fn send(request: BrowserRequest) -> impl Future<Output = Result<BrowserResponse, ServerFnError<E>>> + Send {
+        if let Some(token) = csrf_token() {
+            request.headers().set(CSRF_HEADER, &token);
+        }
+        <BrowserClient as Client<E>>::send(request)
+    }
+}
+
+/// Typed client for the REST API, for callers that cannot use server functions.
+/// Failures are decoded into the server's `ApiError`; idempotent requests are retried when the
+/// server cannot be reached or is temporarily unavailable.
+#[derive(Clone)]
+pub struct ApiClient {
+    base_url: String,
+    credentials: RequestCredentials,
+    retries: u32,
+    on_unauthorized: Option<RefreshHook>,
+}
+
+impl Default for ApiClient {
+    fn default() -> Self {
+        ApiClient::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str) -> ApiClient {
+        ApiClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            credentials: RequestCredentials::Include,
+            retries: DEFAULT_RETRIES,
+            on_unauthorized: None,
+        }
+    }
+
+    pub fn with_credentials(mut self, credentials: RequestCredentials) -> ApiClient {
+        self.credentials = credentials;
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> ApiClient {
+        self.retries = retries;
+        self
+    }
+
+    /// Called once when a request is refused with 401; resolving to `true` means the credentials
+    /// were refreshed and the request is sent again.
+    pub fn on_unauthorized<F, Fut>(mut self, refresh: F) -> ApiClient
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = bool> + 'static,
+    {
+        self.on_unauthorized = Some(Rc::new(move || Box::pin(refresh())));
+        self
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T, ApiError> {
+        self.send(Method::GET, path, query, None).await
+    }
+
+    pub async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: Option<&B>) -> Result<T, ApiError> {
+        self.send(Method::POST, path, &[], encode_body(body)?).await
+    }
+
+    pub async fn put<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: Option<&B>) -> Result<T, ApiError> {
+        self.send(Method::PUT, path, &[], encode_body(body)?).await
+    }
+
+    pub async fn delete<T: DeserializeOwned>(&self, path: &str) -> Result<T, ApiError> {
+        self.send(Method::DELETE, path, &[], None).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, method: Method, path: &str, query: &[(&str, String)], body: Option<String>) -> Result<T, ApiError> {
+        let retries = if is_idempotent(&method) { self.retries } else { 0 };
+        let mut refreshed = false;
+        let mut attempt = 0;
+        loop {
+            let result = self.send_once(method.clone(), path, query, body.clone()).await;
+            match &result {
+                Err(error) if error.status == StatusCode::UNAUTHORIZED.as_u16() && !refreshed => {
+                    refreshed = true;
+                    match &self.on_unauthorized {
+                        Some(refresh) if refresh().await => continue,
+                        _ => return result,
+                    }
+                },
+                Err(error) if is_transient(error.status) && attempt < retries => {
+                    attempt += 1;
+                    gloo_timers::future::sleep(RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+                },
+                _ => return result,
+            }
+        }
+    }
+
+    /// Fetches a CSRF token when none is known yet, as happens on the first request to another origin.
+    async fn ensure_csrf_token(&self) -> Result<String, ApiError> {
+        if let Some(token) = csrf_token() {
+            return Ok(token);
+        }
+        let fetched = Box::pin(self.send_once::<CsrfToken>(Method::GET, "/csrf", &[], None)).await?;
+        Ok(fetched.token)
+    }
+
+    async fn send_once<T: DeserializeOwned>(&self, method: Method, path: &str, query: &[(&str, String)], body: Option<String>) -> Result<T, ApiError> {
+        let mut builder = RequestBuilder::new(&self.url(path))
+            .method(method.clone())
+            .credentials(self.credentials)
+            .query(query.iter().map(|(key, value)| (*key, value.as_str())));
+        if !is_safe(&method) {
+            builder = builder.header(CSRF_HEADER, &self.ensure_csrf_token().await?);
+        }
+        let request = match body {
+            Some(body) => builder.header("Content-Type", "application/json").body(body),
+            None => builder.build(),
+        }
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST.as_u16(), e.to_string()))?;
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ApiError::new(StatusCode::SERVICE_UNAVAILABLE.as_u16(), e.to_string()))?;
+        remember_csrf_token(&response);
+        if response.ok() {
+            return response
+                .json::<T>()
+                .await
+                .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR.as_u16(), e.to_string()));
+        }
+        let status = response.status();
+        match response.json::<ApiError>().await {
+            Ok(error) => Err(error),
+            Err(_) => Err(ApiError::new(status, response.status_text())),
+        }
+    }
+}
+
+fn encode_body<B: Serialize>(body: Option<&B>) -> Result<Option<String>, ApiError> {
+    body.map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST.as_u16(), e.to_string()))
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE)
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS)
+}
+
+/// Failures worth retrying: the server was unreachable, overloaded or restarting.
+fn is_transient(status: u16) -> bool {
+    matches!(StatusCode::from_u16(status), Ok(StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT))
+}
@@ -0,0 +1,256 @@
+#[cfg(feature = "server")]
+pub mod Admin {
+    use axum::{extract::{Path, Query}, http::{header, StatusCode}, response::IntoResponse, Json};
+    use bson::oid::ObjectId;
+    use chrono::Utc;
+    use serde::Deserialize;
+    use tower_sessions::Session;
+    use validator::Validate;
+
+    use crate::backend::{audit::Audit::{self, RequestMeta}, auths::{auth_session::AuthSession, guards::Guards}, forms::{AccountStatus, ApiError, AuditAction, AuditPage, AuditQuery, BanForm, ChangeRoleForm, RecoverPhoneNumberForm, SuspendForm, StorefrontStatus, StorefrontSummary, UserDetail, UserSummary, UsersPage, UsersQuery, Forms::UserRole}, mongo_crud::MongoRepoError, bookings::Bookings, mongo_models::Docs::BaseUser, phone_change::PhoneChange::{self, PhoneChangeError}, storefronts::Storefronts, users::Users, AUDIT_LOG};
+
+    #[derive(Deserialize, utoipa::IntoParams)]
+    #[into_params(parameter_in = Query)]
+    pub struct StorefrontsQuery {
+        /// Defaults to `Pending`.
+        status: Option<StorefrontStatus>,
+    }
+
+    fn parse_id(id: &str) -> Result<ObjectId, StatusCode> {
+        ObjectId::parse_str(id).map_err(|_| StatusCode::BAD_REQUEST)
+    }
+
+    fn repo_status(e: MongoRepoError) -> StatusCode {
+        match e {
+            MongoRepoError::NotFoundError(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Admins may not act on their own account, which would let them lock themselves out.
+    async fn require_other_user(session: &Session, id: &str) -> Result<(BaseUser, ObjectId), StatusCode> {
+        let admin = Guards::require_role(session, &[UserRole::Admin]).await?;
+        let id = parse_id(id)?;
+        if admin.id == Some(id) {
+            return Err(StatusCode::CONFLICT);
+        }
+        Ok((admin, id))
+    }
+
+    #[utoipa::path(get, path = "/users", tag = "admin", params(UsersQuery), responses(
+        (status = 200, description = "Page of members", body = UsersPage),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn list_users(session: Session, Query(query): Query<UsersQuery>) -> Result<Json<UsersPage>, StatusCode> {
+        Guards::require_role(&session, &[UserRole::Admin]).await?;
+        let (users, total) = Users::list_users(&query).await.map_err(repo_status)?;
+        Ok(Json(UsersPage {
+            users: users.iter().map(Users::to_summary).collect(),
+            total,
+            page: query.page.unwrap_or(0),
+            per_page: Users::page_size(&query),
+        }))
+    }
+
+    #[utoipa::path(get, path = "/users/{id}", tag = "admin", params(("id" = String, Path, description = "User id")), responses(
+        (status = 200, description = "Member with their sessions, storefronts and latest bookings", body = UserDetail),
+        (status = 404, description = "User not found", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn get_user(session: Session, Path(id): Path<String>) -> Result<Json<UserDetail>, StatusCode> {
+        Guards::require_role(&session, &[UserRole::Admin]).await?;
+        let id = parse_id(&id)?;
+        let user = Users::get_user(&id).await.map_err(repo_status)?;
+        let sessions = AuthSession::list_user_sessions(&id).await.map_err(repo_status)?;
+        let storefronts = Storefronts::list_by_owner(&id).await.map_err(repo_status)?;
+        let bookings = Bookings::list_by_member(&id).await.map_err(repo_status)?;
+        Ok(Json(UserDetail {
+            user: Users::to_summary(&user),
+            sessions,
+            storefronts: storefronts.iter().map(Storefronts::to_summary).collect(),
+            bookings: Bookings::summarize(&bookings).await.map_err(repo_status)?,
+        }))
+    }
+
+    #[utoipa::path(put, path = "/users/{id}/role", tag = "admin", params(("id" = String, Path, description = "User id")), request_body = ChangeRoleForm, responses(
+        (status = 200, description = "Role changed", body = UserSummary),
+        (status = 404, description = "User not found", body = ApiError),
+        (status = 409, description = "Administrators cannot act on their own account", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn change_role(session: Session, meta: RequestMeta, Path(id): Path<String>, Json(form): Json<ChangeRoleForm>) -> Result<Json<UserSummary>, StatusCode> {
+        let (admin, id) = require_other_user(&session, &id).await?;
+        let user = Users::set_role(&id, form.role.clone()).await.map_err(repo_status)?;
+        Audit::record(AuditAction::RoleChanged, admin.id, Some(id.to_hex()), &meta, Some(format!("Role set to {:?}", form.role))).await;
+        Ok(Json(Users::to_summary(&user)))
+    }
+
+    #[utoipa::path(post, path = "/users/{id}/suspend", tag = "admin", params(("id" = String, Path, description = "User id")), request_body = SuspendForm, responses(
+        (status = 200, description = "Member suspended and logged out", body = UserSummary),
+        (status = 400, description = "Missing reason or end date in the past", body = ApiError),
+        (status = 404, description = "User not found", body = ApiError),
+        (status = 409, description = "Administrators cannot act on their own account", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn suspend_user(session: Session, meta: RequestMeta, Path(id): Path<String>, Json(form): Json<SuspendForm>) -> Result<Json<UserSummary>, StatusCode> {
+        let (admin, id) = require_other_user(&session, &id).await?;
+        if form.reason.trim().is_empty() || form.until <= Utc::now() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let status = AccountStatus::Suspended { until: form.until, reason: form.reason.trim().to_string() };
+        let details = format!("Suspended until {}: {}", form.until.to_rfc3339(), form.reason.trim());
+        let user = block_user(&id, status).await?;
+        Audit::record(AuditAction::AccountSuspended, admin.id, Some(id.to_hex()), &meta, Some(details)).await;
+        Ok(user)
+    }
+
+    #[utoipa::path(post, path = "/users/{id}/ban", tag = "admin", params(("id" = String, Path, description = "User id")), request_body = BanForm, responses(
+        (status = 200, description = "Member banned and logged out", body = UserSummary),
+        (status = 400, description = "Missing reason", body = ApiError),
+        (status = 404, description = "User not found", body = ApiError),
+        (status = 409, description = "Administrators cannot act on their own account", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn ban_user(session: Session, meta: RequestMeta, Path(id): Path<String>, Json(form): Json<BanForm>) -> Result<Json<UserSummary>, StatusCode> {
+        let (admin, id) = require_other_user(&session, &id).await?;
+        if form.reason.trim().is_empty() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let user = block_user(&id, AccountStatus::Banned { reason: form.reason.trim().to_string() }).await?;
+        Audit::record(AuditAction::AccountBanned, admin.id, Some(id.to_hex()), &meta, Some(form.reason.trim().to_string())).await;
+        Ok(user)
+    }
+
+    async fn block_user(id: &ObjectId, status: AccountStatus) -> Result<Json<UserSummary>, StatusCode> {
+        let user = Users::set_status(id, status).await.map_err(repo_status)?;
+        AuthSession::revoke_user_sessions(id).await.map_err(repo_status)?;
+        Ok(Json(Users::to_summary(&user)))
+    }
+
+    #[utoipa::path(post, path = "/users/{id}/reactivate", tag = "admin", params(("id" = String, Path, description = "User id")), responses(
+        (status = 200, description = "Suspension or ban lifted", body = UserSummary),
+        (status = 404, description = "User not found", body = ApiError),
+        (status = 409, description = "Administrators cannot act on their own account", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn reactivate_user(session: Session, meta: RequestMeta, Path(id): Path<String>) -> Result<Json<UserSummary>, StatusCode> {
+        let (admin, id) = require_other_user(&session, &id).await?;
+        let user = Users::set_status(&id, AccountStatus::Active).await.map_err(repo_status)?;
+        Audit::record(AuditAction::AccountReactivated, admin.id, Some(id.to_hex()), &meta, None).await;
+        Ok(Json(Users::to_summary(&user)))
+    }
+
+    #[utoipa::path(post, path = "/users/{id}/phone-number", tag = "admin", params(("id" = String, Path, description = "User id")), request_body = RecoverPhoneNumberForm, responses(
+        (status = 200, description = "Phone number replaced and the member logged out everywhere", body = UserSummary),
+        (status = 208, description = "Phone number already registered", body = ApiError),
+        (status = 400, description = "Invalid phone number or missing reason", body = ApiError),
+        (status = 404, description = "User not found", body = ApiError),
+        (status = 409, description = "Administrators cannot act on their own account", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn recover_phone_number(session: Session, meta: RequestMeta, Path(id): Path<String>, Json(form): Json<RecoverPhoneNumberForm>) -> Result<Json<UserSummary>, StatusCode> {
+        let (admin, id) = require_other_user(&session, &id).await?;
+        if form.validate().is_err() || form.reason.trim().is_empty() {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let user = Users::get_user(&id).await.map_err(repo_status)?;
+        let previous = PhoneChange::current_phone_number(&user).map_err(repo_status)?;
+        let user = PhoneChange::apply(&user, &form.phone_number).await.map_err(|e| match e {
+            PhoneChangeError::NumberTaken => StatusCode::ALREADY_REPORTED,
+            PhoneChangeError::Repo(e) => repo_status(e),
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+        AuthSession::revoke_user_sessions(&id).await.map_err(repo_status)?;
+        let details = format!("{} -> {}: {}", Audit::mask_phone_number(&previous), Audit::mask_phone_number(&form.phone_number), form.reason.trim());
+        Audit::record(AuditAction::PhoneNumberRecovered, admin.id, Some(id.to_hex()), &meta, Some(details)).await;
+        Ok(Json(Users::to_summary(&user)))
+    }
+
+    #[utoipa::path(get, path = "/storefronts", tag = "admin", params(StorefrontsQuery), responses(
+        (status = 200, description = "Storefronts with the requested status, oldest first", body = Vec<StorefrontSummary>),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn list_storefronts(session: Session, Query(query): Query<StorefrontsQuery>) -> Result<Json<Vec<StorefrontSummary>>, StatusCode> {
+        Guards::require_role(&session, &[UserRole::Admin]).await?;
+        let storefronts = Storefronts::list_by_status(query.status.unwrap_or(StorefrontStatus::Pending)).await.map_err(repo_status)?;
+        Ok(Json(storefronts.iter().map(Storefronts::to_summary).collect()))
+    }
+
+    #[utoipa::path(post, path = "/storefronts/{id}/approve", tag = "admin", params(("id" = String, Path, description = "Storefront id")), responses(
+        (status = 200, description = "Storefront approved", body = StorefrontSummary),
+        (status = 404, description = "Storefront not found", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn approve_storefront(session: Session, meta: RequestMeta, Path(id): Path<String>) -> Result<Json<StorefrontSummary>, StatusCode> {
+        review_storefront(session, meta, id, StorefrontStatus::Approved, AuditAction::StorefrontApproved).await
+    }
+
+    #[utoipa::path(post, path = "/storefronts/{id}/reject", tag = "admin", params(("id" = String, Path, description = "Storefront id")), responses(
+        (status = 200, description = "Storefront rejected", body = StorefrontSummary),
+        (status = 404, description = "Storefront not found", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn reject_storefront(session: Session, meta: RequestMeta, Path(id): Path<String>) -> Result<Json<StorefrontSummary>, StatusCode> {
+        review_storefront(session, meta, id, StorefrontStatus::Rejected, AuditAction::StorefrontRejected).await
+    }
+
+    async fn review_storefront(session: Session, meta: RequestMeta, id: String, status: StorefrontStatus, action: AuditAction) -> Result<Json<StorefrontSummary>, StatusCode> {
+        let admin = Guards::require_role(&session, &[UserRole::Admin]).await?;
+        let storefront = Storefronts::set_status(&parse_id(&id)?, status).await.map_err(repo_status)?;
+        Audit::record(action, admin.id, Some(id), &meta, Some(storefront.name.clone())).await;
+        Ok(Json(Storefronts::to_summary(&storefront)))
+    }
+
+    fn validate_audit_query(query: &AuditQuery) -> Result<(), StatusCode> {
+        match query.actor.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+            Some(actor) => parse_id(actor).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    #[utoipa::path(get, path = "/audit", tag = "admin", params(AuditQuery), responses(
+        (status = 200, description = "Page of audit entries, newest first", body = AuditPage),
+        (status = 400, description = "Invalid actor id", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn list_audit_log(session: Session, Query(query): Query<AuditQuery>) -> Result<Json<AuditPage>, StatusCode> {
+        Guards::require_role(&session, &[UserRole::Admin]).await?;
+        validate_audit_query(&query)?;
+        let (entries, total) = Audit::list(&query).await.map_err(repo_status)?;
+        Ok(Json(AuditPage {
+            entries: entries.iter().map(Audit::to_summary).collect(),
+            total,
+            page: query.page.unwrap_or(0),
+            per_page: Audit::page_size(&query),
+        }))
+    }
+
+    #[utoipa::path(get, path = "/audit/export", tag = "admin", params(AuditQuery), responses(
+        (status = 200, description = "Matching audit entries as a CSV attachment", body = String, content_type = "text/csv"),
+        (status = 400, description = "Invalid actor id", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Not an administrator", body = ApiError),
+    ))]
+    pub async fn export_audit_log(session: Session, meta: RequestMeta, Query(query): Query<AuditQuery>) -> Result<axum::response::Response, StatusCode> {
+        let admin = Guards::require_role(&session, &[UserRole::Admin]).await?;
+        validate_audit_query(&query)?;
+        let csv = Audit::export_csv(&query).await.map_err(repo_status)?;
+        Audit::record(AuditAction::DataExported, admin.id, Some(AUDIT_LOG.to_string()), &meta, serde_json::to_string(&query).ok()).await;
+        let filename = format!("attachment; filename=\"audit-log-{}.csv\"", Utc::now().format("%Y%m%d%H%M%S"));
+        Ok((
+            [(header::CONTENT_TYPE, String::from("text/csv; charset=utf-8")), (header::CONTENT_DISPOSITION, filename)],
+            csv,
+        ).into_response())
+    }
+}
@@ -1,59 +1,214 @@
-const CURRENT_USER_KEY:&str = "current_user";
-const JWT_TOKEN:&str = "jwt_token";
+pub const CURRENT_USER_KEY:&str = "current_user";
+pub const JWT_TOKEN:&str = "jwt_token";
+pub const PENDING_LOGIN_KEY:&str = "pending_login";
+pub const PASSKEY_REGISTRATION_KEY:&str = "passkey_registration";
+pub const PASSKEY_AUTHENTICATION_KEY:&str = "passkey_authentication";
+pub const PENDING_PHONE_CHANGE_KEY:&str = "pending_phone_change";
 
 
 
 #[cfg(feature = "server")]
 pub mod Api {
+    use bson::oid::ObjectId;
+    use serde::{Deserialize, Serialize};
     use validator::Validate;
     use axum::{http::StatusCode, response::IntoResponse};
-    
-
-    use crate::backend::{api::{api::{CURRENT_USER_KEY, JWT_TOKEN}, jwt}, forms::{Forms::AuthUserForm, Token}, mongo_crud::MongoRepoError, mongo_models::{self, Docs::BaseUser}, users};
-
-    pub async fn register_user(session: tower_sessions::Session, axum::extract::Json(payload): axum::extract::Json<AuthUserForm>) -> Result<axum::response::Response, StatusCode> {
-        match payload.validate() {
-            Ok(_) => {
-                let optional = users::Users::register_user(payload).await;
-                match optional {
-                    Ok(user) => Ok(axum::Json(user).into_response()),
-                    Err(e) => match e {
-                        MongoRepoError::PasswordExistsError(_) => Err(StatusCode::ALREADY_REPORTED),
-                        _ => Err(StatusCode::INTERNAL_SERVER_ERROR), 
-                    }
-                }
+    use tower_sessions::Session;
+
+    use crate::backend::{api::{api::{CURRENT_USER_KEY, JWT_TOKEN}, jwt, two_factor::TwoFactorApi}, audit::Audit::{self, RequestMeta}, auths::{auth_session::AuthSession, guards::Guards}, forms::{ApiError, AuditAction, AuthError, CsrfToken, Forms::{AuthUserForm, UserRole}, Token, TwoFactorStep, UserView}, mongo_crud::MongoRepoError, mongo_models::Docs::BaseUser, monitoring::Monitoring, telemetry::Telemetry, two_factor::TwoFactor, users};
+
+    /// What the session keeps about the logged in member under `CURRENT_USER_KEY`. Guards reload the member
+    /// from the database on each request, so the id is all they need; field names follow `BaseUser` so that
+    /// sessions created while the whole document was stored still load.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct SessionUser {
+        #[serde(rename = "_id")]
+        pub id: ObjectId,
+        pub role: UserRole,
+    }
+
+    impl SessionUser {
+        pub fn new(user: &BaseUser) -> Option<SessionUser> {
+            Some(SessionUser { id: user.id?, role: user.role.clone() })
+        }
+    }
+
+    pub fn auth_error_response(error: AuthError) -> axum::response::Response {
+        match error {
+            AuthError::InvalidForm => StatusCode::BAD_REQUEST.into_response(),
+            AuthError::AlreadyRegistered => StatusCode::ALREADY_REPORTED.into_response(),
+            AuthError::NotFound => StatusCode::NOT_FOUND.into_response(),
+            AuthError::Unauthenticated => StatusCode::UNAUTHORIZED.into_response(),
+            AuthError::AccountInactive(status) => Guards::account_inactive_response(status),
+            AuthError::TwoFactorRequired(step) => {
+                let error = ApiError { two_factor: Some(step), ..ApiError::new(StatusCode::UNAUTHORIZED.as_u16(), "Two-factor authentication required") };
+                (StatusCode::UNAUTHORIZED, axum::Json(error)).into_response()
+            },
+            AuthError::InvalidTwoFactorCode => {
+                let error = ApiError::new(StatusCode::UNAUTHORIZED.as_u16(), "Invalid two-factor code");
+                (StatusCode::UNAUTHORIZED, axum::Json(error)).into_response()
+            },
+            AuthError::TwoFactorLocked => {
+                let error = ApiError::new(StatusCode::TOO_MANY_REQUESTS.as_u16(), "Too many invalid two-factor codes, try again later");
+                (StatusCode::TOO_MANY_REQUESTS, axum::Json(error)).into_response()
+            },
+            AuthError::Forbidden => StatusCode::FORBIDDEN.into_response(),
+            AuthError::TwoFactorAlreadyEnabled => StatusCode::CONFLICT.into_response(),
+            AuthError::InvalidPasskey => {
+                let error = ApiError::new(StatusCode::UNAUTHORIZED.as_u16(), "Passkey not accepted");
+                (StatusCode::UNAUTHORIZED, axum::Json(error)).into_response()
             },
-            Err(err) => Err(StatusCode::BAD_REQUEST) 
-        }
-    }
-
-    pub async fn login_user(session: tower_sessions::Session, axum::extract::Json(payload): axum::extract::Json<AuthUserForm>) -> Result<axum::Json<BaseUser>, StatusCode> {
-        match payload.validate() {
-            Ok(_) => {
-                let user = users::Users::login_user(payload).await;
-                match user {
-                    Ok(user) => {
-                        let token = jwt::Jwt::get_jwt(user.password.clone())
-                        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-                        session.insert(CURRENT_USER_KEY, user.clone()).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-                        session.insert(JWT_TOKEN, token).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-                        Ok(axum::Json(user))
-                    },
-                    Err(e) => match e {
-                        MongoRepoError::NotFoundError(_) => Err(StatusCode::NOT_FOUND), 
-                        _ => Err(StatusCode::INTERNAL_SERVER_ERROR),
-                    }
+            AuthError::TooManyPasskeys => StatusCode::CONFLICT.into_response(),
+            AuthError::InvalidVerificationCode => {
+                let error = ApiError::new(StatusCode::UNAUTHORIZED.as_u16(), "Invalid verification code");
+                (StatusCode::UNAUTHORIZED, axum::Json(error)).into_response()
+            },
+            AuthError::TooManyPhoneChanges => {
+                let error = ApiError::new(StatusCode::TOO_MANY_REQUESTS.as_u16(), "Too many phone number changes, try again later");
+                (StatusCode::TOO_MANY_REQUESTS, axum::Json(error)).into_response()
+            },
+            AuthError::Internal => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+
+    /// `AuthError` for a status returned by `Guards`.
+    pub fn guard_error(status: StatusCode) -> AuthError {
+        match status {
+            StatusCode::UNAUTHORIZED => AuthError::Unauthenticated,
+            StatusCode::FORBIDDEN => AuthError::Forbidden,
+            _ => AuthError::Internal,
+        }
+    }
+
+    /// Middleware giving bare error statuses an `ApiError` JSON body, so clients decode every failure the same way.
+    pub async fn json_errors(request: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+        let response = next.run(request).await;
+        let status = response.status();
+        let is_json = response.headers().get(axum::http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/json"));
+        if !(status.is_client_error() || status.is_server_error()) || is_json {
+            return response;
+        }
+        let error = ApiError::new(status.as_u16(), status.canonical_reason().unwrap_or("Request failed"));
+        (status, axum::Json(error)).into_response()
+    }
+
+    pub async fn register(meta: &RequestMeta, payload: AuthUserForm) -> Result<BaseUser, AuthError> {
+        payload.validate().map_err(|_| AuthError::InvalidForm)?;
+        match users::Users::register_user(payload).await {
+            Ok(user) => {
+                Audit::record(AuditAction::Registration, user.id, user.id.map(|id| id.to_hex()), meta, None).await;
+                Ok(user)
+            },
+            Err(MongoRepoError::PasswordExistsError(_)) => Err(AuthError::AlreadyRegistered),
+            Err(_) => Err(AuthError::Internal),
+        }
+    }
+
+    /// Logs in with the phone number. Members with two-factor authentication get
+    /// `AuthError::TwoFactorRequired(TwoFactorStep::Verify)` instead of a session and complete the login through
+    /// `TwoFactorApi`. Those whose role requires it but who have not set it up are refused, see
+    /// `TwoFactorApi::hold_login`.
+    pub async fn login(session: &Session, meta: &RequestMeta, payload: AuthUserForm) -> Result<BaseUser, AuthError> {
+        let result = attempt_login(session, meta, payload).await;
+        if !matches!(result, Err(AuthError::TwoFactorRequired(TwoFactorStep::Verify))) {
+            Monitoring::record_login(result.is_ok());
+        }
+        result
+    }
+
+    /// Creates the session of `user`, whose credentials have all been checked.
+    pub async fn start_session(session: &Session, meta: &RequestMeta, user: &BaseUser, details: Option<String>) -> Result<(), AuthError> {
+        let session_user = SessionUser::new(user).ok_or(AuthError::Internal)?;
+        let token = jwt::Jwt::get_jwt(&session_user.id).map_err(|_| AuthError::Internal)?;
+        Telemetry::record_user(&session_user.id);
+        session.insert(CURRENT_USER_KEY, session_user.clone()).await.map_err(|_| AuthError::Internal)?;
+        session.insert(JWT_TOKEN, token).await.map_err(|_| AuthError::Internal)?;
+        AuthSession::record_user_session(session, session_user.id).await.map_err(|_| AuthError::Internal)?;
+        Audit::record(AuditAction::Login, user.id, user.id.map(|id| id.to_hex()), meta, details).await;
+        Ok(())
+    }
+
+    async fn attempt_login(session: &Session, meta: &RequestMeta, payload: AuthUserForm) -> Result<BaseUser, AuthError> {
+        payload.validate().map_err(|_| AuthError::InvalidForm)?;
+        let masked_phone_number = Audit::mask_phone_number(&payload.get_password());
+        match users::Users::login_user(payload).await {
+            Ok(user) => {
+                if let Some(step) = TwoFactor::login_step(&user).await.map_err(|_| AuthError::Internal)? {
+                    return Err(TwoFactorApi::hold_login(session, meta, &user, step).await);
                 }
+                start_session(session, meta, &user, None).await?;
+                Ok(user)
             },
-            Err(err) => Err(StatusCode::BAD_REQUEST)
+            Err(MongoRepoError::NotFoundError(_)) => {
+                Audit::record(AuditAction::LoginFailed, None, Some(masked_phone_number), meta, Some(String::from("Unknown phone number"))).await;
+                Err(AuthError::NotFound)
+            },
+            Err(MongoRepoError::AccountInactiveError(status)) => {
+                Audit::record(AuditAction::LoginFailed, None, Some(masked_phone_number), meta, Some(format!("Account not active: {:?}", status))).await;
+                Err(AuthError::AccountInactive(status))
+            },
+            Err(_) => Err(AuthError::Internal),
         }
     }
 
-    pub async fn is_authenticated(session: tower_sessions::Session) -> Result<axum::Json<Token>, StatusCode> {
-        let token = session.get::<String>(JWT_TOKEN).await.unwrap();
-        match token {
-             Some(value) => Ok(axum::Json(Token::new(value))),
-            _ => Err(StatusCode::UNAUTHORIZED)
+    /// Token of the logged in member. A member suspended or banned since logging in has their session ended.
+    pub async fn authenticated_token(session: &Session) -> Result<Token, AuthError> {
+        let token = session.get::<String>(JWT_TOKEN).await
+            .map_err(|_| AuthError::Internal)?
+            .ok_or(AuthError::Unauthenticated)?;
+        let user = session.get::<SessionUser>(CURRENT_USER_KEY).await.map_err(|_| AuthError::Internal)?;
+        if let Some(SessionUser { id, .. }) = user {
+            Telemetry::record_user(&id);
+            match users::Users::get_user(&id).await {
+                Ok(user) if !user.status.is_active() => {
+                    session.flush().await.map_err(|_| AuthError::Internal)?;
+                    return Err(AuthError::AccountInactive(user.status));
+                },
+                Ok(_) => {},
+                Err(MongoRepoError::NotFoundError(_)) => return Err(AuthError::Unauthenticated),
+                Err(_) => return Err(AuthError::Internal),
+            }
         }
+        Ok(Token::new(token))
+    }
+
+    #[utoipa::path(post, path = "/register", tag = "auth", request_body = AuthUserForm, responses(
+        (status = 200, description = "Member registered", body = UserView),
+        (status = 208, description = "Phone number already registered", body = ApiError),
+        (status = 400, description = "Invalid form", body = ApiError),
+    ))]
+    pub async fn register_user(meta: RequestMeta, axum::extract::Json(payload): axum::extract::Json<AuthUserForm>) -> Result<axum::Json<UserView>, axum::response::Response> {
+        register(&meta, payload).await.map(|user| axum::Json(users::Users::to_view(&user))).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/login", tag = "auth", request_body = AuthUserForm, responses(
+        (status = 200, description = "Logged in, session cookie set", body = UserView),
+        (status = 400, description = "Invalid form", body = ApiError),
+        (status = 401, description = "`two_factor` is `Verify`: phone number accepted, continue with `/login/two-factor`. \
+            `Enroll`: refused, as an admin must set up two-factor authentication first", body = ApiError),
+        (status = 403, description = "Account suspended or banned", body = ApiError),
+        (status = 404, description = "Unknown phone number", body = ApiError),
+    ))]
+    pub async fn login_user(session: Session, meta: RequestMeta, axum::extract::Json(payload): axum::extract::Json<AuthUserForm>) -> Result<axum::Json<UserView>, axum::response::Response> {
+        login(&session, &meta, payload).await.map(|user| axum::Json(users::Users::to_view(&user))).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(get, path = "/is_authenticated", tag = "auth", responses(
+        (status = 200, description = "Token of the logged in member", body = Token),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Account suspended or banned", body = ApiError),
+    ))]
+    pub async fn is_authenticated(session: Session) -> Result<axum::Json<Token>, axum::response::Response> {
+        authenticated_token(&session).await.map(axum::Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(get, path = "/csrf", tag = "auth", responses(
+        (status = 200, description = "Token to send in the `X-CSRF-Token` header of POST, PUT, PATCH and DELETE requests. \
+            Also returned in that response header by every call, and set as the `csrf_token` cookie for the app's own scripts.", body = CsrfToken),
+    ))]
+    pub async fn csrf_token(axum::Extension(token): axum::Extension<CsrfToken>) -> axum::Json<CsrfToken> {
+        axum::Json(token)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,113 @@
+use dioxus::prelude::*;
+use webauthn_rs_proto::{CreationChallengeResponse, RequestChallengeResponse};
+
+use crate::{api_client::CsrfClient, backend::forms::{AuthError, AuthUserForm, ConfirmPhoneChangeForm, NewPasskeyForm, PasskeyLoginForm, PasskeySummary, PhoneChangeChallenge, PhoneChangeForm, RecoveryCodes, Token, TwoFactorCodeForm, TwoFactorEnrollment, UserView}};
+
+#[cfg(feature = "server")]
+use crate::backend::{api::{api::Api, passkeys::PasskeysApi, phone_change::PhoneChangeApi, two_factor::TwoFactorApi}, audit::Audit::RequestMeta, users::Users};
+
+#[server(endpoint = "auth/register", client = CsrfClient)]
+pub async fn register(form: AuthUserForm) -> Result<UserView, ServerFnError<AuthError>> {
+    let meta: RequestMeta = extract().await.map_err(|_| AuthError::Internal)?;
+    let user = Api::register(&meta, form).await?;
+    Ok(Users::to_view(&user))
+}
+
+#[server(endpoint = "auth/login", client = CsrfClient)]
+pub async fn login(form: AuthUserForm) -> Result<UserView, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    let meta: RequestMeta = extract().await.map_err(|_| AuthError::Internal)?;
+    let user = Api::login(&session, &meta, form).await?;
+    Ok(Users::to_view(&user))
+}
+
+#[server(endpoint = "auth/is_authenticated", client = CsrfClient)]
+pub async fn is_authenticated() -> Result<Token, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(Api::authenticated_token(&session).await?)
+}
+
+/// Second step of a login that answered `AuthError::TwoFactorRequired(TwoFactorStep::Verify)`.
+#[server(endpoint = "auth/verify_two_factor", client = CsrfClient)]
+pub async fn verify_two_factor(form: TwoFactorCodeForm) -> Result<UserView, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    let meta: RequestMeta = extract().await.map_err(|_| AuthError::Internal)?;
+    let user = TwoFactorApi::verify_login(&session, &meta, form).await?;
+    Ok(Users::to_view(&user))
+}
+
+#[server(endpoint = "auth/enroll_two_factor", client = CsrfClient)]
+pub async fn enroll_two_factor() -> Result<TwoFactorEnrollment, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(TwoFactorApi::enroll(&session).await?)
+}
+
+#[server(endpoint = "auth/confirm_two_factor", client = CsrfClient)]
+pub async fn confirm_two_factor(form: TwoFactorCodeForm) -> Result<RecoveryCodes, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    let meta: RequestMeta = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(TwoFactorApi::confirm(&session, &meta, form).await?)
+}
+
+/// Options for `navigator.credentials.get()`, to be answered through `finish_passkey_login`.
+#[server(endpoint = "auth/start_passkey_login", client = CsrfClient)]
+pub async fn start_passkey_login() -> Result<RequestChallengeResponse, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(PasskeysApi::start_login(&session).await?)
+}
+
+#[server(endpoint = "auth/finish_passkey_login", client = CsrfClient)]
+pub async fn finish_passkey_login(form: PasskeyLoginForm) -> Result<UserView, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    let meta: RequestMeta = extract().await.map_err(|_| AuthError::Internal)?;
+    let user = PasskeysApi::finish_login(&session, &meta, form).await?;
+    Ok(Users::to_view(&user))
+}
+
+/// Options for `navigator.credentials.create()`, to be answered through `finish_passkey_registration`.
+#[server(endpoint = "auth/start_passkey_registration", client = CsrfClient)]
+pub async fn start_passkey_registration() -> Result<CreationChallengeResponse, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(PasskeysApi::start_registration(&session).await?)
+}
+
+#[server(endpoint = "auth/finish_passkey_registration", client = CsrfClient)]
+pub async fn finish_passkey_registration(form: NewPasskeyForm) -> Result<PasskeySummary, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    let meta: RequestMeta = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(PasskeysApi::finish_registration(&session, &meta, form).await?)
+}
+
+#[server(endpoint = "auth/list_passkeys", client = CsrfClient)]
+pub async fn list_passkeys() -> Result<Vec<PasskeySummary>, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(PasskeysApi::list(&session).await?)
+}
+
+#[server(endpoint = "auth/remove_passkey", client = CsrfClient)]
+pub async fn remove_passkey(id: String) -> Result<(), ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    let meta: RequestMeta = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(PasskeysApi::remove(&session, &meta, &id).await?)
+}
+
+/// Sends codes to `form.phone_number` and to the current number, to be entered through `confirm_phone_change`.
+#[server(endpoint = "auth/start_phone_change", client = CsrfClient)]
+pub async fn start_phone_change(form: PhoneChangeForm) -> Result<PhoneChangeChallenge, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(PhoneChangeApi::start(&session, form).await?)
+}
+
+/// Whether the logged in member could move to `form.phone_number`, so the form can tell before codes are sent.
+#[server(endpoint = "auth/phone_number_available", client = CsrfClient)]
+pub async fn phone_number_available(form: PhoneChangeForm) -> Result<bool, ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(PhoneChangeApi::is_available(&session, form).await?)
+}
+
+#[server(endpoint = "auth/confirm_phone_change", client = CsrfClient)]
+pub async fn confirm_phone_change(form: ConfirmPhoneChangeForm) -> Result<(), ServerFnError<AuthError>> {
+    let session: tower_sessions::Session = extract().await.map_err(|_| AuthError::Internal)?;
+    let meta: RequestMeta = extract().await.map_err(|_| AuthError::Internal)?;
+    Ok(PhoneChangeApi::confirm(&session, &meta, form).await?)
+}
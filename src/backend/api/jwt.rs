@@ -2,25 +2,23 @@
 
 #[cfg(feature = "server")]
 pub mod Jwt {
+    use bson::oid::ObjectId;
     use chrono::{Duration, Utc};
     use dioxus::prelude::Context;
     use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
     use serde::{Deserialize, Serialize};
     use dotenvy::dotenv;
 
-    #[derive(Serialize, Deserialize)]
-    struct  User {
-        password: String,
+    /// Claims of the session token. It leaves the server, so it only names the member by id and never carries
+    /// their stored credential.
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct Claims {
+        /// Hex id of the member.
+        pub sub: String,
+        pub exp: i64,
     }
 
-
-    #[derive(Serialize, Deserialize)]
-    struct Claims {
-        password: String,
-        exp: i64,
-    }
-
-    pub fn get_jwt(password: String) -> Result<String, String> {
+    pub fn get_jwt(user_id: &ObjectId) -> Result<String, String> {
         dotenv().ok();
         let secret = std::env::var("JWT_PASSCODE")
             .context("JWT_PASSCODE is not found.")
@@ -28,7 +26,7 @@ pub mod Jwt {
         let token = encode(
             &Header::default(),
             &Claims {
-                password,
+                sub: user_id.to_hex(),
                 exp: (Utc::now() + Duration::minutes(1)).timestamp(),
             },
             &EncodingKey::from_secret(secret.as_str().as_bytes()),
@@ -37,11 +35,11 @@ pub mod Jwt {
         token
     }
 
-    pub fn decode_jwt(token: &str) -> Result<User, String> {
+    pub fn decode_jwt(token: &str) -> Result<Claims, String> {
         let secret = std::env::var("JWT_PASSCODE")
             .context("JWT_PASSCODE is not found.")
             .map_err(|e| e.to_string())?;
-        let token_data = decode::<User>(
+        let token_data = decode::<Claims>(
             token,
             &DecodingKey::from_secret(secret.as_str().as_bytes()),
             &Validation::default(),
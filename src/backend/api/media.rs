@@ -0,0 +1,104 @@
+#[cfg(feature = "server")]
+pub mod Media {
+    use axum::{extract::{Multipart, Path}, http::{header, StatusCode}, response::IntoResponse};
+    use bson::oid::ObjectId;
+    use chrono::Utc;
+
+    use crate::backend::{auths::guards::Guards, forms::ApiError, media::{blob_store::Blobs::{content_type_for, BlobStore, LocalBlobStore}, images::Images::{self, ImageError}}, mongo_crud::MongoRepo, mongo_models::Docs::{ImageKind, MediaDoc, MediaVariant}, MEDIA};
+
+    const UPLOAD_FIELD: &str = "file";
+    const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+    pub fn kind_prefix(kind: ImageKind) -> &'static str {
+        match kind {
+            ImageKind::Listing => "listing",
+            ImageKind::Portfolio => "portfolio",
+            ImageKind::Avatar => "avatar",
+        }
+    }
+
+    async fn read_upload(mut multipart: Multipart) -> Result<Vec<u8>, StatusCode> {
+        while let Some(field) = multipart.next_field().await.map_err(|_| StatusCode::BAD_REQUEST)? {
+            if field.name() == Some(UPLOAD_FIELD) {
+                let bytes = field.bytes().await.map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+                return Ok(bytes.to_vec());
+            }
+        }
+        Err(StatusCode::BAD_REQUEST)
+    }
+
+    /// Multipart body of an image upload.
+    #[derive(utoipa::ToSchema)]
+    pub struct ImageUpload {
+        #[schema(value_type = String, format = Binary)]
+        pub file: Vec<u8>,
+    }
+
+    #[utoipa::path(post, path = "/media/{kind}", tag = "media",
+        params(("kind" = ImageKind, Path, description = "What the image is used for; sets the size limit and variants")),
+        request_body(content = ImageUpload, content_type = "multipart/form-data"),
+        responses(
+            (status = 200, description = "Image stored with its resized variants", body = MediaDoc),
+            (status = 400, description = "Missing `file` field", body = ApiError),
+            (status = 401, description = "Not logged in", body = ApiError),
+            (status = 413, description = "Image too large", body = ApiError),
+            (status = 415, description = "Not a JPEG, PNG or WebP image", body = ApiError),
+            (status = 422, description = "Image could not be decoded", body = ApiError),
+        ))]
+    pub async fn upload_image(session: tower_sessions::Session, Path(kind): Path<ImageKind>, multipart: Multipart) -> Result<axum::Json<MediaDoc>, StatusCode> {
+        let user = Guards::current_user(&session).await?;
+        let owner = user.id.ok_or(StatusCode::UNAUTHORIZED)?;
+
+        let bytes = read_upload(multipart).await?;
+        let variants = tokio::task::spawn_blocking(move || Images::process(kind, &bytes))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map_err(|e| match e {
+                ImageError::TooLargeError(_) => StatusCode::PAYLOAD_TOO_LARGE,
+                ImageError::UnsupportedTypeError => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ImageError::DecodeError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                ImageError::EncodeError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            })?;
+
+        let store = LocalBlobStore::from_env();
+        let media_id = ObjectId::new();
+        let mut stored = Vec::with_capacity(variants.len());
+        for variant in variants {
+            let key = format!("{}/{}/{}.{}", kind_prefix(kind), media_id.to_hex(), variant.name, variant.extension);
+            store.put(&key, &variant.bytes).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            stored.push(MediaVariant {
+                name: variant.name,
+                key,
+                content_type: variant.content_type.to_string(),
+                width: variant.width,
+                height: variant.height,
+                size: variant.bytes.len() as u64,
+            });
+        }
+
+        let doc = MediaDoc {
+            id: Some(media_id),
+            owner,
+            kind,
+            variants: stored,
+            created: Utc::now(),
+        };
+        let repo = MongoRepo::<MediaDoc>::new("sample_mflix", MEDIA).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if repo.create(doc.clone()).await.is_err() {
+            for variant in &doc.variants {
+                let _ = store.delete(&variant.key).await;
+            }
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Ok(axum::Json(doc))
+    }
+
+    pub async fn serve_blob(Path(key): Path<String>) -> Result<axum::response::Response, StatusCode> {
+        let store = LocalBlobStore::from_env();
+        let bytes = store.get(&key).await.map_err(|_| StatusCode::NOT_FOUND)?.ok_or(StatusCode::NOT_FOUND)?;
+        Ok((
+            [(header::CONTENT_TYPE, content_type_for(&key)), (header::CACHE_CONTROL, CACHE_CONTROL)],
+            bytes,
+        ).into_response())
+    }
+}
@@ -1,4 +1,10 @@
-
-
 pub mod api;
-pub mod jwt;
\ No newline at end of file
+pub mod auth;
+pub mod jwt;
+pub mod media;
+pub mod admin;
+pub mod storefronts;
+pub mod two_factor;
+pub mod passkeys;
+pub mod phone_change;
+pub mod routes;
\ No newline at end of file
@@ -0,0 +1,192 @@
+#[cfg(feature = "server")]
+pub mod PasskeysApi {
+    use axum::{extract::Path, http::StatusCode, response::Response, Json};
+    use bson::oid::ObjectId;
+    use chrono::{DateTime, Duration, Utc};
+    use serde::{de::DeserializeOwned, Deserialize, Serialize};
+    use tower_sessions::Session;
+    use validator::Validate;
+    use webauthn_rs::prelude::{CreationChallengeResponse, DiscoverableAuthentication, PasskeyRegistration, RequestChallengeResponse};
+
+    use crate::backend::{api::{api::{Api::{self, auth_error_response, guard_error}, PASSKEY_AUTHENTICATION_KEY, PASSKEY_REGISTRATION_KEY}, two_factor::TwoFactorApi}, audit::Audit::{self, RequestMeta}, auths::guards::Guards, forms::{ApiError, AuditAction, AuthError, NewPasskeyForm, PasskeyLoginForm, PasskeySummary, TwoFactorStep, UserView}, mongo_crud::MongoRepoError, mongo_models::Docs::BaseUser, monitoring::Monitoring, passkeys::Passkeys::{self, PasskeyError}, two_factor::TwoFactor, users::Users};
+
+    /// How long the browser may take to answer a challenge.
+    const CEREMONY_MINUTES: i64 = 5;
+
+    /// State of a registration or sign-in between its two requests, kept in the session so that each challenge
+    /// is answered once, by the browser it was sent to.
+    #[derive(Serialize, Deserialize)]
+    struct Ceremony<T> {
+        state: T,
+        started: DateTime<Utc>,
+    }
+
+    fn passkey_error(e: PasskeyError) -> AuthError {
+        match e {
+            PasskeyError::UnknownCredential | PasskeyError::AlreadyRegistered | PasskeyError::Rejected(_) => AuthError::InvalidPasskey,
+            PasskeyError::TooMany => AuthError::TooManyPasskeys,
+            PasskeyError::Config(_) | PasskeyError::Repo(_) => AuthError::Internal,
+        }
+    }
+
+    async fn begin_ceremony<T: Serialize>(session: &Session, key: &str, state: T) -> Result<(), AuthError> {
+        session.insert(key, Ceremony { state, started: Utc::now() }).await.map_err(|_| AuthError::Internal)
+    }
+
+    /// Takes the state of the ceremony under `key` out of the session, so that its challenge cannot be answered twice.
+    async fn take_ceremony<T: DeserializeOwned>(session: &Session, key: &str) -> Result<T, AuthError> {
+        let ceremony = session.remove::<Ceremony<T>>(key).await
+            .map_err(|_| AuthError::Internal)?
+            .ok_or(AuthError::InvalidPasskey)?;
+        if Utc::now() - ceremony.started > Duration::minutes(CEREMONY_MINUTES) {
+            return Err(AuthError::InvalidPasskey);
+        }
+        Ok(ceremony.state)
+    }
+
+    async fn current_user(session: &Session) -> Result<(BaseUser, ObjectId), AuthError> {
+        let user = Guards::current_user(session).await.map_err(guard_error)?;
+        let id = user.id.ok_or(AuthError::Internal)?;
+        Ok((user, id))
+    }
+
+    /// Challenge for registering a new passkey on the logged in member's account.
+    pub async fn start_registration(session: &Session) -> Result<CreationChallengeResponse, AuthError> {
+        let (user, id) = current_user(session).await?;
+        let existing = Passkeys::list(&id).await.map_err(|_| AuthError::Internal)?
+            .into_iter()
+            .map(|credential| credential.passkey)
+            .collect::<Vec<_>>();
+        let (challenge, state) = Passkeys::start_registration(Passkeys::webauthn().map_err(passkey_error)?, &user, &existing).map_err(passkey_error)?;
+        begin_ceremony(session, PASSKEY_REGISTRATION_KEY, state).await?;
+        Ok(challenge)
+    }
+
+    pub async fn finish_registration(session: &Session, meta: &RequestMeta, form: NewPasskeyForm) -> Result<PasskeySummary, AuthError> {
+        form.validate().map_err(|_| AuthError::InvalidForm)?;
+        let (_, id) = current_user(session).await?;
+        let state: PasskeyRegistration = take_ceremony(session, PASSKEY_REGISTRATION_KEY).await?;
+        let passkey = Passkeys::finish_registration(Passkeys::webauthn().map_err(passkey_error)?, &form.credential, &state).map_err(passkey_error)?;
+        let credential = Passkeys::save(&id, &form.name, passkey).await.map_err(passkey_error)?;
+        Audit::record(AuditAction::PasskeyAdded, Some(id), Some(id.to_hex()), meta, Some(credential.name.clone())).await;
+        Ok(Passkeys::to_summary(&credential))
+    }
+
+    /// Challenge for signing in with a passkey, any the browser holds for this site.
+    pub async fn start_login(session: &Session) -> Result<RequestChallengeResponse, AuthError> {
+        let (challenge, state) = Passkeys::start_authentication(Passkeys::webauthn().map_err(passkey_error)?).map_err(passkey_error)?;
+        begin_ceremony(session, PASSKEY_AUTHENTICATION_KEY, state).await?;
+        Ok(challenge)
+    }
+
+    /// Signs in with a passkey. Passkeys require user verification, so they stand for both factors; an admin
+    /// still without an authenticator app is refused with `AuthError::TwoFactorRequired(TwoFactorStep::Enroll)` as
+    /// with the phone number.
+    pub async fn finish_login(session: &Session, meta: &RequestMeta, form: PasskeyLoginForm) -> Result<BaseUser, AuthError> {
+        let result = attempt_finish_login(session, meta, form).await;
+        Monitoring::record_login(result.is_ok());
+        result
+    }
+
+    async fn attempt_finish_login(session: &Session, meta: &RequestMeta, form: PasskeyLoginForm) -> Result<BaseUser, AuthError> {
+        let state: DiscoverableAuthentication = take_ceremony(session, PASSKEY_AUTHENTICATION_KEY).await?;
+        let credential = match Passkeys::find_for(&form.credential).await {
+            Ok(credential) => credential,
+            Err(e) => {
+                Audit::record(AuditAction::LoginFailed, None, None, meta, Some(String::from("Unknown passkey"))).await;
+                return Err(passkey_error(e));
+            },
+        };
+        let webauthn = Passkeys::webauthn().map_err(passkey_error)?;
+        let result = match Passkeys::finish_authentication(webauthn, &form.credential, state, &credential.passkey) {
+            Ok(result) => result,
+            Err(e) => {
+                let details = format!("Passkey \"{}\" rejected: {}", credential.name, e);
+                Audit::record(AuditAction::LoginFailed, None, Some(credential.user.to_hex()), meta, Some(details)).await;
+                return Err(passkey_error(e));
+            },
+        };
+        Passkeys::record_use(&credential, &result).await.map_err(|_| AuthError::Internal)?;
+        let user = Users::get_user(&credential.user).await.map_err(|e| match e {
+            MongoRepoError::NotFoundError(_) => AuthError::InvalidPasskey,
+            _ => AuthError::Internal,
+        })?;
+        if !user.status.is_active() {
+            let details = format!("Account not active: {:?}", user.status);
+            Audit::record(AuditAction::LoginFailed, None, Some(credential.user.to_hex()), meta, Some(details)).await;
+            return Err(AuthError::AccountInactive(user.status));
+        }
+        if TwoFactor::login_step(&user).await.map_err(|_| AuthError::Internal)? == Some(TwoFactorStep::Enroll) {
+            return Err(TwoFactorApi::hold_login(session, meta, &user, TwoFactorStep::Enroll).await);
+        }
+        Api::start_session(session, meta, &user, Some(format!("Passkey \"{}\"", credential.name))).await?;
+        Ok(user)
+    }
+
+    pub async fn list(session: &Session) -> Result<Vec<PasskeySummary>, AuthError> {
+        let (_, id) = current_user(session).await?;
+        let credentials = Passkeys::list(&id).await.map_err(|_| AuthError::Internal)?;
+        Ok(credentials.iter().map(Passkeys::to_summary).collect())
+    }
+
+    pub async fn remove(session: &Session, meta: &RequestMeta, credential_id: &str) -> Result<(), AuthError> {
+        let (_, id) = current_user(session).await?;
+        if !Passkeys::remove(&id, credential_id).await.map_err(|_| AuthError::Internal)? {
+            return Err(AuthError::NotFound);
+        }
+        Audit::record(AuditAction::PasskeyRemoved, Some(id), Some(id.to_hex()), meta, Some(credential_id.to_string())).await;
+        Ok(())
+    }
+
+    #[utoipa::path(post, path = "/passkeys/register/start", tag = "auth", responses(
+        (status = 200, description = "Options for `navigator.credentials.create()`", body = Object),
+        (status = 401, description = "Not logged in", body = ApiError),
+    ))]
+    pub async fn start_passkey_registration(session: Session) -> Result<Json<CreationChallengeResponse>, Response> {
+        start_registration(&session).await.map(Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/passkeys/register/finish", tag = "auth", request_body = NewPasskeyForm, responses(
+        (status = 200, description = "Passkey registered", body = PasskeySummary),
+        (status = 400, description = "Invalid form", body = ApiError),
+        (status = 401, description = "Not logged in, or the passkey was not accepted", body = ApiError),
+        (status = 409, description = "Too many passkeys", body = ApiError),
+    ))]
+    pub async fn finish_passkey_registration(session: Session, meta: RequestMeta, Json(payload): Json<NewPasskeyForm>) -> Result<Json<PasskeySummary>, Response> {
+        finish_registration(&session, &meta, payload).await.map(Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(get, path = "/passkeys", tag = "auth", responses(
+        (status = 200, description = "Passkeys of the logged in member", body = Vec<PasskeySummary>),
+        (status = 401, description = "Not logged in", body = ApiError),
+    ))]
+    pub async fn list_passkeys(session: Session) -> Result<Json<Vec<PasskeySummary>>, Response> {
+        list(&session).await.map(Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(delete, path = "/passkeys/{id}", tag = "auth", params(("id" = String, Path, description = "Credential id")), responses(
+        (status = 204, description = "Passkey removed"),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 404, description = "No such passkey", body = ApiError),
+    ))]
+    pub async fn remove_passkey(session: Session, meta: RequestMeta, Path(id): Path<String>) -> Result<StatusCode, Response> {
+        remove(&session, &meta, &id).await.map(|_| StatusCode::NO_CONTENT).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/login/passkey/start", tag = "auth", responses(
+        (status = 200, description = "Options for `navigator.credentials.get()`", body = Object),
+    ))]
+    pub async fn start_passkey_login(session: Session) -> Result<Json<RequestChallengeResponse>, Response> {
+        start_login(&session).await.map(Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/login/passkey/finish", tag = "auth", request_body = PasskeyLoginForm, responses(
+        (status = 200, description = "Logged in, session cookie set", body = UserView),
+        (status = 401, description = "Passkey not accepted, or an admin must set up two-factor authentication first \
+            (`two_factor` is `Enroll`)", body = ApiError),
+        (status = 403, description = "Account suspended or banned", body = ApiError),
+    ))]
+    pub async fn finish_passkey_login(session: Session, meta: RequestMeta, Json(payload): Json<PasskeyLoginForm>) -> Result<Json<UserView>, Response> {
+        finish_login(&session, &meta, payload).await.map(|user| Json(Users::to_view(&user))).map_err(auth_error_response)
+    }
+}
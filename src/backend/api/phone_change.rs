@@ -0,0 +1,143 @@
+#[cfg(feature = "server")]
+pub mod PhoneChangeApi {
+    use axum::{http::StatusCode, response::Response, Json};
+    use chrono::{DateTime, Duration, Utc};
+    use serde::{Deserialize, Serialize};
+    use tower_sessions::Session;
+    use validator::Validate;
+
+    use crate::backend::{api::{api::{Api::{auth_error_response, guard_error}, JWT_TOKEN, PENDING_PHONE_CHANGE_KEY}, jwt}, audit::Audit::{self, RequestMeta}, auths::{auth_session::AuthSession, guards::Guards}, forms::{ApiError, AuditAction, AuthError, ConfirmPhoneChangeForm, PhoneChangeChallenge, PhoneChangeForm}, mongo_crud::MongoRepoError, phone_change::PhoneChange::{self, PhoneChangeError, Proof, SentCodes}, two_factor::TwoFactor, users::Users};
+
+    /// How long the codes of a change stay valid.
+    const PENDING_CHANGE_MINUTES: i64 = 10;
+    /// Wrong codes allowed per change before it has to be started again.
+    const MAX_ATTEMPTS: u32 = 5;
+
+    /// A phone number change waiting for its codes, kept in the session under `PENDING_PHONE_CHANGE_KEY`.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct PendingPhoneChange {
+        pub phone_number: String,
+        pub current_code: String,
+        pub new_code: String,
+        pub started: DateTime<Utc>,
+        pub attempts: u32,
+    }
+
+    impl PendingPhoneChange {
+        fn sent_codes(&self) -> SentCodes {
+            SentCodes { current: self.current_code.clone(), new: self.new_code.clone() }
+        }
+    }
+
+    fn phone_change_error(e: PhoneChangeError) -> AuthError {
+        match e {
+            PhoneChangeError::SameNumber => AuthError::InvalidForm,
+            PhoneChangeError::NumberTaken => AuthError::AlreadyRegistered,
+            PhoneChangeError::InvalidCode => AuthError::InvalidVerificationCode,
+            PhoneChangeError::TooSoon(_) => AuthError::TooManyPhoneChanges,
+            PhoneChangeError::Sms(_) | PhoneChangeError::Repo(_) => AuthError::Internal,
+        }
+    }
+
+    /// Sends a code to the new number and to the current one of the logged in member, replacing any change
+    /// already in progress.
+    pub async fn start(session: &Session, form: PhoneChangeForm) -> Result<PhoneChangeChallenge, AuthError> {
+        form.validate().map_err(|_| AuthError::InvalidForm)?;
+        let user = Guards::current_user(session).await.map_err(guard_error)?;
+        let id = user.id.ok_or(AuthError::Internal)?;
+        let sent = PhoneChange::send_codes(&user, &form.phone_number).await.map_err(phone_change_error)?;
+        let pending = PendingPhoneChange {
+            phone_number: Users::normalize_phone_number(&form.phone_number),
+            current_code: sent.current,
+            new_code: sent.new,
+            started: Utc::now(),
+            attempts: 0,
+        };
+        let current = PhoneChange::current_phone_number(&user).map_err(|_| AuthError::Internal)?;
+        let challenge = PhoneChangeChallenge {
+            current_phone_number: Audit::mask_phone_number(&current),
+            new_phone_number: Audit::mask_phone_number(&pending.phone_number),
+            recovery_code_allowed: TwoFactor::is_enabled(&id).await.map_err(|_| AuthError::Internal)?,
+            expires: pending.started + Duration::minutes(PENDING_CHANGE_MINUTES),
+        };
+        session.insert(PENDING_PHONE_CHANGE_KEY, pending).await.map_err(|_| AuthError::Internal)?;
+        Ok(challenge)
+    }
+
+    /// Whether no member, the logged in one included, uses `form.phone_number`.
+    pub async fn is_available(session: &Session, form: PhoneChangeForm) -> Result<bool, AuthError> {
+        form.validate().map_err(|_| AuthError::InvalidForm)?;
+        Guards::current_user(session).await.map_err(guard_error)?;
+        match Users::find_by_phone_number(&form.phone_number).await {
+            Ok(_) => Ok(false),
+            Err(MongoRepoError::NotFoundError(_)) => Ok(true),
+            Err(_) => Err(AuthError::Internal),
+        }
+    }
+
+    /// Completes the change started by `start` once both codes check out. The member's other sessions are ended, and
+    /// the current one carries on under the new number.
+    pub async fn confirm(session: &Session, meta: &RequestMeta, form: ConfirmPhoneChangeForm) -> Result<(), AuthError> {
+        form.validate().map_err(|_| AuthError::InvalidForm)?;
+        let user = Guards::current_user(session).await.map_err(guard_error)?;
+        let id = user.id.ok_or(AuthError::Internal)?;
+        let mut pending = session.get::<PendingPhoneChange>(PENDING_PHONE_CHANGE_KEY).await
+            .map_err(|_| AuthError::Internal)?
+            .ok_or(AuthError::InvalidVerificationCode)?;
+        if Utc::now() - pending.started > Duration::minutes(PENDING_CHANGE_MINUTES) {
+            session.remove::<PendingPhoneChange>(PENDING_PHONE_CHANGE_KEY).await.map_err(|_| AuthError::Internal)?;
+            return Err(AuthError::InvalidVerificationCode);
+        }
+        // The new number is checked first, so that a recovery code is only used up for a change that goes through.
+        let proof = if PhoneChange::code_matches(&pending.new_code, &form.new_code) {
+            PhoneChange::verify_current(&id, &pending.sent_codes(), &form.current_code).await
+        } else {
+            Err(PhoneChangeError::InvalidCode)
+        };
+        let proof = match proof {
+            Ok(proof) => proof,
+            Err(PhoneChangeError::InvalidCode) => {
+                pending.attempts += 1;
+                if pending.attempts >= MAX_ATTEMPTS {
+                    session.remove::<PendingPhoneChange>(PENDING_PHONE_CHANGE_KEY).await.map_err(|_| AuthError::Internal)?;
+                } else {
+                    session.insert(PENDING_PHONE_CHANGE_KEY, pending).await.map_err(|_| AuthError::Internal)?;
+                }
+                return Err(AuthError::InvalidVerificationCode);
+            },
+            Err(e) => return Err(phone_change_error(e)),
+        };
+        session.remove::<PendingPhoneChange>(PENDING_PHONE_CHANGE_KEY).await.map_err(|_| AuthError::Internal)?;
+        PhoneChange::apply(&user, &pending.phone_number).await.map_err(phone_change_error)?;
+        AuthSession::revoke_other_sessions(&id, session).await.map_err(|_| AuthError::Internal)?;
+        let token = jwt::Jwt::get_jwt(&id).map_err(|_| AuthError::Internal)?;
+        session.insert(JWT_TOKEN, token).await.map_err(|_| AuthError::Internal)?;
+        let details = match proof {
+            Proof::CurrentNumber => format!("Moved to {}, confirmed from the previous number", Audit::mask_phone_number(&pending.phone_number)),
+            Proof::RecoveryCode { remaining } => format!("Moved to {}, confirmed with a recovery code, {} left", Audit::mask_phone_number(&pending.phone_number), remaining),
+        };
+        Audit::record(AuditAction::PhoneNumberChanged, Some(id), Some(id.to_hex()), meta, Some(details)).await;
+        Ok(())
+    }
+
+    #[utoipa::path(post, path = "/account/phone-number", tag = "auth", request_body = PhoneChangeForm, responses(
+        (status = 200, description = "Codes sent to the new and the current number, to be entered at `/account/phone-number/confirm`", body = PhoneChangeChallenge),
+        (status = 208, description = "Phone number already registered", body = ApiError),
+        (status = 400, description = "Invalid form, or the number is the current one", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 429, description = "A change was started less than a minute ago, or five within the hour", body = ApiError),
+    ))]
+    pub async fn start_phone_change(session: Session, Json(payload): Json<PhoneChangeForm>) -> Result<Json<PhoneChangeChallenge>, Response> {
+        start(&session, payload).await.map(Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/account/phone-number/confirm", tag = "auth", request_body = ConfirmPhoneChangeForm, responses(
+        (status = 204, description = "Phone number changed; other sessions are logged out"),
+        (status = 208, description = "The new number was registered by another member meanwhile", body = ApiError),
+        (status = 400, description = "Invalid form", body = ApiError),
+        (status = 401, description = "Wrong code, no change in progress, or not logged in", body = ApiError),
+    ))]
+    pub async fn confirm_phone_change(session: Session, meta: RequestMeta, Json(payload): Json<ConfirmPhoneChangeForm>) -> Result<StatusCode, Response> {
+        confirm(&session, &meta, payload).await.map(|_| StatusCode::NO_CONTENT).map_err(auth_error_response)
+    }
+}
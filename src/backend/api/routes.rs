@@ -0,0 +1,255 @@
+#[cfg(feature = "server")]
+pub mod Routes {
+    use std::time::Duration;
+
+    use axum::{extract::{DefaultBodyLimit, State}, http::{header, HeaderName, HeaderValue, Method}, middleware, response::{Html, Response}, routing::{get, post}, Extension, Json};
+    use chrono::{DateTime, TimeZone, Utc};
+    use utoipa::{openapi::{path::Operation, security::{ApiKey, ApiKeyValue, SecurityScheme}, Deprecated, OpenApi as OpenApiDocument}, Modify, OpenApi};
+    use tower_http::cors::{AllowOrigin, CorsLayer};
+    use utoipa_axum::{router::OpenApiRouter, routes};
+
+    use crate::backend::{api::{admin::Admin, api::Api, media::Media, passkeys::PasskeysApi, phone_change::PhoneChangeApi, storefronts::Storefronts, two_factor::TwoFactorApi}, auths::{auth_session::AuthSession::CookieConfig, csrf::CSRF_HEADER, guards::Guards}, media::images::Images, security_headers::SecurityHeaders::{self, ContentSecurityPolicy, CSP_REPORT_PATH}, telemetry::Telemetry::REQUEST_ID_HEADER};
+
+    /// Newest API version; REST clients should target `/api/{CURRENT_VERSION}`.
+    pub const CURRENT_VERSION: &str = "v1";
+
+    /// Default name of the cookie holding the tower-sessions id, which authenticates every REST call after login.
+    /// `SESSION_COOKIE_NAME` overrides it.
+    pub const SESSION_COOKIE: &str = "id";
+
+    /// Violation reports are small; anything larger is not a report.
+    const CSP_REPORT_LIMIT: usize = 64 * 1024;
+
+    const DOCS_PAGE: &str = r##"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Freelancer API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui", withCredentials: true });
+  </script>
+</body>
+</html>"##;
+
+    #[derive(OpenApi)]
+    #[openapi(
+        info(title = "Freelancer API", description = "REST API of the Freelancer marketplace. Log in through `/api/v1/login`; later calls are authenticated by the session cookie it sets. The unversioned `/api/...` paths are deprecated aliases of v1."),
+        modifiers(&SessionCookie),
+        tags(
+            (name = "auth", description = "Registration, login, two-factor authentication, passkeys and phone number changes"),
+            (name = "media", description = "Image uploads"),
+            (name = "storefronts", description = "Seller storefronts"),
+            (name = "admin", description = "Administration, restricted to admins"),
+        ),
+    )]
+    struct ApiDoc;
+
+    /// Security schemes of the document, with the session cookie under the name the server is configured with.
+    struct SessionCookie;
+
+    impl Modify for SessionCookie {
+        fn modify(&self, openapi: &mut OpenApiDocument) {
+            let components = openapi.components.get_or_insert_with(Default::default);
+            let session_cookie = CookieConfig::get().session_name.clone();
+            components.add_security_scheme("session", SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(session_cookie))));
+            components.add_security_scheme("csrf", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-CSRF-Token",
+                "Required on POST, PUT, PATCH and DELETE; the value of the `csrf_token` cookie or of `GET /api/v1/csrf`",
+            ))));
+        }
+    }
+
+    /// Retirement schedule of a set of routes, announced on each of their responses through the
+    /// `Deprecation` (RFC 9745), `Sunset` (RFC 8594) and `Link: rel="successor-version"` headers.
+    #[derive(Clone, Copy)]
+    pub struct Deprecation {
+        pub since: DateTime<Utc>,
+        pub sunset: DateTime<Utc>,
+        /// Path prefix replacing the deprecated routes.
+        pub successor: &'static str,
+    }
+
+    impl Deprecation {
+        async fn add_headers(State(deprecation): State<Deprecation>, mut response: Response) -> Response {
+            let headers = response.headers_mut();
+            let values = [
+                ("deprecation", format!("@{}", deprecation.since.timestamp())),
+                ("sunset", deprecation.sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string()),
+                (header::LINK.as_str(), format!("<{}>; rel=\"successor-version\"", deprecation.successor)),
+            ];
+            for (name, value) in values {
+                if let Ok(value) = HeaderValue::from_str(&value) {
+                    headers.insert(name, value);
+                }
+            }
+            response
+        }
+    }
+
+    /// The unversioned `/api/...` paths predate `/api/v1` and are kept as aliases until clients move over.
+    pub fn unversioned_deprecation() -> Deprecation {
+        Deprecation {
+            since: Utc.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).unwrap(),
+            sunset: Utc.with_ymd_and_hms(2027, 4, 19, 0, 0, 0).unwrap(),
+            successor: "/api/v1",
+        }
+    }
+
+    fn operations_mut(openapi: &mut OpenApiDocument) -> impl Iterator<Item = &mut Operation> {
+        openapi.paths.paths.values_mut()
+            .flat_map(|item| [&mut item.get, &mut item.post, &mut item.put, &mut item.delete, &mut item.patch])
+            .flatten()
+    }
+
+    /// Marks every route of `router` as deprecated: responses carry the deprecation headers and the
+    /// operations are flagged `deprecated` in the OpenAPI document.
+    pub fn deprecate(router: OpenApiRouter, deprecation: Deprecation) -> OpenApiRouter {
+        let (router, mut openapi) = router
+            .layer(middleware::map_response_with_state(deprecation, Deprecation::add_headers))
+            .split_for_parts();
+        for operation in operations_mut(&mut openapi) {
+            operation.deprecated = Some(Deprecated::True);
+        }
+        OpenApiRouter::with_openapi(openapi).merge(router.into())
+    }
+
+    /// `router` mounted at `path`, with `_{suffix}` added to its operation ids so that they stay unique when
+    /// the same handlers are mounted more than once.
+    fn mount(path: &str, suffix: Option<&str>, router: OpenApiRouter) -> OpenApiRouter {
+        let (router, mut openapi) = router.split_for_parts();
+        if let Some(suffix) = suffix {
+            for operation in operations_mut(&mut openapi) {
+                operation.operation_id = operation.operation_id.take().map(|id| format!("{id}_{suffix}"));
+            }
+        }
+        OpenApiRouter::new().nest(path, OpenApiRouter::with_openapi(openapi).merge(router.into()))
+    }
+
+    /// Version `name` of the REST API, mounted at `/api/{name}`. A version replaced by a newer one stays
+    /// mounted with its own handlers and DTOs, wrapped in `deprecate`, until its sunset:
+    ///
+    /// ```ignore
+    /// api.merge(version("v1", deprecate(v1(), v1_deprecation()))).merge(version("v2", v2()))
+    /// ```
+    ///
+    /// Operation ids of versions other than `CURRENT_VERSION` end in `_{name}`.
+    pub fn version(name: &str, router: OpenApiRouter) -> OpenApiRouter {
+        mount(&format!("/api/{name}"), (name != CURRENT_VERSION).then_some(name), router)
+    }
+
+    /// The deprecated unversioned `/api/...` aliases of v1. Their operation ids end in `_unversioned`.
+    pub fn unversioned() -> OpenApiRouter {
+        mount("/api", Some("unversioned"), deprecate(v1(), unversioned_deprecation()))
+    }
+
+    /// Version 1 of the REST API, relative to its `/api/v1` mount point. Routes are registered together
+    /// with their documentation, so a handler missing from the OpenAPI document cannot be routed through here.
+    ///
+    /// A breaking change to a request or response shape goes into a new version with its own router and
+    /// DTOs, while this one keeps serving the current types until it is retired through `deprecate`.
+    pub fn v1() -> OpenApiRouter {
+        let admin = OpenApiRouter::new()
+            .routes(routes!(Admin::list_users))
+            .routes(routes!(Admin::get_user))
+            .routes(routes!(Admin::change_role))
+            .routes(routes!(Admin::suspend_user))
+            .routes(routes!(Admin::ban_user))
+            .routes(routes!(Admin::reactivate_user))
+            .routes(routes!(Admin::recover_phone_number))
+            .routes(routes!(Admin::list_storefronts))
+            .routes(routes!(Admin::approve_storefront))
+            .routes(routes!(Admin::reject_storefront))
+            .routes(routes!(Admin::list_audit_log))
+            .routes(routes!(Admin::export_audit_log));
+
+        let media = OpenApiRouter::new()
+            .routes(routes!(Media::upload_image))
+            .layer(DefaultBodyLimit::max(Images::MAX_UPLOAD_BYTES + 64 * 1024));
+
+        OpenApiRouter::new()
+            .routes(routes!(Api::register_user))
+            .routes(routes!(Api::login_user))
+            .routes(routes!(Api::is_authenticated))
+            .routes(routes!(Api::csrf_token))
+            .routes(routes!(TwoFactorApi::login_two_factor))
+            .routes(routes!(TwoFactorApi::enroll_two_factor))
+            .routes(routes!(TwoFactorApi::confirm_two_factor))
+            .routes(routes!(TwoFactorApi::regenerate_two_factor_recovery_codes))
+            .routes(routes!(TwoFactorApi::disable_two_factor))
+            .routes(routes!(PasskeysApi::start_passkey_login))
+            .routes(routes!(PasskeysApi::finish_passkey_login))
+            .routes(routes!(PasskeysApi::start_passkey_registration))
+            .routes(routes!(PasskeysApi::finish_passkey_registration))
+            .routes(routes!(PasskeysApi::list_passkeys))
+            .routes(routes!(PasskeysApi::remove_passkey))
+            .routes(routes!(PhoneChangeApi::start_phone_change))
+            .routes(routes!(PhoneChangeApi::confirm_phone_change))
+            .routes(routes!(Storefronts::create_storefront))
+            .merge(media)
+            .nest("/admin", admin)
+            .layer(middleware::from_fn(Guards::enforce_account_status))
+            .layer(middleware::from_fn(Api::json_errors))
+    }
+
+    /// Every supported version of the REST API under `/api`, along with the unversioned aliases; this is what
+    /// the OpenAPI document describes.
+    pub fn api_router() -> OpenApiRouter {
+        OpenApiRouter::with_openapi(ApiDoc::openapi())
+            .merge(version(CURRENT_VERSION, v1()))
+            .merge(unversioned())
+    }
+
+    pub fn openapi() -> OpenApiDocument {
+        api_router().into_openapi()
+    }
+
+    /// CORS for the origins listed, comma separated, in `CORS_ALLOWED_ORIGINS`. Without the variable no origin is
+    /// allowed and browsers keep to the same-origin policy. Allowed origins may send credentials, so list only
+    /// trusted sites; they also need `COOKIE_SAME_SITE=none` for the session cookie to be sent.
+    pub fn cors() -> CorsLayer {
+        let origins = std::env::var("CORS_ALLOWED_ORIGINS").unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .filter_map(|origin| match HeaderValue::from_str(origin) {
+                Ok(origin) => Some(origin),
+                Err(_) => {
+                    tracing::warn!(origin, "Ignoring invalid CORS origin");
+                    None
+                },
+            })
+            .collect::<Vec<HeaderValue>>();
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_credentials(true)
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+            .allow_headers([header::CONTENT_TYPE, HeaderName::from_static(CSRF_HEADER)])
+            .expose_headers([HeaderName::from_static(CSRF_HEADER), HeaderName::from_static(REQUEST_ID_HEADER)])
+            .max_age(Duration::from_secs(3600))
+    }
+
+    /// The docs page loads Swagger UI from unpkg, on top of what the app may load.
+    fn docs_policy() -> ContentSecurityPolicy {
+        SecurityHeaders::configured_policy()
+            .allow("script-src", "https://unpkg.com")
+            .allow("style-src", "https://unpkg.com")
+    }
+
+    /// `api_router` along with its OpenAPI document at `/api/openapi.json`, an interactive page at `/api/docs`
+    /// and the CSP report endpoint.
+    pub fn documented_api_router() -> axum::Router {
+        let (router, openapi) = api_router().split_for_parts();
+        router
+            .route("/api/openapi.json", get(move || {
+                let openapi = openapi.clone();
+                async move { Json(openapi) }
+            }))
+            .route("/api/docs", get(|| async { (Extension(docs_policy()), Html(DOCS_PAGE)) }))
+            .route(CSP_REPORT_PATH, post(SecurityHeaders::report).layer(DefaultBodyLimit::max(CSP_REPORT_LIMIT)))
+    }
+}
@@ -0,0 +1,22 @@
+#[cfg(feature = "server")]
+pub mod Storefronts {
+    use axum::{http::StatusCode, Json};
+    use tower_sessions::Session;
+    use validator::Validate;
+
+    use crate::backend::{auths::guards::Guards, forms::{ApiError, StorefrontSummary, Forms::{StorefrontForm, UserRole}}, storefronts};
+
+    #[utoipa::path(post, path = "/storefronts", tag = "storefronts", request_body = StorefrontForm, responses(
+        (status = 200, description = "Storefront created, pending review", body = StorefrontSummary),
+        (status = 400, description = "Invalid form", body = ApiError),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Only sellers can open a storefront", body = ApiError),
+    ))]
+    pub async fn create_storefront(session: Session, Json(payload): Json<StorefrontForm>) -> Result<Json<StorefrontSummary>, StatusCode> {
+        let seller = Guards::require_role(&session, &[UserRole::Seller]).await?;
+        payload.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+        let owner = seller.id.ok_or(StatusCode::UNAUTHORIZED)?;
+        let storefront = storefronts::Storefronts::create_storefront(owner, payload).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Ok(Json(storefronts::Storefronts::to_summary(&storefront)))
+    }
+}
@@ -0,0 +1,227 @@
+#[cfg(feature = "server")]
+pub mod TwoFactorApi {
+    use axum::{http::StatusCode, response::Response, Json};
+    use chrono::{DateTime, Duration, Utc};
+    use serde::{Deserialize, Serialize};
+    use tower_sessions::Session;
+    use validator::Validate;
+
+    use crate::backend::{api::api::{Api::{self, auth_error_response, guard_error, SessionUser}, PENDING_LOGIN_KEY}, audit::Audit::{self, RequestMeta}, auths::guards::Guards, forms::{ApiError, AuditAction, AuthError, RecoveryCodes, TwoFactorCodeForm, TwoFactorEnrollment, TwoFactorStep, UserView}, mongo_crud::MongoRepoError, mongo_models::Docs::BaseUser, monitoring::Monitoring, two_factor::TwoFactor::{self, TwoFactorError, Verified}, users::Users};
+
+    /// How long the second step may take once the phone number was accepted.
+    const PENDING_LOGIN_MINUTES: i64 = 10;
+
+    /// A login waiting for its second factor, kept in the session under `PENDING_LOGIN_KEY`. Nothing but the
+    /// two-factor endpoints looks at it, so the member has no access until the login completes.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct PendingLogin {
+        pub user: SessionUser,
+        pub step: TwoFactorStep,
+        pub started: DateTime<Utc>,
+    }
+
+    fn two_factor_error(e: TwoFactorError) -> AuthError {
+        match e {
+            TwoFactorError::AlreadyEnabled => AuthError::TwoFactorAlreadyEnabled,
+            TwoFactorError::NotEnrolled | TwoFactorError::InvalidCode => AuthError::InvalidTwoFactorCode,
+            TwoFactorError::LockedOut(_) => AuthError::TwoFactorLocked,
+            TwoFactorError::Repo(_) => AuthError::Internal,
+        }
+    }
+
+    /// Puts the login of `user` on hold until `step` is passed.
+    pub async fn begin(session: &Session, user: &BaseUser, step: TwoFactorStep) -> Result<(), AuthError> {
+        let pending = PendingLogin {
+            user: SessionUser::new(user).ok_or(AuthError::Internal)?,
+            step,
+            started: Utc::now(),
+        };
+        session.insert(PENDING_LOGIN_KEY, pending).await.map_err(|_| AuthError::Internal)
+    }
+
+    /// Answer to a login of `user`, whose first factor was accepted, when their account asks for `step`. A code is
+    /// then awaited; a member who has to set up two-factor authentication first is refused, as enrolling on the
+    /// strength of the phone number alone would let anyone knowing it pick the second factor. They enroll from a
+    /// session started before their role required it, or with `freelance-admin enroll-two-factor`.
+    pub async fn hold_login(session: &Session, meta: &RequestMeta, user: &BaseUser, step: TwoFactorStep) -> AuthError {
+        match step {
+            TwoFactorStep::Verify => match begin(session, user, step).await {
+                Ok(()) => AuthError::TwoFactorRequired(step),
+                Err(e) => e,
+            },
+            TwoFactorStep::Enroll => {
+                let details = String::from("Two-factor authentication required but not set up");
+                Audit::record(AuditAction::LoginFailed, None, user.id.map(|id| id.to_hex()), meta, Some(details)).await;
+                AuthError::TwoFactorRequired(step)
+            },
+        }
+    }
+
+    async fn pending_login(session: &Session, step: TwoFactorStep) -> Result<PendingLogin, AuthError> {
+        let pending = session.get::<PendingLogin>(PENDING_LOGIN_KEY).await
+            .map_err(|_| AuthError::Internal)?
+            .filter(|pending| pending.step == step)
+            .ok_or(AuthError::Unauthenticated)?;
+        if Utc::now() - pending.started > Duration::minutes(PENDING_LOGIN_MINUTES) {
+            session.remove::<PendingLogin>(PENDING_LOGIN_KEY).await.map_err(|_| AuthError::Internal)?;
+            return Err(AuthError::Unauthenticated);
+        }
+        Ok(pending)
+    }
+
+    /// The member of a pending login, reloaded in case they were suspended or banned meanwhile.
+    async fn pending_user(pending: &PendingLogin) -> Result<BaseUser, AuthError> {
+        let user = Users::get_user(&pending.user.id).await.map_err(|e| match e {
+            MongoRepoError::NotFoundError(_) => AuthError::Unauthenticated,
+            _ => AuthError::Internal,
+        })?;
+        if !user.status.is_active() {
+            return Err(AuthError::AccountInactive(user.status));
+        }
+        Ok(user)
+    }
+
+    async fn complete_login(session: &Session, meta: &RequestMeta, user: &BaseUser, details: String) -> Result<(), AuthError> {
+        session.remove::<PendingLogin>(PENDING_LOGIN_KEY).await.map_err(|_| AuthError::Internal)?;
+        Api::start_session(session, meta, user, Some(details)).await
+    }
+
+    /// Second step of a login with two-factor authentication, taking a code from the authenticator app or a
+    /// recovery code. Once `TwoFactor` locks the member out after too many wrong codes, the login is dropped and
+    /// starting over from the phone number does not help until the lockout ends.
+    pub async fn verify_login(session: &Session, meta: &RequestMeta, form: TwoFactorCodeForm) -> Result<BaseUser, AuthError> {
+        let result = attempt_verify_login(session, meta, form).await;
+        Monitoring::record_login(result.is_ok());
+        result
+    }
+
+    async fn attempt_verify_login(session: &Session, meta: &RequestMeta, form: TwoFactorCodeForm) -> Result<BaseUser, AuthError> {
+        form.validate().map_err(|_| AuthError::InvalidForm)?;
+        let pending = pending_login(session, TwoFactorStep::Verify).await?;
+        let user = pending_user(&pending).await?;
+        match TwoFactor::verify(&pending.user.id, &form.code).await {
+            Ok(verified) => {
+                let details = match verified {
+                    Verified::Code => String::from("Two-factor code"),
+                    Verified::RecoveryCode { remaining } => format!("Recovery code, {} left", remaining),
+                };
+                complete_login(session, meta, &user, details).await?;
+                Ok(user)
+            },
+            Err(TwoFactorError::Repo(_)) => Err(AuthError::Internal),
+            Err(TwoFactorError::LockedOut(until)) => {
+                let details = format!("Two-factor codes locked until {}", until.to_rfc3339());
+                Audit::record(AuditAction::LoginFailed, None, Some(pending.user.id.to_hex()), meta, Some(details)).await;
+                session.remove::<PendingLogin>(PENDING_LOGIN_KEY).await.map_err(|_| AuthError::Internal)?;
+                Err(AuthError::TwoFactorLocked)
+            },
+            Err(_) => {
+                let details = String::from("Invalid two-factor code");
+                Audit::record(AuditAction::LoginFailed, None, Some(pending.user.id.to_hex()), meta, Some(details)).await;
+                Err(AuthError::InvalidTwoFactorCode)
+            },
+        }
+    }
+
+    /// Member setting up two-factor authentication: a logged in seller or admin. A login waiting for a code does
+    /// not count, see `hold_login`.
+    async fn enrolling_user(session: &Session) -> Result<BaseUser, AuthError> {
+        let user = Guards::current_user(session).await.map_err(guard_error)?;
+        if !TwoFactor::available_for(&user.role) {
+            return Err(AuthError::Forbidden);
+        }
+        Ok(user)
+    }
+
+    pub async fn enroll(session: &Session) -> Result<TwoFactorEnrollment, AuthError> {
+        let user = enrolling_user(session).await?;
+        TwoFactor::start_enrollment(&user).await.map_err(two_factor_error)
+    }
+
+    /// Turns two-factor authentication on with a first code for the secret from `enroll`, and returns the
+    /// recovery codes.
+    pub async fn confirm(session: &Session, meta: &RequestMeta, form: TwoFactorCodeForm) -> Result<RecoveryCodes, AuthError> {
+        form.validate().map_err(|_| AuthError::InvalidForm)?;
+        let user = enrolling_user(session).await?;
+        let id = user.id.ok_or(AuthError::Internal)?;
+        let codes = TwoFactor::confirm_enrollment(&id, &form.code).await.map_err(two_factor_error)?;
+        Audit::record(AuditAction::TwoFactorEnabled, Some(id), Some(id.to_hex()), meta, None).await;
+        Ok(codes)
+    }
+
+    /// Replaces the recovery codes of the logged in member, confirmed with a code from the authenticator app.
+    pub async fn regenerate_recovery_codes(session: &Session, form: TwoFactorCodeForm) -> Result<RecoveryCodes, AuthError> {
+        form.validate().map_err(|_| AuthError::InvalidForm)?;
+        let user = Guards::current_user(session).await.map_err(guard_error)?;
+        let id = user.id.ok_or(AuthError::Internal)?;
+        TwoFactor::verify_code(&id, &form.code).await.map_err(two_factor_error)?;
+        TwoFactor::regenerate_recovery_codes(&id).await.map_err(two_factor_error)
+    }
+
+    /// Turns two-factor authentication off, confirmed with a code from the authenticator app. Refused to
+    /// members whose role requires it.
+    pub async fn disable(session: &Session, meta: &RequestMeta, form: TwoFactorCodeForm) -> Result<(), AuthError> {
+        form.validate().map_err(|_| AuthError::InvalidForm)?;
+        let user = Guards::current_user(session).await.map_err(guard_error)?;
+        if TwoFactor::required_for(&user.role) {
+            return Err(AuthError::Forbidden);
+        }
+        let id = user.id.ok_or(AuthError::Internal)?;
+        TwoFactor::verify_code(&id, &form.code).await.map_err(two_factor_error)?;
+        TwoFactor::disable(&id).await.map_err(|_| AuthError::Internal)?;
+        Audit::record(AuditAction::TwoFactorDisabled, Some(id), Some(id.to_hex()), meta, None).await;
+        Ok(())
+    }
+
+    #[utoipa::path(post, path = "/login/two-factor", tag = "auth", request_body = TwoFactorCodeForm, responses(
+        (status = 200, description = "Logged in, session cookie set", body = UserView),
+        (status = 400, description = "Invalid form", body = ApiError),
+        (status = 401, description = "Wrong code, or no login waiting for one", body = ApiError),
+        (status = 403, description = "Account suspended or banned", body = ApiError),
+        (status = 429, description = "Too many wrong codes for the member; codes are refused for 15 minutes", body = ApiError),
+    ))]
+    pub async fn login_two_factor(session: Session, meta: RequestMeta, Json(payload): Json<TwoFactorCodeForm>) -> Result<Json<UserView>, Response> {
+        verify_login(&session, &meta, payload).await.map(|user| Json(Users::to_view(&user))).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/two-factor/enroll", tag = "auth", responses(
+        (status = 200, description = "New secret, to be confirmed through `/two-factor/confirm`", body = TwoFactorEnrollment),
+        (status = 401, description = "Not logged in", body = ApiError),
+        (status = 403, description = "Only sellers and admins can set up two-factor authentication", body = ApiError),
+        (status = 409, description = "Two-factor authentication is already enabled", body = ApiError),
+    ))]
+    pub async fn enroll_two_factor(session: Session) -> Result<Json<TwoFactorEnrollment>, Response> {
+        enroll(&session).await.map(Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/two-factor/confirm", tag = "auth", request_body = TwoFactorCodeForm, responses(
+        (status = 200, description = "Two-factor authentication enabled; the recovery codes are shown only this once", body = RecoveryCodes),
+        (status = 400, description = "Invalid form", body = ApiError),
+        (status = 401, description = "Wrong code, or not logged in", body = ApiError),
+        (status = 409, description = "Two-factor authentication is already enabled", body = ApiError),
+    ))]
+    pub async fn confirm_two_factor(session: Session, meta: RequestMeta, Json(payload): Json<TwoFactorCodeForm>) -> Result<Json<RecoveryCodes>, Response> {
+        confirm(&session, &meta, payload).await.map(Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/two-factor/recovery-codes", tag = "auth", request_body = TwoFactorCodeForm, responses(
+        (status = 200, description = "New recovery codes, replacing the previous ones", body = RecoveryCodes),
+        (status = 400, description = "Invalid form", body = ApiError),
+        (status = 401, description = "Wrong code, or not logged in", body = ApiError),
+        (status = 429, description = "Too many wrong codes; codes are refused for 15 minutes", body = ApiError),
+    ))]
+    pub async fn regenerate_two_factor_recovery_codes(session: Session, Json(payload): Json<TwoFactorCodeForm>) -> Result<Json<RecoveryCodes>, Response> {
+        regenerate_recovery_codes(&session, payload).await.map(Json).map_err(auth_error_response)
+    }
+
+    #[utoipa::path(post, path = "/two-factor/disable", tag = "auth", request_body = TwoFactorCodeForm, responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 400, description = "Invalid form", body = ApiError),
+        (status = 401, description = "Wrong code, or not logged in", body = ApiError),
+        (status = 403, description = "Required for admins", body = ApiError),
+        (status = 429, description = "Too many wrong codes; codes are refused for 15 minutes", body = ApiError),
+    ))]
+    pub async fn disable_two_factor(session: Session, meta: RequestMeta, Json(payload): Json<TwoFactorCodeForm>) -> Result<StatusCode, Response> {
+        disable(&session, &meta, payload).await.map(|_| StatusCode::NO_CONTENT).map_err(auth_error_response)
+    }
+}
@@ -0,0 +1,166 @@
+#[cfg(feature="server")]
+pub mod Audit {
+    use std::{convert::Infallible, net::SocketAddr};
+
+    use axum::{async_trait, extract::{ConnectInfo, FromRequestParts}, http::{header, request::Parts}};
+    use bson::{doc, oid::ObjectId, to_bson, Document};
+    use chrono::Utc;
+    use dotenvy::dotenv;
+
+    use crate::backend::{forms::{AuditAction, AuditEntrySummary, AuditQuery}, mongo_crud::{MongoRepo, MongoRepoError}, mongo_models::Docs::AuditEntry, AUDIT_LOG};
+
+    const DEFAULT_PAGE_SIZE: u64 = 50;
+    const MAX_PAGE_SIZE: u64 = 200;
+    pub const MAX_EXPORT_ROWS: i64 = 10_000;
+    /// User agent of the entries `freelance-admin` records, which have no actor.
+    pub const CLI_ACTOR: &str = "cli";
+
+    /// Client address and user agent of the request being audited.
+    /// `X-Forwarded-For` is only honoured when `TRUST_FORWARDED_FOR=true`, i.e. behind a proxy that sets it.
+    #[derive(Clone, Debug, Default)]
+    pub struct RequestMeta {
+        pub ip: Option<String>,
+        pub user_agent: Option<String>,
+    }
+
+    fn trust_forwarded_for() -> bool {
+        dotenv().ok();
+        std::env::var("TRUST_FORWARDED_FOR").map(|v| v == "true").unwrap_or(false)
+    }
+
+    #[async_trait]
+    impl<S: Send + Sync> FromRequestParts<S> for RequestMeta {
+        type Rejection = Infallible;
+
+        async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+            let forwarded = if trust_forwarded_for() {
+                parts.headers.get("x-forwarded-for")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.split(',').next())
+                    .map(|v| v.trim().to_string())
+            } else {
+                None
+            };
+            let ip = forwarded.or_else(|| parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|c| c.0.ip().to_string()));
+            let user_agent = parts.headers.get(header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
+            Ok(RequestMeta { ip, user_agent })
+        }
+    }
+
+    pub async fn get_audit_repo() -> MongoRepo<AuditEntry> {
+        MongoRepo::<AuditEntry>::new("sample_mflix", AUDIT_LOG).await.unwrap()
+    }
+
+    /// Appends an entry to the audit log. The log is insert-only; nothing in the application updates or deletes entries.
+    /// A failed write is reported but never fails the action being audited.
+    pub async fn record(action: AuditAction, actor: Option<ObjectId>, target: Option<String>, meta: &RequestMeta, details: Option<String>) {
+        let entry = AuditEntry {
+            id: None,
+            action,
+            actor,
+            target,
+            ip: meta.ip.clone(),
+            user_agent: meta.user_agent.clone(),
+            details,
+            created: Utc::now(),
+        };
+        if let Err(e) = get_audit_repo().await.create(entry).await {
+            tracing::error!(?action, error = %e, "Failed to write audit entry");
+        }
+    }
+
+    /// Phone numbers reduced to their last three digits, enough to correlate failed logins without storing the number.
+    pub fn mask_phone_number(phone_number: &str) -> String {
+        let digits = phone_number.chars().filter(|c| c.is_ascii_digit()).collect::<Vec<char>>();
+        let tail = digits[digits.len().saturating_sub(3)..].iter().collect::<String>();
+        format!("***{}", tail)
+    }
+
+    pub fn to_summary(entry: &AuditEntry) -> AuditEntrySummary {
+        AuditEntrySummary {
+            id: entry.id.map(|id| id.to_hex()).unwrap_or_default(),
+            action: entry.action,
+            actor_id: entry.actor.map(|id| id.to_hex()),
+            target: entry.target.clone(),
+            ip: entry.ip.clone(),
+            user_agent: entry.user_agent.clone(),
+            details: entry.details.clone(),
+            created: entry.created,
+        }
+    }
+
+    pub fn page_size(query: &AuditQuery) -> u64 {
+        query.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    fn query_filter(query: &AuditQuery) -> Result<Document, MongoRepoError> {
+        let mut filter = Document::new();
+        if let Some(action) = &query.action {
+            filter.insert("action", to_bson(action).map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?);
+        }
+        if let Some(actor) = query.actor.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+            let actor = ObjectId::parse_str(actor).map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+            filter.insert("actor", actor);
+        }
+        if let Some(target) = query.target.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            filter.insert("target", target);
+        }
+        let mut created = Document::new();
+        if let Some(from) = query.from {
+            created.insert("$gte", bson::DateTime::from_chrono(from));
+        }
+        if let Some(to) = query.to {
+            created.insert("$lt", bson::DateTime::from_chrono(to));
+        }
+        if !created.is_empty() {
+            filter.insert("created", created);
+        }
+        Ok(filter)
+    }
+
+    pub async fn list(query: &AuditQuery) -> Result<(Vec<AuditEntry>, u64), MongoRepoError> {
+        let col = get_audit_repo().await;
+        let filter = query_filter(query)?;
+        let per_page = page_size(query);
+        let page = query.page.unwrap_or(0);
+        let total = col.count(filter.clone()).await.map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        let entries = col.find_many(filter, Some(doc! { "created": -1 }), Some(page * per_page), Some(per_page as i64))
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        Ok((entries, total))
+    }
+
+    fn csv_field(value: &str) -> String {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Every entry matching `query`, newest first and capped at `MAX_EXPORT_ROWS`, as CSV.
+    pub async fn export_csv(query: &AuditQuery) -> Result<String, MongoRepoError> {
+        let col = get_audit_repo().await;
+        let entries = col.find_many(query_filter(query)?, Some(doc! { "created": -1 }), None, Some(MAX_EXPORT_ROWS))
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        let mut csv = String::from("id,created,action,actor,target,ip,user_agent,details\n");
+        for entry in entries.iter().map(to_summary) {
+            let row = [
+                entry.id,
+                entry.created.to_rfc3339(),
+                entry.action.as_str().to_string(),
+                entry.actor_id.unwrap_or_default(),
+                entry.target.unwrap_or_default(),
+                entry.ip.unwrap_or_default(),
+                entry.user_agent.unwrap_or_default(),
+                entry.details.unwrap_or_default(),
+            ];
+            csv.push_str(&row.iter().map(|v| csv_field(v)).collect::<Vec<String>>().join(","));
+            csv.push('\n');
+        }
+        Ok(csv)
+    }
+}
@@ -9,8 +9,17 @@ pub mod AuthSession {
     use dioxus::prelude::Context;
     use tokio::task::JoinHandle;
     use tower_sessions_mongodb_store::{mongodb::Client, mongodb::options::ClientOptions ,MongoDBStore};
-    use tower_sessions::{cookie::time::Duration, Expiry, MemoryStore, Session, SessionManagerLayer};
+    use std::sync::LazyLock;
+
+    use tower_sessions::{cookie::{time::Duration, Cookie, SameSite}, Expiry, Session, SessionManagerLayer};
     use tower_sessions_core::ExpiredDeletion;
+    use bson::{doc, oid::ObjectId, Document};
+    use chrono::Utc;
+
+    use crate::backend::{api::routes::Routes, forms::SessionSummary, mongo_crud::{MongoRepo, MongoRepoError}, mongo_models::Docs::UserSession, shutdown::Shutdown, USER_SESSIONS};
+
+    const SESSION_DATABASE: &str = "tower-sessions";
+    const SESSION_COLLECTION: &str = "sessions";
 
 
     #[derive(Debug, Error)]
@@ -19,6 +28,57 @@ pub mod AuthSession {
         DatabaseUrlError(String),
     }
 
+    /// Attributes of the session and CSRF cookies, read once from the environment:
+    /// - `SESSION_COOKIE_NAME`, `id` by default
+    /// - `COOKIE_SECURE`, `true` by default; browsers accept secure cookies from `localhost`, except Safari
+    /// - `COOKIE_SAME_SITE`, one of `strict`, `lax` (default) or `none`; clients on another site need `none`
+    /// - `COOKIE_DOMAIN`, unset by default so that cookies stay on the host that set them
+    #[derive(Clone, Debug)]
+    pub struct CookieConfig {
+        pub session_name: String,
+        pub secure: bool,
+        pub same_site: SameSite,
+        pub domain: Option<String>,
+    }
+
+    static COOKIE_CONFIG: LazyLock<CookieConfig> = LazyLock::new(CookieConfig::from_env);
+
+    impl CookieConfig {
+        pub fn from_env() -> CookieConfig {
+            dotenv().ok();
+            let var = |name: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty());
+            let same_site = match var("COOKIE_SAME_SITE").map(|v| v.to_ascii_lowercase()).as_deref() {
+                Some("strict") => SameSite::Strict,
+                Some("none") => SameSite::None,
+                _ => SameSite::Lax,
+            };
+            CookieConfig {
+                session_name: var("SESSION_COOKIE_NAME").unwrap_or_else(|| Routes::SESSION_COOKIE.to_string()),
+                secure: var("COOKIE_SECURE").map(|v| v != "false").unwrap_or(true),
+                same_site,
+                domain: var("COOKIE_DOMAIN"),
+            }
+        }
+
+        pub fn get() -> &'static CookieConfig {
+            &COOKIE_CONFIG
+        }
+
+        /// A cookie with these attributes that scripts can read.
+        pub fn script_cookie(&self, name: &'static str, value: String) -> Cookie<'static> {
+            let mut cookie = Cookie::build((name, value))
+                .path("/")
+                .secure(self.secure)
+                .same_site(self.same_site)
+                .http_only(false)
+                .build();
+            if let Some(domain) = &self.domain {
+                cookie.set_domain(domain.clone());
+            }
+            cookie
+        }
+    }
+
     pub struct SessionData{
         pub layer: SessionManagerLayer<MongoDBStore>,
         pub deletion_task: JoinHandle<Result<(),tower_sessions_core::session_store::Error>>
@@ -33,18 +93,136 @@ pub mod AuthSession {
 
     pub async fn create_app_session() -> SessionData {
         let client = create_mongodb_client().await;
-        let session_store = MongoDBStore::new(client, "tower-sessions".to_string());
-        let deletion_task = tokio::task::spawn(
-            session_store
-                .clone()
-                .continuously_delete_expired(tokio::time::Duration::from_secs(60)),
-        );
-        let session_layer = SessionManagerLayer::new(session_store)
-        .with_secure(false)
+        let session_store = MongoDBStore::new(client, SESSION_DATABASE.to_string());
+        let deletion = session_store.clone().continuously_delete_expired(tokio::time::Duration::from_secs(60));
+        let deletion_task = tokio::task::spawn(async move {
+            tokio::select! {
+                result = deletion => result,
+                _ = Shutdown::requested() => Ok(()),
+            }
+        });
+        let cookies = CookieConfig::get();
+        let mut session_layer = SessionManagerLayer::new(session_store)
+        .with_name(cookies.session_name.clone())
+        .with_secure(cookies.secure)
+        .with_same_site(cookies.same_site)
+        .with_http_only(true)
         .with_expiry(Expiry::OnInactivity(Duration::hours(1)));
+        if let Some(domain) = &cookies.domain {
+            session_layer = session_layer.with_domain(domain.clone());
+        }
         SessionData {
             layer: session_layer.to_owned(),
             deletion_task: deletion_task
         }
     }
-}
\ No newline at end of file
+
+    async fn get_user_sessions_repo() -> MongoRepo<UserSession> {
+        MongoRepo::<UserSession>::new("sample_mflix", USER_SESSIONS).await.unwrap()
+    }
+
+    async fn get_session_records_repo() -> MongoRepo<Document> {
+        MongoRepo::<Document>::new(SESSION_DATABASE, SESSION_COLLECTION).await.unwrap()
+    }
+
+    /// Saves the session so that it has an id and remembers it as belonging to `user`.
+    pub async fn record_user_session(session: &Session, user: ObjectId) -> Result<(), MongoRepoError> {
+        session.save().await.map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        let session_id = session.id().ok_or(MongoRepoError::UnexpectedError("Session has no id".to_string()))?;
+        let record = UserSession {
+            id: None,
+            session_id: session_id.to_string(),
+            user,
+            created: Utc::now(),
+        };
+        get_user_sessions_repo().await.create(record).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Sessions of `user` that still exist in the session store; stale links are dropped on the way.
+    pub async fn list_user_sessions(user: &ObjectId) -> Result<Vec<SessionSummary>, MongoRepoError> {
+        let links = get_user_sessions_repo().await;
+        let records = get_session_records_repo().await;
+        let known = links.find_many(doc! { "user": user }, Some(doc! { "created": -1 }), None, None)
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        let ids = known.iter().map(|s| s.session_id.clone()).collect::<Vec<String>>();
+        let alive = records.find_many(doc! { "_id": { "$in": &ids }, "expireAt": { "$gt": bson::DateTime::now() } }, None, None, None)
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?
+            .iter()
+            .filter_map(|record| record.get_str("_id").ok().map(|id| id.to_string()))
+            .collect::<Vec<String>>();
+        links.delete_many(doc! { "user": user, "session_id": { "$nin": &alive } })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(known
+            .into_iter()
+            .filter(|s| alive.contains(&s.session_id))
+            .map(|s| SessionSummary { session_id: s.session_id, created: s.created })
+            .collect())
+    }
+
+    /// Deletes every session of `user` from the store, logging them out everywhere.
+    pub async fn revoke_user_sessions(user: &ObjectId) -> Result<u64, MongoRepoError> {
+        let links = get_user_sessions_repo().await;
+        let ids = links.find_many(doc! { "user": user }, None, None, None)
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?
+            .into_iter()
+            .map(|s| s.session_id)
+            .collect::<Vec<String>>();
+        let revoked = get_session_records_repo().await
+            .delete_many(doc! { "_id": { "$in": &ids } })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        links.delete_many(doc! { "user": user }).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(revoked)
+    }
+
+    /// Deletes every session of `user` but `keep`, logging them out on their other devices.
+    pub async fn revoke_other_sessions(user: &ObjectId, keep: &Session) -> Result<u64, MongoRepoError> {
+        let keep = keep.id().map(|id| id.to_string()).unwrap_or_default();
+        let links = get_user_sessions_repo().await;
+        let ids = links.find_many(doc! { "user": user, "session_id": { "$ne": &keep } }, None, None, None)
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?
+            .into_iter()
+            .map(|s| s.session_id)
+            .collect::<Vec<String>>();
+        let revoked = get_session_records_repo().await
+            .delete_many(doc! { "_id": { "$in": &ids } })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        links.delete_many(doc! { "user": user, "session_id": { "$ne": &keep } }).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(revoked)
+    }
+
+    /// Number of unexpired sessions in the store.
+    pub async fn count_active_sessions() -> Result<u64, MongoRepoError> {
+        get_session_records_repo().await
+            .count(doc! { "expireAt": { "$gt": bson::DateTime::now() } })
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))
+    }
+
+    /// Deletes expired sessions from the store and the user links pointing at sessions that no longer exist.
+    /// Returns the number of sessions and links removed.
+    pub async fn purge_expired_sessions() -> Result<(u64, u64), MongoRepoError> {
+        let records = get_session_records_repo().await;
+        let sessions = records.delete_many(doc! { "expireAt": { "$lt": bson::DateTime::now() } })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        let alive = records.find_many(Document::new(), None, None, None)
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?
+            .iter()
+            .filter_map(|record| record.get_str("_id").ok().map(|id| id.to_string()))
+            .collect::<Vec<String>>();
+        let links = get_user_sessions_repo().await
+            .delete_many(doc! { "session_id": { "$nin": &alive } })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok((sessions, links))
+    }
+}
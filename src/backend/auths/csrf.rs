@@ -0,0 +1,74 @@
+/// Cookie carrying the CSRF token. Scripts of the app read it to echo the token back in `CSRF_HEADER`.
+pub const CSRF_COOKIE: &str = "csrf_token";
+/// Header that state-changing `/api` requests must carry, equal to the `CSRF_COOKIE` value.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+#[cfg(feature = "server")]
+pub mod Csrf {
+    use axum::{extract::Request, http::{header, HeaderValue, Method, StatusCode}, middleware::Next, response::{IntoResponse, Response}, Json};
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use rand::RngCore;
+    use tower_sessions::cookie::Cookie;
+
+    use crate::backend::{auths::{auth_session::AuthSession::CookieConfig, csrf::{CSRF_COOKIE, CSRF_HEADER}}, forms::{ApiError, CsrfToken}, security_headers::SecurityHeaders::CSP_REPORT_PATH};
+
+    /// Endpoints browsers post to by themselves, unable to add the header.
+    const EXEMPT_PATHS: &[&str] = &[CSP_REPORT_PATH];
+
+    fn new_token() -> String {
+        let mut bytes = [0u8; 32];
+        rand::rng().fill_bytes(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn request_token(request: &Request) -> Option<String> {
+        request.headers().get_all(header::COOKIE).iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(Cookie::split_parse)
+            .filter_map(Result::ok)
+            .find(|cookie| cookie.name() == CSRF_COOKIE)
+            .map(|cookie| cookie.value().to_string())
+            .filter(|token| !token.is_empty())
+    }
+
+    /// Compares without returning early, so that response timing does not reveal how much of a guess was right.
+    fn tokens_match(expected: &str, given: &str) -> bool {
+        expected.len() == given.len() && expected.bytes().zip(given.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+
+    fn requires_token(request: &Request) -> bool {
+        let safe = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE);
+        let path = request.uri().path();
+        !safe && path.starts_with("/api/") && !EXEMPT_PATHS.contains(&path)
+    }
+
+    /// Double-submit CSRF protection. Requests under `/api` other than GET, HEAD, OPTIONS and TRACE are refused
+    /// with 403 unless `CSRF_HEADER` repeats the `CSRF_COOKIE` value, which a page on another site can neither read
+    /// nor send. Every response carries the token in `CSRF_HEADER`, and browsers without the cookie are given one.
+    pub async fn protect(mut request: Request, next: Next) -> Response {
+        let existing = request_token(&request);
+        let token = existing.clone().unwrap_or_else(new_token);
+        let mut response = if requires_token(&request) && !existing.as_deref().is_some_and(|expected| {
+            let given = request.headers().get(CSRF_HEADER).and_then(|value| value.to_str().ok()).unwrap_or_default();
+            tokens_match(expected, given)
+        }) {
+            tracing::warn!(path = %request.uri().path(), "Refused request without a valid CSRF token");
+            let error = ApiError::new(StatusCode::FORBIDDEN.as_u16(), "Missing or invalid CSRF token");
+            (StatusCode::FORBIDDEN, Json(error)).into_response()
+        } else {
+            request.extensions_mut().insert(CsrfToken { token: token.clone() });
+            next.run(request).await
+        };
+
+        if let Ok(value) = HeaderValue::from_str(&token) {
+            response.headers_mut().insert(CSRF_HEADER, value);
+        }
+        if existing.is_none() {
+            let cookie = CookieConfig::get().script_cookie(CSRF_COOKIE, token);
+            if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+        }
+        response
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(feature = "server")]
+pub mod Guards {
+    use axum::{extract::Request, http::StatusCode, middleware::Next, response::{IntoResponse, Response}, Json};
+    use tower_sessions::Session;
+
+    use crate::backend::{api::api::{Api::SessionUser, CURRENT_USER_KEY}, forms::{AccountStatus, ApiError, Forms::UserRole}, mongo_crud::MongoRepoError, mongo_models::Docs::BaseUser, telemetry::Telemetry, two_factor::TwoFactor, users};
+
+    /// The logged in user, reloaded from the database so that role and status changes apply to existing sessions.
+    pub async fn current_user(session: &Session) -> Result<BaseUser, StatusCode> {
+        let SessionUser { id, .. } = session.get::<SessionUser>(CURRENT_USER_KEY).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        Telemetry::record_user(&id);
+        let user = users::Users::get_user(&id).await.map_err(|e| match e {
+            MongoRepoError::NotFoundError(_) => StatusCode::UNAUTHORIZED,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        })?;
+        if user.status.is_active() {
+            Ok(user)
+        } else {
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+
+    /// The logged in user if their role is one of `roles`. Roles that require two-factor authentication are
+    /// refused until it is enabled, which covers members promoted while logged in.
+    pub async fn require_role(session: &Session, roles: &[UserRole]) -> Result<BaseUser, StatusCode> {
+        let user = current_user(session).await?;
+        if !roles.contains(&user.role) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        if TwoFactor::required_for(&user.role) {
+            let id = user.id.ok_or(StatusCode::UNAUTHORIZED)?;
+            if !TwoFactor::is_enabled(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+                return Err(StatusCode::FORBIDDEN);
+            }
+        }
+        Ok(user)
+    }
+
+    /// Response sent to a suspended or banned user, carrying the status so the client can explain it.
+    pub fn account_inactive_response(status: AccountStatus) -> Response {
+        let error = ApiError {
+            account_status: Some(status),
+            ..ApiError::new(StatusCode::FORBIDDEN.as_u16(), "Account is not active")
+        };
+        (StatusCode::FORBIDDEN, Json(error)).into_response()
+    }
+
+    /// Middleware ending the session of a user whose account stopped being active since they logged in.
+    /// Applies to every request under the layer, WebSocket upgrades included.
+    pub async fn enforce_account_status(session: Session, request: Request, next: Next) -> Response {
+        let id = match session.get::<SessionUser>(CURRENT_USER_KEY).await {
+            Ok(Some(user)) => user.id,
+            Ok(None) => return next.run(request).await,
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+        Telemetry::record_user(&id);
+        match users::Users::get_user(&id).await {
+            Ok(user) if !user.status.is_active() => {
+                if session.flush().await.is_err() {
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+                account_inactive_response(user.status)
+            },
+            Ok(_) | Err(MongoRepoError::NotFoundError(_)) => next.run(request).await,
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+}
@@ -1,3 +1,3 @@
-
-
-pub mod auth_session;
\ No newline at end of file
+pub mod auth_session;
+pub mod guards;
+pub mod csrf;
\ No newline at end of file
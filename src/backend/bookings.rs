@@ -0,0 +1,52 @@
+#[cfg(feature="server")]
+pub mod Bookings {
+    use std::collections::HashMap;
+
+    use bson::{doc, oid::ObjectId};
+    use crate::backend::{forms::BookingSummary, mongo_crud::{MongoRepo, MongoRepoError}, mongo_models::Docs::{Booking, Listing}, BOOKINGS, LISTINGS};
+
+    /// Bookings shown with a member on the admin console.
+    const RECENT_BOOKINGS: i64 = 50;
+
+    pub async fn get_bookings_repo() -> MongoRepo<Booking> {
+        MongoRepo::<Booking>::new("sample_mflix", BOOKINGS).await.unwrap()
+    }
+
+    async fn get_listings_repo() -> MongoRepo<Listing> {
+        MongoRepo::<Listing>::new("sample_mflix", LISTINGS).await.unwrap()
+    }
+
+    pub fn to_summary(booking: &Booking, listing_title: Option<String>) -> BookingSummary {
+        BookingSummary {
+            id: booking.id.map(|id| id.to_hex()).unwrap_or_default(),
+            listing_id: booking.listing.to_hex(),
+            listing_title,
+            buyer_id: booking.buyer.to_hex(),
+            seller_id: booking.seller.to_hex(),
+            scheduled: booking.scheduled,
+            status: booking.status,
+            created: booking.created,
+        }
+    }
+
+    /// Latest bookings `user` made as a buyer or received as a seller, most recently scheduled first.
+    pub async fn list_by_member(user: &ObjectId) -> Result<Vec<Booking>, MongoRepoError> {
+        let col = get_bookings_repo().await;
+        col.find_many(doc! { "$or": [{ "buyer": user }, { "seller": user }] }, Some(doc! { "scheduled": -1 }), None, Some(RECENT_BOOKINGS))
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))
+    }
+
+    /// `bookings` as summaries, with the titles of their listings looked up at once.
+    pub async fn summarize(bookings: &[Booking]) -> Result<Vec<BookingSummary>, MongoRepoError> {
+        let ids: Vec<ObjectId> = bookings.iter().map(|booking| booking.listing).collect();
+        let titles: HashMap<ObjectId, String> = get_listings_repo().await
+            .find_many(doc! { "_id": { "$in": ids } }, None, None, None)
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?
+            .into_iter()
+            .filter_map(|listing| Some((listing.id?, listing.title)))
+            .collect();
+        Ok(bookings.iter().map(|booking| to_summary(booking, titles.get(&booking.listing).cloned())).collect())
+    }
+}
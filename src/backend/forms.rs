@@ -1,13 +1,25 @@
 use dioxus::signals::Signal;
 use serde::Serialize;
 use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use validator::{Validate, ValidationError};
+
+use crate::backend::phone::{validate_phone_field, PhoneNumberDetails};
 
 
 #[derive(Serialize,Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct Token {
     token: String
 }
 
+/// Token state-changing API requests echo in the `X-CSRF-Token` header.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct CsrfToken {
+    pub token: String,
+}
+
 impl Token {
     pub fn get_token(&self) -> String {
         self.token.clone()
@@ -20,18 +32,474 @@ impl Token {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub enum UserRole {
+    Buyer,
+    Seller,
+    Admin,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub enum AccountStatus {
+    #[default]
+    Active,
+    Suspended { until: DateTime<Utc>, reason: String },
+    Banned { reason: String },
+}
+
+impl AccountStatus {
+    /// Whether the account may sign in at `now`; a suspension lifts itself once `until` has passed.
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        match self {
+            AccountStatus::Active => true,
+            AccountStatus::Suspended { until, .. } => *until <= now,
+            AccountStatus::Banned { .. } => false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.is_active_at(Utc::now())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub enum StorefrontStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A member as returned to themselves by the REST API and the server functions. Fields are picked explicitly so that
+/// stored credentials never leave the server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct UserView {
+    pub id: String,
+    pub role: UserRole,
+    pub status: AccountStatus,
+    pub created: DateTime<Utc>,
+}
+
+/// Second step a login waits for after the phone number was accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub enum TwoFactorStep {
+    /// Enter a code from the authenticator app, or a recovery code.
+    Verify,
+    /// The role requires two-factor authentication, which must be set up before the login completes.
+    Enroll,
+}
+
+/// A 6-digit code from the authenticator app, or one of the recovery codes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct TwoFactorCodeForm {
+    #[validate(length(min = 6, max = 20, message = "Enter the 6-digit code or a recovery code"))]
+    pub code: String,
+}
+
+/// A new TOTP secret, to be confirmed with a code before it protects the account.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct TwoFactorEnrollment {
+    /// Base32 secret, for typing into an authenticator app.
+    pub secret: String,
+    /// `otpauth://` URI, for rendering as a QR code.
+    pub otpauth_uri: String,
+}
+
+/// Single-use codes standing in for the authenticator app. Shown once; only their hashes are kept.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct RecoveryCodes {
+    pub codes: Vec<String>,
+}
+
+/// A passkey of the logged in member, as listed on their account.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct PasskeySummary {
+    /// Credential id, base64url encoded.
+    pub id: String,
+    pub name: String,
+    pub created: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// Answer of the browser to the challenge of `/passkeys/register/start`, with a name telling the passkey apart.
+#[derive(Clone, Debug, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct NewPasskeyForm {
+    #[validate(length(min = 1, max = 64, message = "Name the passkey, e.g. \"Work laptop\""))]
+    pub name: String,
+    /// `PublicKeyCredential` returned by `navigator.credentials.create()`.
+    #[cfg_attr(feature = "server", schema(value_type = Object))]
+    pub credential: webauthn_rs_proto::RegisterPublicKeyCredential,
+}
+
+/// Answer of the browser to the challenge of `/login/passkey/start`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct PasskeyLoginForm {
+    /// `PublicKeyCredential` returned by `navigator.credentials.get()`.
+    #[cfg_attr(feature = "server", schema(value_type = Object))]
+    pub credential: webauthn_rs_proto::PublicKeyCredential,
+}
+
+/// New phone number for the logged in member, who then receives a code on it and on the current one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct PhoneChangeForm {
+    #[validate(custom(function = "validate_phone_field", message = "Enter a valid mobile number"))]
+    pub phone_number: String,
+}
+
+/// Where the codes of a phone number change were sent, the numbers masked.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct PhoneChangeChallenge {
+    pub current_phone_number: String,
+    pub new_phone_number: String,
+    /// Whether a two-factor recovery code may stand in for the code sent to the current number.
+    pub recovery_code_allowed: bool,
+    pub expires: DateTime<Utc>,
+}
+
+/// Second step of a phone number change: the code sent to the new number, and proof of the current one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ConfirmPhoneChangeForm {
+    /// The 6-digit code sent to the current number, or a two-factor recovery code when it is lost.
+    #[validate(length(min = 6, max = 20, message = "Enter the code sent to your current number or a recovery code"))]
+    pub current_code: String,
+    #[validate(length(equal = 6, message = "Enter the 6-digit code sent to your new number"))]
+    pub new_code: String,
+}
+
+/// Phone number an admin gives a member who lost theirs, once their identity was checked some other way.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct RecoverPhoneNumberForm {
+    #[validate(custom(function = "validate_phone_field", message = "Enter a valid mobile number"))]
+    pub phone_number: String,
+    /// How the member's identity was checked, kept in the audit log.
+    #[validate(length(min = 1, max = 500, message = "Explain how the member's identity was checked"))]
+    pub reason: String,
+}
+
+/// A member as listed in the admin console, with their phone number. Members get `UserView` instead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct UserSummary {
+    pub id: String,
+    pub phone_number: String,
+    /// Country, kind and network of the phone number, when it is a valid one.
+    pub phone_details: Option<PhoneNumberDetails>,
+    pub role: UserRole,
+    pub status: AccountStatus,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct UsersPage {
+    pub users: Vec<UserSummary>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct UsersQuery {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+    pub search: Option<String>,
+    pub role: Option<UserRole>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct StorefrontSummary {
+    pub id: String,
+    pub owner_id: String,
+    pub name: String,
+    pub description: String,
+    pub status: StorefrontStatus,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub enum BookingStatus {
+    Pending,
+    Confirmed,
+    Completed,
+    Cancelled,
+}
+
+/// A booking as listed to admins, with the title of the listing it is for.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct BookingSummary {
+    pub id: String,
+    pub listing_id: String,
+    /// `None` when the listing was deleted.
+    pub listing_title: Option<String>,
+    pub buyer_id: String,
+    pub seller_id: String,
+    pub scheduled: DateTime<Utc>,
+    pub status: BookingStatus,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct UserDetail {
+    pub user: UserSummary,
+    pub sessions: Vec<SessionSummary>,
+    pub storefronts: Vec<StorefrontSummary>,
+    /// Latest bookings the member made as a buyer or received as a seller.
+    pub bookings: Vec<BookingSummary>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ChangeRoleForm {
+    pub role: UserRole,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct SuspendForm {
+    pub until: DateTime<Utc>,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct BanForm {
+    pub reason: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub enum AuditAction {
+    Login,
+    LoginFailed,
+    Registration,
+    RoleChanged,
+    AccountSuspended,
+    AccountBanned,
+    AccountReactivated,
+    StorefrontApproved,
+    StorefrontRejected,
+    DataExported,
+    DataImported,
+    TwoFactorEnabled,
+    TwoFactorDisabled,
+    PasskeyAdded,
+    PasskeyRemoved,
+    PhoneNumberChanged,
+    PhoneNumberRecovered,
+}
+
+impl AuditAction {
+    pub const ALL: [AuditAction; 17] = [
+        AuditAction::Login,
+        AuditAction::LoginFailed,
+        AuditAction::Registration,
+        AuditAction::RoleChanged,
+        AuditAction::AccountSuspended,
+        AuditAction::AccountBanned,
+        AuditAction::AccountReactivated,
+        AuditAction::StorefrontApproved,
+        AuditAction::StorefrontRejected,
+        AuditAction::DataExported,
+        AuditAction::DataImported,
+        AuditAction::TwoFactorEnabled,
+        AuditAction::TwoFactorDisabled,
+        AuditAction::PasskeyAdded,
+        AuditAction::PasskeyRemoved,
+        AuditAction::PhoneNumberChanged,
+        AuditAction::PhoneNumberRecovered,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Login => "Login",
+            AuditAction::LoginFailed => "LoginFailed",
+            AuditAction::Registration => "Registration",
+            AuditAction::RoleChanged => "RoleChanged",
+            AuditAction::AccountSuspended => "AccountSuspended",
+            AuditAction::AccountBanned => "AccountBanned",
+            AuditAction::AccountReactivated => "AccountReactivated",
+            AuditAction::StorefrontApproved => "StorefrontApproved",
+            AuditAction::StorefrontRejected => "StorefrontRejected",
+            AuditAction::DataExported => "DataExported",
+            AuditAction::DataImported => "DataImported",
+            AuditAction::TwoFactorEnabled => "TwoFactorEnabled",
+            AuditAction::TwoFactorDisabled => "TwoFactorDisabled",
+            AuditAction::PasskeyAdded => "PasskeyAdded",
+            AuditAction::PasskeyRemoved => "PasskeyRemoved",
+            AuditAction::PhoneNumberChanged => "PhoneNumberChanged",
+            AuditAction::PhoneNumberRecovered => "PhoneNumberRecovered",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AuditEntrySummary {
+    pub id: String,
+    pub action: AuditAction,
+    pub actor_id: Option<String>,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: Option<String>,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::IntoParams), into_params(parameter_in = Query))]
+pub struct AuditQuery {
+    pub action: Option<AuditAction>,
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AuditPage {
+    pub entries: Vec<AuditEntrySummary>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+}
+
+fn validate_role(role: &UserRole) -> Result<(), ValidationError> {
+    match role {
+        UserRole::Buyer | UserRole::Seller => Ok(()),
+        _ => Err(ValidationError::new("invalid_role")),
+    }
+}
+
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq,Eq,Hash,Validate)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct AuthUserForm {
+    #[validate(custom(function = "validate_phone_field", message = "Enter a valid mobile number"))]
+    password: String,
+    #[validate(custom(function = "validate_role", message = "Role must be either Buyer or Seller"))]
+    role: Option<UserRole>
+}
+
+impl AuthUserForm {
+    pub fn create_buyer(password: String) -> AuthUserForm {
+        AuthUserForm {
+            password: password,
+            role: Some(UserRole::Buyer)
+        }
+    }
+
+    pub fn new(password:String) -> AuthUserForm {
+        AuthUserForm { password: password, role: None }
+    }
+
+    pub fn get_password(&self) -> String {
+        self.password.clone()
+    }
+    pub fn get_role(&self) -> Option<UserRole> {
+        self.role.clone()
+    }
+}
+
+/// Why an auth server function failed. It crosses the wire as JSON through `Display`/`FromStr`,
+/// which is how `ServerFnError` carries custom errors.
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+pub enum AuthError {
+    InvalidForm,
+    AlreadyRegistered,
+    NotFound,
+    Unauthenticated,
+    AccountInactive(AccountStatus),
+    /// The phone number was accepted and the login waits for this step.
+    TwoFactorRequired(TwoFactorStep),
+    InvalidTwoFactorCode,
+    /// Too many wrong two-factor codes were entered for the member; no code is checked for a while.
+    TwoFactorLocked,
+    Forbidden,
+    TwoFactorAlreadyEnabled,
+    /// The passkey is unknown, or its signature, challenge or counter did not check out.
+    InvalidPasskey,
+    TooManyPasskeys,
+    /// A code of a phone number change was wrong, or the change expired.
+    InvalidVerificationCode,
+    /// The member started phone number changes too often; no codes were sent.
+    TooManyPhoneChanges,
+    Internal,
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", serde_json::to_string(self).map_err(|_| std::fmt::Error)?)
+    }
+}
+
+impl std::str::FromStr for AuthError {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Body of every failed REST API response.
+#[derive(Serialize,Deserialize,Debug,Clone,PartialEq)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+    /// Set when the request was refused because the member is suspended or banned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_status: Option<AccountStatus>,
+    /// Set when a login needs a second factor before it completes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub two_factor: Option<TwoFactorStep>,
+}
+
+impl ApiError {
+    pub fn new(status: u16, message: impl Into<String>) -> ApiError {
+        ApiError { status, message: message.into(), account_status: None, two_factor: None }
+    }
+}
+
 use dioxus::hooks::Resource;
+use dioxus::prelude::ServerFnError;
 
 #[derive(Clone)]
 pub struct ResourceValues {
-    pub is_authenticated: Resource<Result<Token,String>>,
+    pub is_authenticated: Resource<Result<Token,ServerFnError<AuthError>>>,
     pub theme_mode: Signal<String>
 }
 
 #[cfg(feature="server")] 
 pub mod Forms {
     use validator::Validate;
-    use validator::ValidationError;
     use serde::Serialize;
     use serde::Deserialize;
 
@@ -40,45 +508,13 @@ pub mod Forms {
     //     token: String
     // }
 
-    #[derive(Clone, Debug,Serialize,Deserialize)]
-    pub enum UserRole {
-        Buyer,
-        Seller,
-    }
-
-    fn validate_role(role: &UserRole) -> Result<(), ValidationError> {
-        match role {
-            UserRole::Buyer | UserRole::Seller => Ok(()),
-            _ => Err(ValidationError::new("invalid_role")),
-        }
-    }
-    
-
-    #[derive(Serialize,Deserialize,Debug,Clone, Validate)]
-    pub struct AuthUserForm {
-        #[validate(length(min = 10, max = 14, message = "Phone number must be 14 characters"))]
-        password: String,
-        #[validate(custom(function = "validate_role", message = "Role must be either Buyer or Seller"))]
-        role: Option<UserRole>
-    }
-
-    impl AuthUserForm {
-        pub fn create_buyer(password: String) -> AuthUserForm {
-            AuthUserForm {
-                password: password,
-                role: Some(UserRole::Buyer)
-            }
-        }
+    pub use super::{AuthUserForm, UserRole};
 
-        pub fn new(password:String) -> AuthUserForm {
-            AuthUserForm { password: password, role: None }
-        }
-
-        pub fn get_password(&self) -> String {
-            self.password.clone()
-        }
-        pub fn get_role(&self) -> Option<UserRole> {
-            self.role.clone()
-        }
+    #[derive(Serialize,Deserialize,Debug,Clone, Validate, utoipa::ToSchema)]
+    pub struct StorefrontForm {
+        #[validate(length(min = 3, max = 80, message = "Storefront name must be between 3 and 80 characters"))]
+        pub name: String,
+        #[validate(length(max = 2000, message = "Description must be at most 2000 characters"))]
+        pub description: String,
     }
 }
\ No newline at end of file
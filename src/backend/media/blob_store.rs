@@ -0,0 +1,93 @@
+#[cfg(feature = "server")]
+pub mod Blobs {
+    use std::future::Future;
+    use std::path::{Component, Path, PathBuf};
+
+    use dotenvy::dotenv;
+    use thiserror::Error;
+
+    const DEFAULT_MEDIA_ROOT: &str = "media";
+
+    #[derive(Debug, Error)]
+    pub enum BlobStoreError {
+        #[error("Invalid blob key: {0}")]
+        InvalidKeyError(String),
+
+        #[error("Blob storage failure: {0}")]
+        IoError(#[from] std::io::Error),
+    }
+
+    /// Storage for uploaded binary files addressed by a relative `/`-separated key.
+    /// `LocalBlobStore` writes to disk; a GridFS backed store only has to implement this trait.
+    pub trait BlobStore: Send + Sync {
+        fn put(&self, key: &str, bytes: &[u8]) -> impl Future<Output = Result<(), BlobStoreError>> + Send;
+        fn get(&self, key: &str) -> impl Future<Output = Result<Option<Vec<u8>>, BlobStoreError>> + Send;
+        fn delete(&self, key: &str) -> impl Future<Output = Result<bool, BlobStoreError>> + Send;
+    }
+
+    pub struct LocalBlobStore {
+        root: PathBuf,
+    }
+
+    impl LocalBlobStore {
+        pub fn new(root: impl Into<PathBuf>) -> Self {
+            Self { root: root.into() }
+        }
+
+        pub fn from_env() -> Self {
+            dotenv().ok();
+            let root = std::env::var("MEDIA_ROOT").unwrap_or_else(|_| DEFAULT_MEDIA_ROOT.to_string());
+            Self::new(root)
+        }
+
+        fn resolve(&self, key: &str) -> Result<PathBuf, BlobStoreError> {
+            let relative = Path::new(key);
+            let is_safe = !key.is_empty()
+                && !key.contains('\\')
+                && relative.components().all(|c| matches!(c, Component::Normal(_)));
+            if is_safe {
+                Ok(self.root.join(relative))
+            } else {
+                Err(BlobStoreError::InvalidKeyError(key.to_string()))
+            }
+        }
+    }
+
+    impl BlobStore for LocalBlobStore {
+        async fn put(&self, key: &str, bytes: &[u8]) -> Result<(), BlobStoreError> {
+            let path = self.resolve(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(path, bytes).await?;
+            Ok(())
+        }
+
+        async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BlobStoreError> {
+            let path = self.resolve(key)?;
+            match tokio::fs::read(path).await {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+
+        async fn delete(&self, key: &str) -> Result<bool, BlobStoreError> {
+            let path = self.resolve(key)?;
+            match tokio::fs::remove_file(path).await {
+                Ok(_) => Ok(true),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+
+    pub fn content_type_for(key: &str) -> &'static str {
+        match Path::new(key).extension().and_then(|e| e.to_str()) {
+            Some("jpg") | Some("jpeg") => "image/jpeg",
+            Some("png") => "image/png",
+            Some("webp") => "image/webp",
+            _ => "application/octet-stream",
+        }
+    }
+}
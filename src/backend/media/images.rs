@@ -0,0 +1,134 @@
+#[cfg(feature = "server")]
+pub mod Images {
+    use std::io::Cursor;
+
+    use image::{imageops::FilterType, DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+    use thiserror::Error;
+
+    use crate::backend::mongo_models::Docs::ImageKind;
+
+    pub const MAX_UPLOAD_BYTES: usize = 8 * 1024 * 1024;
+    const MAX_AVATAR_BYTES: usize = 2 * 1024 * 1024;
+    const MAX_DIMENSION: u32 = 8000;
+    const MAX_DECODE_ALLOC: u64 = 256 * 1024 * 1024;
+    const JPEG_QUALITY: u8 = 85;
+
+    #[derive(Debug, Error)]
+    pub enum ImageError {
+        #[error("Image exceeds {0} bytes")]
+        TooLargeError(usize),
+
+        #[error("Unsupported image type")]
+        UnsupportedTypeError,
+
+        #[error("Failed to decode image: {0}")]
+        DecodeError(String),
+
+        #[error("Failed to encode image: {0}")]
+        EncodeError(String),
+    }
+
+    pub struct ProcessedImage {
+        pub name: String,
+        pub extension: &'static str,
+        pub content_type: &'static str,
+        pub width: u32,
+        pub height: u32,
+        pub bytes: Vec<u8>,
+    }
+
+    fn max_bytes(kind: ImageKind) -> usize {
+        match kind {
+            ImageKind::Avatar => MAX_AVATAR_BYTES,
+            ImageKind::Listing | ImageKind::Portfolio => MAX_UPLOAD_BYTES,
+        }
+    }
+
+    /// Longest edge of every variant generated for `kind`, the first one being the full size image.
+    fn variant_sizes(kind: ImageKind) -> &'static [(&'static str, u32)] {
+        match kind {
+            ImageKind::Avatar => &[("original", 512), ("thumb", 128)],
+            ImageKind::Listing | ImageKind::Portfolio => &[("original", 2048), ("medium", 1024), ("thumb", 320)],
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Result<(DynamicImage, ImageFormat), ImageError> {
+        let format = image::guess_format(bytes).map_err(|_| ImageError::UnsupportedTypeError)?;
+        if !matches!(format, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP) {
+            return Err(ImageError::UnsupportedTypeError);
+        }
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(MAX_DIMENSION);
+        limits.max_image_height = Some(MAX_DIMENSION);
+        limits.max_alloc = Some(MAX_DECODE_ALLOC);
+
+        let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+        reader.limits(limits);
+        let mut decoder = reader.into_decoder().map_err(|e| ImageError::DecodeError(e.to_string()))?;
+        let orientation = decoder.orientation().map_err(|e| ImageError::DecodeError(e.to_string()))?;
+        let mut image = DynamicImage::from_decoder(decoder).map_err(|e| ImageError::DecodeError(e.to_string()))?;
+        // Metadata is dropped on re-encode, so the EXIF rotation has to be baked into the pixels first.
+        image.apply_orientation(orientation);
+        Ok((image, format))
+    }
+
+    fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+        let image = match format {
+            ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8()),
+            _ => DynamicImage::ImageRgba8(image.to_rgba8()),
+        };
+        let mut buffer = Cursor::new(Vec::new());
+        match format {
+            ImageFormat::Jpeg => image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, JPEG_QUALITY)
+                .encode_image(&image)
+                .map_err(|e| ImageError::EncodeError(e.to_string()))?,
+            _ => image
+                .write_to(&mut buffer, format)
+                .map_err(|e| ImageError::EncodeError(e.to_string()))?,
+        }
+        Ok(buffer.into_inner())
+    }
+
+    fn processed(name: &str, image: &DynamicImage, format: ImageFormat) -> Result<ProcessedImage, ImageError> {
+        let (extension, content_type) = match format {
+            ImageFormat::Jpeg => ("jpg", "image/jpeg"),
+            ImageFormat::WebP => ("webp", "image/webp"),
+            _ => ("png", "image/png"),
+        };
+        Ok(ProcessedImage {
+            name: name.to_string(),
+            extension,
+            content_type,
+            width: image.width(),
+            height: image.height(),
+            bytes: encode(image, format)?,
+        })
+    }
+
+    /// Validates an uploaded image and produces its resized variants, each as the
+    /// fallback format (JPEG for JPEG uploads, PNG otherwise) and as WebP.
+    /// Re-encoding from decoded pixels strips EXIF and any other embedded metadata.
+    pub fn process(kind: ImageKind, bytes: &[u8]) -> Result<Vec<ProcessedImage>, ImageError> {
+        let limit = max_bytes(kind);
+        if bytes.len() > limit {
+            return Err(ImageError::TooLargeError(limit));
+        }
+        let (image, format) = decode(bytes)?;
+        let fallback = match format {
+            ImageFormat::Jpeg => ImageFormat::Jpeg,
+            _ => ImageFormat::Png,
+        };
+        let mut variants = Vec::new();
+        for (index, (name, edge)) in variant_sizes(kind).iter().enumerate() {
+            let fits = image.width() <= *edge && image.height() <= *edge;
+            // Smaller variants of an image that already fits would just duplicate a larger one.
+            if fits && index > 0 {
+                continue;
+            }
+            let resized = if fits { image.clone() } else { image.resize(*edge, *edge, FilterType::Lanczos3) };
+            variants.push(processed(name, &resized, fallback)?);
+            variants.push(processed(name, &resized, ImageFormat::WebP)?);
+        }
+        Ok(variants)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod blob_store;
+pub mod images;
\ No newline at end of file
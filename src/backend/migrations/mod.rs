@@ -0,0 +1,3 @@
+pub mod runner;
+pub mod steps;
+pub mod versions;
@@ -0,0 +1,260 @@
+#[cfg(feature = "server")]
+pub mod Runner {
+    use std::time::Duration;
+
+    use bson::{doc, oid::ObjectId, Document};
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Serialize};
+    use thiserror::Error;
+    use tower_sessions_mongodb_store::mongodb::{error::{ErrorKind, WriteFailure}, options::UpdateOptions, Collection, Database};
+
+    use crate::backend::{auths::auth_session::AuthSession, migrations::versions::Versions::{self, Migration}};
+
+    const MIGRATIONS: &str = "_migrations";
+    const MIGRATIONS_LOCK: &str = "_migrations_lock";
+    const LOCK_ID: &str = "migrations";
+    /// A lock older than this is considered abandoned by a crashed instance and can be taken over.
+    const LOCK_TTL: chrono::Duration = chrono::Duration::minutes(10);
+    const LOCK_WAIT: Duration = Duration::from_secs(120);
+    const LOCK_RETRY: Duration = Duration::from_secs(2);
+
+    #[derive(Debug, Error)]
+    pub enum MigrationError {
+        #[error("Database error: {0}")]
+        DatabaseError(#[from] tower_sessions_mongodb_store::mongodb::error::Error),
+
+        #[error("Timed out waiting for the migration lock held by {0}")]
+        LockTimeoutError(String),
+
+        #[error("Migration {0} failed: {1}")]
+        StepError(u32, String),
+
+        #[error("Unknown migration version {0}")]
+        UnknownVersionError(u32),
+
+        #[error("{0}; expected up, down <version> or status")]
+        UsageError(String),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct AppliedMigration {
+        #[serde(rename = "_id")]
+        pub version: u32,
+        pub name: String,
+        #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+        pub applied: DateTime<Utc>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    struct MigrationLock {
+        #[serde(rename = "_id")]
+        id: String,
+        owner: String,
+        #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+        expires: DateTime<Utc>,
+    }
+
+    pub struct MigrationStatus {
+        pub version: u32,
+        pub name: &'static str,
+        pub applied: Option<DateTime<Utc>>,
+    }
+
+    pub async fn database() -> Database {
+        AuthSession::create_mongodb_client().await.database("sample_mflix")
+    }
+
+    fn migrations(db: &Database) -> Collection<AppliedMigration> {
+        db.collection::<AppliedMigration>(MIGRATIONS)
+    }
+
+    async fn applied(db: &Database) -> Result<Vec<AppliedMigration>, MigrationError> {
+        let mut cursor = migrations(db).find(Document::new(), None).await?;
+        let mut applied = Vec::new();
+        while cursor.advance().await? {
+            applied.push(cursor.deserialize_current()?);
+        }
+        applied.sort_by_key(|m| m.version);
+        Ok(applied)
+    }
+
+    fn is_duplicate_key(e: &tower_sessions_mongodb_store::mongodb::error::Error) -> bool {
+        matches!(e.kind.as_ref(), ErrorKind::Write(WriteFailure::WriteError(write_err)) if write_err.code == 11000)
+    }
+
+    /// Takes the lock document, or an expired one. While another instance holds it the upsert
+    /// collides with the existing `_id` and we retry until `LOCK_WAIT` runs out.
+    async fn acquire_lock(db: &Database, owner: &str) -> Result<(), MigrationError> {
+        let locks = db.collection::<MigrationLock>(MIGRATIONS_LOCK);
+        let started = tokio::time::Instant::now();
+        loop {
+            let now = Utc::now();
+            let result = locks.update_one(
+                doc! { "_id": LOCK_ID, "expires": { "$lt": bson::DateTime::from_chrono(now) } },
+                doc! { "$set": { "owner": owner, "expires": bson::DateTime::from_chrono(now + LOCK_TTL) } },
+                UpdateOptions::builder().upsert(true).build(),
+            ).await;
+            match result {
+                Ok(_) => return Ok(()),
+                Err(e) if is_duplicate_key(&e) => {
+                    if started.elapsed() >= LOCK_WAIT {
+                        let holder = locks.find_one(doc! { "_id": LOCK_ID }, None).await?
+                            .map(|lock| lock.owner)
+                            .unwrap_or_default();
+                        return Err(MigrationError::LockTimeoutError(holder));
+                    }
+                    tokio::time::sleep(LOCK_RETRY).await;
+                },
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    async fn release_lock(db: &Database, owner: &str) -> Result<(), MigrationError> {
+        db.collection::<MigrationLock>(MIGRATIONS_LOCK)
+            .delete_one(doc! { "_id": LOCK_ID, "owner": owner }, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Runs `task` while holding the migration lock, releasing it whatever the outcome. A lock that cannot be
+    /// released is only logged: it expires on its own, and the outcome of `task` is what the caller needs.
+    async fn with_lock<F, T>(db: &Database, task: F) -> Result<T, MigrationError>
+    where
+        F: std::future::Future<Output = Result<T, MigrationError>>,
+    {
+        let owner = ObjectId::new().to_hex();
+        acquire_lock(db, &owner).await?;
+        let result = task.await;
+        if let Err(e) = release_lock(db, &owner).await {
+            tracing::warn!(error = %e, "Could not release the migration lock");
+        }
+        result
+    }
+
+    async fn apply(db: &Database, migration: &Migration) -> Result<(), MigrationError> {
+        (migration.up)(db).await.map_err(|e| MigrationError::StepError(migration.version, e.to_string()))?;
+        let record = AppliedMigration { version: migration.version, name: migration.name.to_string(), applied: Utc::now() };
+        migrations(db).insert_one(record, None).await?;
+        Ok(())
+    }
+
+    async fn revert(db: &Database, migration: &Migration) -> Result<(), MigrationError> {
+        (migration.down)(db).await.map_err(|e| MigrationError::StepError(migration.version, e.to_string()))?;
+        migrations(db).delete_one(doc! { "_id": migration.version }, None).await?;
+        Ok(())
+    }
+
+    /// Applies every migration that has not run yet, in version order. Returns the versions applied.
+    pub async fn migrate_up(db: &Database) -> Result<Vec<u32>, MigrationError> {
+        with_lock(db, async {
+            let done = applied(db).await?.iter().map(|m| m.version).collect::<Vec<u32>>();
+            let mut ran = Vec::new();
+            for migration in Versions::all().iter().filter(|m| !done.contains(&m.version)) {
+                apply(db, migration).await?;
+                ran.push(migration.version);
+            }
+            Ok(ran)
+        }).await
+    }
+
+    /// Reverts applied migrations newer than `target`, newest first. Returns the versions reverted.
+    pub async fn migrate_down(db: &Database, target: u32) -> Result<Vec<u32>, MigrationError> {
+        let known = Versions::all();
+        if target != 0 && !known.iter().any(|m| m.version == target) {
+            return Err(MigrationError::UnknownVersionError(target));
+        }
+        with_lock(db, async {
+            let done = applied(db).await?.iter().map(|m| m.version).collect::<Vec<u32>>();
+            let mut reverted = Vec::new();
+            for migration in known.iter().rev().filter(|m| m.version > target && done.contains(&m.version)) {
+                revert(db, migration).await?;
+                reverted.push(migration.version);
+            }
+            Ok(reverted)
+        }).await
+    }
+
+    pub async fn status(db: &Database) -> Result<Vec<MigrationStatus>, MigrationError> {
+        let done = applied(db).await?;
+        Ok(Versions::all()
+            .iter()
+            .map(|m| MigrationStatus {
+                version: m.version,
+                name: m.name,
+                applied: done.iter().find(|a| a.version == m.version).map(|a| a.applied),
+            })
+            .collect())
+    }
+
+    /// Whether every known migration has been applied.
+    pub async fn is_up_to_date(db: &Database) -> Result<bool, MigrationError> {
+        Ok(status(db).await?.iter().all(|m| m.applied.is_some()))
+    }
+
+    /// Runs the migrations sequentially at startup unless `MIGRATE_ON_STARTUP=false`.
+    pub async fn migrate_on_startup() -> Result<(), MigrationError> {
+        dotenvy::dotenv().ok();
+        if std::env::var("MIGRATE_ON_STARTUP").map(|v| v == "false").unwrap_or(false) {
+            return Ok(());
+        }
+        let db = database().await;
+        for version in migrate_up(&db).await? {
+            tracing::info!(version, "Applied migration");
+        }
+        Ok(())
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Command {
+        Up,
+        /// Revert down to this version; `0` reverts every migration.
+        Down(u32),
+        Status,
+    }
+
+    /// Parses the arguments of `migrate`. The target of `down` is required, so that a forgotten or mistyped
+    /// version cannot revert every migration.
+    pub fn parse_cli(args: &[String]) -> Result<Command, MigrationError> {
+        match args.first().map(String::as_str) {
+            Some("up") | None => Ok(Command::Up),
+            Some("down") => match args.get(1) {
+                Some(target) => target.parse::<u32>()
+                    .map(Command::Down)
+                    .map_err(|_| MigrationError::UsageError(format!("`{}` is not a migration version", target))),
+                None => Err(MigrationError::UsageError(String::from("`migrate down` needs the version to revert to, 0 for all"))),
+            },
+            Some("status") => Ok(Command::Status),
+            Some(other) => Err(MigrationError::UsageError(format!("Unknown migrate command `{}`", other))),
+        }
+    }
+
+    /// `migrate up`, `migrate down <version>` and `migrate status` command line entry point.
+    pub async fn run_cli(args: &[String]) -> Result<(), MigrationError> {
+        let command = parse_cli(args)?;
+        let db = database().await;
+        match command {
+            Command::Up => {
+                let ran = migrate_up(&db).await?;
+                if ran.is_empty() {
+                    println!("Database is up to date");
+                }
+                for version in ran {
+                    println!("Applied migration {}", version);
+                }
+            },
+            Command::Down(target) => {
+                for version in migrate_down(&db, target).await? {
+                    println!("Reverted migration {}", version);
+                }
+            },
+            Command::Status => {
+                for migration in status(&db).await? {
+                    let applied = migration.applied.map(|a| a.to_rfc3339()).unwrap_or_else(|| String::from("pending"));
+                    println!("{:>4}  {:<32} {}", migration.version, migration.name, applied);
+                }
+            },
+        }
+        Ok(())
+    }
+}
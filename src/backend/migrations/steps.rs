@@ -0,0 +1,47 @@
+#[cfg(feature = "server")]
+pub mod Steps {
+    use bson::{doc, Document};
+    use tower_sessions_mongodb_store::mongodb::{error::Result, options::IndexOptions, Database, IndexModel};
+
+    /// Index named the way MongoDB names it by default (`field_1_other_-1`), so indexes
+    /// created before migrations existed are recognised instead of conflicting.
+    pub fn index_name(keys: &Document) -> String {
+        keys.iter()
+            .map(|(field, order)| format!("{}_{}", field, order))
+            .collect::<Vec<String>>()
+            .join("_")
+    }
+
+    pub async fn create_index(db: &Database, collection: &str, keys: Document, unique: bool) -> Result<()> {
+        let options = IndexOptions::builder().name(Some(index_name(&keys))).unique(Some(unique)).build();
+        let model = IndexModel::builder().keys(keys).options(options).build();
+        db.collection::<Document>(collection).create_index(model, None).await?;
+        Ok(())
+    }
+
+    pub async fn drop_index(db: &Database, collection: &str, keys: Document) -> Result<()> {
+        db.collection::<Document>(collection).drop_index(index_name(&keys), None).await
+    }
+
+    /// Sets `field` to `value` on every document of `collection` that does not have it yet.
+    pub async fn backfill(db: &Database, collection: &str, field: &str, value: impl Into<bson::Bson>) -> Result<u64> {
+        let result = db.collection::<Document>(collection)
+            .update_many(doc! { field: { "$exists": false } }, doc! { "$set": { field: value.into() } }, None)
+            .await?;
+        Ok(result.modified_count)
+    }
+
+    pub async fn rename_field(db: &Database, collection: &str, from: &str, to: &str) -> Result<u64> {
+        let result = db.collection::<Document>(collection)
+            .update_many(doc! { from: { "$exists": true } }, doc! { "$rename": { from: to } }, None)
+            .await?;
+        Ok(result.modified_count)
+    }
+
+    pub async fn unset_field(db: &Database, collection: &str, filter: Document, field: &str) -> Result<u64> {
+        let result = db.collection::<Document>(collection)
+            .update_many(filter, doc! { "$unset": { field: "" } }, None)
+            .await?;
+        Ok(result.modified_count)
+    }
+}
@@ -0,0 +1,156 @@
+#[cfg(feature = "server")]
+pub mod Versions {
+    use std::{future::Future, pin::Pin};
+
+    use bson::{doc, to_bson, Document};
+    use tower_sessions_mongodb_store::mongodb::{error::{Error, Result}, Database};
+
+    use crate::backend::{forms::AccountStatus, migrations::steps::Steps, phone, utils::server_utils::{decrypt, encrypt}, AUDIT_LOG, BASE_USERS, MEDIA, PASSKEYS, STOREFRONTS, TWO_FACTOR, USER_SESSIONS};
+
+    pub type MigrationFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+    pub type MigrationFn = for<'a> fn(&'a Database) -> MigrationFuture<'a>;
+
+    pub struct Migration {
+        pub version: u32,
+        pub name: &'static str,
+        pub up: MigrationFn,
+        pub down: MigrationFn,
+    }
+
+    /// Every migration in the order it is applied. Versions must increase and never be reused;
+    /// once shipped, a migration is changed by adding a new one rather than editing it.
+    pub fn all() -> Vec<Migration> {
+        vec![
+            Migration { version: 1, name: "unique_user_credential", up: unique_user_credential_up, down: unique_user_credential_down },
+            Migration { version: 2, name: "backfill_user_status", up: backfill_user_status_up, down: backfill_user_status_down },
+            Migration { version: 3, name: "lookup_indexes", up: lookup_indexes_up, down: lookup_indexes_down },
+            Migration { version: 4, name: "unique_two_factor_user", up: unique_two_factor_user_up, down: unique_two_factor_user_down },
+            Migration { version: 5, name: "passkey_indexes", up: passkey_indexes_up, down: passkey_indexes_down },
+            Migration { version: 6, name: "e164_phone_numbers", up: e164_phone_numbers_up, down: e164_phone_numbers_down },
+        ]
+    }
+
+    fn unique_user_credential_up(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move { Steps::create_index(db, BASE_USERS, doc! { "password": 1 }, true).await })
+    }
+
+    fn unique_user_credential_down(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move { Steps::drop_index(db, BASE_USERS, doc! { "password": 1 }).await })
+    }
+
+    fn backfill_user_status_up(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move {
+            let active = to_bson(&AccountStatus::Active)?;
+            Steps::backfill(db, BASE_USERS, "status", active).await?;
+            Ok(())
+        })
+    }
+
+    fn backfill_user_status_down(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move {
+            let active = to_bson(&AccountStatus::Active)?;
+            Steps::unset_field(db, BASE_USERS, doc! { "status": active }, "status").await?;
+            Ok(())
+        })
+    }
+
+    fn lookup_indexes() -> Vec<(&'static str, bson::Document)> {
+        vec![
+            (USER_SESSIONS, doc! { "user": 1 }),
+            (STOREFRONTS, doc! { "owner": 1 }),
+            (STOREFRONTS, doc! { "status": 1, "created": 1 }),
+            (AUDIT_LOG, doc! { "created": -1 }),
+            (AUDIT_LOG, doc! { "actor": 1, "created": -1 }),
+            (MEDIA, doc! { "owner": 1 }),
+        ]
+    }
+
+    fn lookup_indexes_up(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move {
+            for (collection, keys) in lookup_indexes() {
+                Steps::create_index(db, collection, keys, false).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn lookup_indexes_down(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move {
+            for (collection, keys) in lookup_indexes() {
+                Steps::drop_index(db, collection, keys).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn unique_two_factor_user_up(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move { Steps::create_index(db, TWO_FACTOR, doc! { "user": 1 }, true).await })
+    }
+
+    fn unique_two_factor_user_down(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move { Steps::drop_index(db, TWO_FACTOR, doc! { "user": 1 }).await })
+    }
+
+    fn passkey_indexes_up(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move {
+            Steps::create_index(db, PASSKEYS, doc! { "credential_id": 1 }, true).await?;
+            Steps::create_index(db, PASSKEYS, doc! { "user": 1 }, false).await
+        })
+    }
+
+    fn passkey_indexes_down(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move {
+            Steps::drop_index(db, PASSKEYS, doc! { "credential_id": 1 }).await?;
+            Steps::drop_index(db, PASSKEYS, doc! { "user": 1 }).await
+        })
+    }
+
+    /// E.164 form of a phone number stored before numbers were kept in E.164, if it differs. They were stored in the
+    /// international format without spaces, which is E.164 already unless the format has punctuation, as in
+    /// `+1650-253-0000`.
+    pub fn e164_phone_number(stored: &str) -> Option<String> {
+        let e164 = phone::normalize(stored);
+        (e164 != stored).then_some(e164)
+    }
+
+    /// The form a number stored in E.164 had before, if it differs, for going back to code looking numbers up that way.
+    pub fn legacy_phone_number(stored: &str) -> Option<String> {
+        let legacy = phone::format_international(stored, phone::DEFAULT_COUNTRY).chars().filter(|c| !c.is_whitespace()).collect::<String>();
+        (legacy != stored).then_some(legacy)
+    }
+
+    /// Re-encrypts the phone number of every member as `rewrite` gives it, when it changes. A number already used by
+    /// another member in its new form is left as it is, for an admin to sort out, since the `password` index is unique.
+    async fn rewrite_phone_numbers(db: &Database, rewrite: fn(&str) -> Option<String>) -> Result<()> {
+        let users = db.collection::<Document>(BASE_USERS);
+        let mut cursor = users.find(doc! {}, None).await?;
+        while cursor.advance().await? {
+            let user = cursor.deserialize_current()?;
+            let (Ok(id), Ok(encrypted)) = (user.get_object_id("_id"), user.get_str("password")) else {
+                continue;
+            };
+            let stored = decrypt(encrypted).map_err(|_| {
+                let message = format!("Phone number of user {} does not decrypt with ENCRYPTION_KEY", id.to_hex());
+                Error::from(std::io::Error::new(std::io::ErrorKind::InvalidData, message))
+            })?;
+            let Some(rewritten) = rewrite(&stored) else {
+                continue;
+            };
+            let rewritten = encrypt(&rewritten);
+            if users.count_documents(doc! { "password": &rewritten, "_id": { "$ne": id } }, None).await? > 0 {
+                tracing::warn!(user = %id.to_hex(), "Phone number left as it is, another member uses it in its new form");
+                continue;
+            }
+            users.update_one(doc! { "_id": id }, doc! { "$set": { "password": rewritten } }, None).await?;
+        }
+        Ok(())
+    }
+
+    fn e164_phone_numbers_up(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move { rewrite_phone_numbers(db, e164_phone_number).await })
+    }
+
+    fn e164_phone_numbers_down(db: &Database) -> MigrationFuture<'_> {
+        Box::pin(async move { rewrite_phone_numbers(db, legacy_phone_number).await })
+    }
+}
@@ -1,10 +1,45 @@
 
+#[cfg(feature = "server")]
 pub const BASE_USERS:&str = "base_users";
+#[cfg(feature = "server")]
+pub const MEDIA:&str = "media";
+#[cfg(feature = "server")]
+pub const STOREFRONTS:&str = "storefronts";
+#[cfg(feature = "server")]
+pub const USER_SESSIONS:&str = "user_sessions";
+#[cfg(feature = "server")]
+pub const AUDIT_LOG:&str = "audit_log";
+#[cfg(feature = "server")]
+pub const LISTINGS:&str = "listings";
+#[cfg(feature = "server")]
+pub const BOOKINGS:&str = "bookings";
+#[cfg(feature = "server")]
+pub const REVIEWS:&str = "reviews";
+#[cfg(feature = "server")]
+pub const TWO_FACTOR:&str = "two_factor";
+#[cfg(feature = "server")]
+pub const PASSKEYS:&str = "passkeys";
 
 pub mod utils;
+pub mod phone;
 pub mod mongo_crud;
 pub mod forms;
 pub mod users;
 pub mod mongo_models;
 pub mod auths;
-pub mod api;
\ No newline at end of file
+pub mod api;
+pub mod media;
+pub mod storefronts;
+pub mod bookings;
+pub mod audit;
+pub mod migrations;
+pub mod transfer;
+pub mod seed;
+pub mod telemetry;
+pub mod monitoring;
+pub mod shutdown;
+pub mod security_headers;
+pub mod two_factor;
+pub mod passkeys;
+pub mod sms;
+pub mod phone_change;
\ No newline at end of file
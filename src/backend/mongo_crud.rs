@@ -8,7 +8,7 @@ use dioxus::prelude::Context;
 // };
 
 #[cfg(feature = "server")]
-use tower_sessions_mongodb_store::mongodb::{Client,error::Result,Collection,IndexModel,options::{ClientOptions,IndexOptions}};
+use tower_sessions_mongodb_store::mongodb::{Client,error::Result,Collection,IndexModel,options::{ClientOptions,FindOptions,IndexOptions,ReplaceOptions}};
 
 #[cfg(feature="server")]
 use bson::{doc, oid::ObjectId, to_bson, Document};
@@ -22,6 +22,8 @@ use std::env;
 
 use thiserror::Error;
 
+use crate::backend::forms::AccountStatus;
+
 #[derive(Debug, Error)]
 pub enum MongoRepoError {
     #[error("Password already exists: {0}")]
@@ -38,6 +40,9 @@ pub enum MongoRepoError {
 
     #[error("NOT FOUND")]
     NotFoundError(String),
+
+    #[error("Account is not active")]
+    AccountInactiveError(AccountStatus),
 }
 
 #[cfg(feature="server")]
@@ -69,6 +74,7 @@ where
     }
 
     // CREATE
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "insert_one"), err)]
     pub async fn create(&self, item: T) -> Result<ObjectId> {
         let insert_result = self.col.insert_one(item, None).await?;
         Ok(insert_result
@@ -78,16 +84,35 @@ where
     }
 
     // READ by ID
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "find_one"), err)]
     pub async fn get_by_id(&self, id: &str) -> Result<Option<T>> {
         let obj_id = ObjectId::parse_str(id).unwrap();
         self.col.find_one(doc! { "_id": obj_id }, None).await
     }
 
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "find_one"), err)]
     pub async fn find_one(&self, filter: Document) -> Result<Option<T>> {
         self.col.find_one(filter, None).await
     }
 
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "find"), err)]
+    pub async fn find_many(&self, filter: Document, sort: Option<Document>, skip: Option<u64>, limit: Option<i64>) -> Result<Vec<T>> {
+        let options = FindOptions::builder().sort(sort).skip(skip).limit(limit).build();
+        let mut cursor = self.col.find(filter, options).await?;
+        let mut items = Vec::new();
+        while cursor.advance().await? {
+            items.push(cursor.deserialize_current()?);
+        }
+        Ok(items)
+    }
+
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "count_documents"), err)]
+    pub async fn count(&self, filter: Document) -> Result<u64> {
+        self.col.count_documents(filter, None).await
+    }
+
     // UPDATE by ID
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "update_one"), err)]
     pub async fn update_by_id(&self, id: &str, update_doc: Document) -> Result<bool> {
         let obj_id = ObjectId::parse_str(id).unwrap();
         let result = self
@@ -97,13 +122,36 @@ where
         Ok(result.modified_count > 0)
     }
 
+    // UPDATE one matching `filter` with a full update document such as `{ "$pull": ... }`, returns whether one matched
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "update_one"), err)]
+    pub async fn update_one(&self, filter: Document, update_doc: Document) -> Result<bool> {
+        let result = self.col.update_one(filter, update_doc, None).await?;
+        Ok(result.matched_count > 0)
+    }
+
+    // UPSERT by ID, returns whether a new document was inserted
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "replace_one"), err)]
+    pub async fn upsert_by_id(&self, id: &ObjectId, item: T) -> Result<bool> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+        let result = self.col.replace_one(doc! { "_id": id }, item, options).await?;
+        Ok(result.upserted_id.is_some())
+    }
+
     // DELETE by ID
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "delete_one"), err)]
     pub async fn delete_by_id(&self, id: &str) -> Result<bool> {
         let obj_id = ObjectId::parse_str(id).unwrap();
         let result = self.col.delete_one(doc! { "_id": obj_id }, None).await?;
         Ok(result.deleted_count > 0)
     }
 
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "delete_many"), err)]
+    pub async fn delete_many(&self, filter: Document) -> Result<u64> {
+        let result = self.col.delete_many(filter, None).await?;
+        Ok(result.deleted_count)
+    }
+
+    #[tracing::instrument(name = "mongodb", skip_all, fields(collection = %self.col.name(), operation = "create_index"), err)]
     pub async fn create_unique_index(&self,indexes: Document) -> Result<()>{
         let index_model = IndexModel::builder().keys(indexes).options(IndexOptions::builder().unique(true).build()).build();
         let result = self.col.create_index(index_model, None).await.unwrap();
@@ -6,7 +6,16 @@ pub mod Docs {
     use serde::Deserialize;
     use chrono::{DateTime, Utc};
     use bson::oid::ObjectId;
-    use crate::backend::forms::Forms::UserRole;
+    use crate::backend::forms::{AccountStatus, AuditAction, StorefrontStatus, Forms::UserRole};
+    pub use crate::backend::forms::BookingStatus;
+
+    /// JSON form of an `ObjectId`, described for the OpenAPI document.
+    #[derive(Serialize,utoipa::ToSchema)]
+    #[schema(as = ObjectId)]
+    pub struct ObjectIdJson {
+        #[serde(rename = "$oid")]
+        pub oid: String,
+    }
 
     #[derive(Serialize,Deserialize,Debug,Clone)]
     pub struct BaseUser {
@@ -14,7 +23,153 @@ pub mod Docs {
         pub id: Option<ObjectId>,
         pub password: String,
         pub role: UserRole,
+        #[serde(default)]
+        pub status: AccountStatus,
+        pub created: DateTime<Utc>,
+        pub modified: DateTime<Utc>,
+    }
+
+    #[derive(Serialize,Deserialize,Debug,Clone)]
+    pub struct Storefront {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        pub id: Option<ObjectId>,
+        pub owner: ObjectId,
+        pub name: String,
+        pub description: String,
+        pub status: StorefrontStatus,
+        pub created: DateTime<Utc>,
+        pub modified: DateTime<Utc>,
+    }
+
+    /// Links a tower-sessions id to the user that logged in with it, the session
+    /// payload itself being opaque to queries.
+    #[derive(Serialize,Deserialize,Debug,Clone)]
+    pub struct UserSession {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        pub id: Option<ObjectId>,
+        pub session_id: String,
+        pub user: ObjectId,
+        pub created: DateTime<Utc>,
+    }
+
+    /// TOTP credential of a member, one per user. The secret is encrypted like phone numbers, and
+    /// recovery codes are stored as SHA-256 hashes removed as they are used. `enabled` stays false
+    /// until the member confirms the secret with a first code.
+    #[derive(Serialize,Deserialize,Debug,Clone)]
+    pub struct TwoFactorCredential {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        pub id: Option<ObjectId>,
+        pub user: ObjectId,
+        pub secret: String,
+        pub enabled: bool,
+        pub recovery_codes: Vec<String>,
+        /// Time step of the last accepted code, so that a code cannot be replayed within its window.
+        pub last_step: i64,
+        /// Wrong codes since the last accepted one. Kept with the credential rather than the session, so that
+        /// starting the login over does not reset it.
+        #[serde(default)]
+        pub failed_attempts: u32,
+        /// No code is checked until then, after too many wrong ones.
+        #[serde(default)]
+        pub locked_until: Option<DateTime<Utc>>,
+        pub created: DateTime<Utc>,
+        pub modified: DateTime<Utc>,
+    }
+
+    /// A passkey registered by `user`. `credential_id` is the base64url credential id, unique across members, by
+    /// which a sign-in finds the passkey; `passkey` holds the public key and the signature counter.
+    #[derive(Serialize,Deserialize,Debug,Clone)]
+    pub struct PasskeyCredential {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        pub id: Option<ObjectId>,
+        pub user: ObjectId,
+        pub credential_id: String,
+        pub name: String,
+        pub passkey: webauthn_rs::prelude::Passkey,
+        pub created: DateTime<Utc>,
+        pub last_used: Option<DateTime<Utc>>,
+    }
+
+    #[derive(Serialize,Deserialize,Debug,Clone)]
+    pub struct AuditEntry {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        pub id: Option<ObjectId>,
+        pub action: AuditAction,
+        pub actor: Option<ObjectId>,
+        pub target: Option<String>,
+        pub ip: Option<String>,
+        pub user_agent: Option<String>,
+        pub details: Option<String>,
+        #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+        pub created: DateTime<Utc>,
+    }
+
+    #[derive(Serialize,Deserialize,Debug,Clone,Copy,PartialEq,utoipa::ToSchema)]
+    #[serde(rename_all = "lowercase")]
+    pub enum ImageKind {
+        Listing,
+        Portfolio,
+        Avatar,
+    }
+
+    #[derive(Serialize,Deserialize,Debug,Clone,utoipa::ToSchema)]
+    pub struct MediaVariant {
+        pub name: String,
+        pub key: String,
+        pub content_type: String,
+        pub width: u32,
+        pub height: u32,
+        pub size: u64,
+    }
+
+    #[derive(Serialize,Deserialize,Debug,Clone,utoipa::ToSchema)]
+    pub struct MediaDoc {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        #[schema(value_type = Option<ObjectIdJson>)]
+        pub id: Option<ObjectId>,
+        #[schema(value_type = ObjectIdJson)]
+        pub owner: ObjectId,
+        pub kind: ImageKind,
+        pub variants: Vec<MediaVariant>,
+        pub created: DateTime<Utc>,
+    }
+
+    #[derive(Serialize,Deserialize,Debug,Clone)]
+    pub struct Listing {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        pub id: Option<ObjectId>,
+        pub storefront: ObjectId,
+        pub seller: ObjectId,
+        pub title: String,
+        pub description: String,
+        pub price_tzs: i64,
+        pub duration_minutes: u32,
+        pub images: Vec<ObjectId>,
         pub created: DateTime<Utc>,
         pub modified: DateTime<Utc>,
     }
-}
\ No newline at end of file
+
+    #[derive(Serialize,Deserialize,Debug,Clone)]
+    pub struct Booking {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        pub id: Option<ObjectId>,
+        pub listing: ObjectId,
+        pub buyer: ObjectId,
+        pub seller: ObjectId,
+        pub scheduled: DateTime<Utc>,
+        pub status: BookingStatus,
+        pub created: DateTime<Utc>,
+    }
+
+    #[derive(Serialize,Deserialize,Debug,Clone)]
+    pub struct Review {
+        #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+        pub id: Option<ObjectId>,
+        pub booking: ObjectId,
+        pub listing: ObjectId,
+        pub author: ObjectId,
+        pub rating: u8,
+        pub comment: String,
+        pub created: DateTime<Utc>,
+    }
+}
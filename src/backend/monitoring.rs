@@ -0,0 +1,174 @@
+#[cfg(feature = "server")]
+pub mod Monitoring {
+    use std::{future::Future, sync::OnceLock, time::{Duration, Instant}};
+
+    use axum::{extract::{MatchedPath, Request}, http::{header, HeaderMap, StatusCode}, middleware::Next, response::{IntoResponse, Response}, routing::get, Json, Router};
+    use bson::doc;
+    use metrics::{counter, gauge, histogram};
+    use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+    use serde::Serialize;
+
+    use crate::backend::{auths::auth_session::AuthSession, migrations::runner::Runner, shutdown::Shutdown, sms::Sms};
+
+    const REQUESTS_TOTAL: &str = "http_requests_total";
+    const REQUEST_DURATION: &str = "http_request_duration_seconds";
+    const LOGINS_TOTAL: &str = "auth_logins_total";
+    const ACTIVE_SESSIONS: &str = "sessions_active";
+    const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+    /// Route label of requests that matched no route, such as pages rendered by the app.
+    const OTHER_ROUTE: &str = "other";
+    /// A check taking longer than this counts as failed, so that probes get an answer before they give up.
+    const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+    static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+    #[derive(Serialize, Debug)]
+    pub struct Readiness {
+        pub ready: bool,
+        pub shutting_down: bool,
+        pub mongodb: String,
+        pub session_store: String,
+        pub migrations: String,
+        /// Whether an SMS gateway is configured. Only phone number changes send text messages, so this is reported
+        /// without affecting `ready`.
+        pub sms: String,
+    }
+
+    /// Installs the Prometheus recorder served at `/metrics`. Metrics recorded before this are discarded.
+    pub fn install() {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.to_string()), LATENCY_BUCKETS)
+            .and_then(|builder| builder.install_recorder());
+        match recorder {
+            Ok(handle) => {
+                let _ = PROMETHEUS.set(handle);
+            },
+            Err(e) => tracing::error!(error = %e, "Failed to install the metrics recorder"),
+        }
+    }
+
+    /// Token the scraper sends as `Authorization: Bearer <token>` to read `/metrics`, from `METRICS_TOKEN`.
+    pub fn metrics_token() -> Option<String> {
+        std::env::var("METRICS_TOKEN").ok().filter(|token| !token.trim().is_empty())
+    }
+
+    /// `/healthz`, `/readyz` and `/metrics`, kept outside `/api` for the load balancer and the scraper.
+    pub fn router() -> Router {
+        router_with_metrics_token(metrics_token())
+    }
+
+    /// `router`, with `/metrics` answering only requests bearing `token`. The metrics tell how the site is used, so
+    /// without a token they are not served at all.
+    pub fn router_with_metrics_token(token: Option<String>) -> Router {
+        let router = Router::new()
+            .route("/healthz", get(healthz))
+            .route("/readyz", get(readyz));
+        let Some(token) = token else {
+            return router;
+        };
+        router.route("/metrics", get(move |headers: HeaderMap| async move {
+            if !bears_token(&headers, &token) {
+                return (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")]).into_response();
+            }
+            render_metrics().await
+        }))
+    }
+
+    /// Whether `headers` carry `token` as a bearer token. Every byte is compared, so that timing does not tell how
+    /// much of a guess is right.
+    pub fn bears_token(headers: &HeaderMap, token: &str) -> bool {
+        let Some(sent) = headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()).and_then(|value| value.strip_prefix("Bearer ")) else {
+            return false;
+        };
+        sent.len() == token.len() && sent.bytes().zip(token.bytes()).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+    }
+
+    /// Liveness: the process is serving requests. Nothing else is checked, so that a database outage
+    /// does not get the server restarted.
+    pub async fn healthz() -> &'static str {
+        "ok"
+    }
+
+    /// Readiness: MongoDB answers, the session store is reachable and every migration is applied. The SMS gateway
+    /// is reported too, but does not fail readiness.
+    /// Answers 503 otherwise, and from the moment shutdown begins, so that the load balancer stops sending traffic here.
+    pub async fn readyz() -> Response {
+        let readiness = readiness().await;
+        let status = if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+        (status, Json(readiness)).into_response()
+    }
+
+    pub async fn readiness() -> Readiness {
+        if Shutdown::is_shutting_down() {
+            let skipped = || String::from("skipped");
+            return Readiness { ready: false, shutting_down: true, mongodb: skipped(), session_store: skipped(), migrations: skipped(), sms: skipped() };
+        }
+        let (mongodb, session_store, migrations) = tokio::join!(
+            check(async {
+                Runner::database().await.run_command(doc! { "ping": 1 }, None).await.map(|_| ()).map_err(|e| e.to_string())
+            }),
+            check(async {
+                AuthSession::count_active_sessions().await.map(|_| ()).map_err(|e| e.to_string())
+            }),
+            check(async {
+                match Runner::is_up_to_date(&Runner::database().await).await {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err(String::from("pending migrations")),
+                    Err(e) => Err(e.to_string()),
+                }
+            }),
+        );
+        let sms = match Sms::check_configuration() {
+            Ok(()) => String::from("ok"),
+            Err(e) => e.to_string(),
+        };
+        Readiness {
+            ready: [&mongodb, &session_store, &migrations].iter().all(|status| status.as_str() == "ok"),
+            shutting_down: false,
+            mongodb,
+            session_store,
+            migrations,
+            sms,
+        }
+    }
+
+    async fn check(check: impl Future<Output = Result<(), String>>) -> String {
+        match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+            Ok(Ok(())) => String::from("ok"),
+            Ok(Err(e)) => e,
+            Err(_) => String::from("timed out"),
+        }
+    }
+
+    /// Prometheus text exposition of every metric, with the session store size read at scrape time.
+    pub async fn render_metrics() -> Response {
+        let Some(handle) = PROMETHEUS.get() else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        if let Ok(Ok(sessions)) = tokio::time::timeout(CHECK_TIMEOUT, AuthSession::count_active_sessions()).await {
+            gauge!(ACTIVE_SESSIONS).set(sessions as f64);
+        }
+        handle.run_upkeep();
+        ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], handle.render()).into_response()
+    }
+
+    /// Middleware counting requests and their latency per method and route. Routes are labelled with their
+    /// template, e.g. `/api/v1/admin/users/:id`, to keep the number of series bounded.
+    pub async fn track_requests(request: Request, next: Next) -> Response {
+        let route = request.extensions().get::<MatchedPath>()
+            .map(|path| path.as_str().to_string())
+            .unwrap_or_else(|| OTHER_ROUTE.to_string());
+        let method = request.method().to_string();
+        let started = Instant::now();
+        let response = next.run(request).await;
+        let status = response.status().as_u16().to_string();
+        counter!(REQUESTS_TOTAL, "method" => method.clone(), "route" => route.clone(), "status" => status).increment(1);
+        histogram!(REQUEST_DURATION, "method" => method, "route" => route).record(started.elapsed().as_secs_f64());
+        response
+    }
+
+    pub fn record_login(succeeded: bool) {
+        let outcome = if succeeded { "success" } else { "failure" };
+        counter!(LOGINS_TOTAL, "outcome" => outcome).increment(1);
+    }
+}
@@ -0,0 +1,191 @@
+#[cfg(feature = "server")]
+pub mod Passkeys {
+    use std::sync::LazyLock;
+
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    use bson::{doc, oid::ObjectId, to_bson};
+    use chrono::Utc;
+    use thiserror::Error;
+    use webauthn_rs::prelude::{
+        AuthenticationResult, CreationChallengeResponse, DiscoverableAuthentication, DiscoverableKey, Passkey,
+        PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse,
+        Url, Uuid, Webauthn, WebauthnBuilder, WebauthnError,
+    };
+    use webauthn_rs_proto::ResidentKeyRequirement;
+
+    use crate::backend::{forms::PasskeySummary, mongo_crud::{MongoRepo, MongoRepoError}, mongo_models::Docs::{BaseUser, PasskeyCredential}, utils::server_utils::decrypt, PASSKEYS};
+
+    const DEFAULT_RP_ID: &str = "localhost";
+    const DEFAULT_RP_ORIGIN: &str = "http://localhost:8080";
+    const DEFAULT_RP_NAME: &str = "Freelancer";
+    /// Passkeys a member may register, so that a stolen session cannot fill the collection.
+    pub const MAX_PASSKEYS: usize = 10;
+
+    #[derive(Debug, Error)]
+    pub enum PasskeyError {
+        #[error("Passkeys are not configured: {0}")]
+        Config(String),
+        #[error("Unknown passkey")]
+        UnknownCredential,
+        #[error("Passkey already registered")]
+        AlreadyRegistered,
+        #[error("Too many passkeys")]
+        TooMany,
+        /// The ceremony failed, including a signature counter that did not increase, which suggests a cloned authenticator.
+        #[error("Passkey rejected: {0}")]
+        Rejected(#[from] WebauthnError),
+        #[error(transparent)]
+        Repo(#[from] MongoRepoError),
+    }
+
+    /// Relying party read once from the environment:
+    /// - `WEBAUTHN_RP_ID`, the domain passkeys are bound to (`localhost` by default)
+    /// - `WEBAUTHN_RP_ORIGIN`, the origin the app is served from (`http://localhost:8080` by default)
+    /// - `WEBAUTHN_RP_NAME`, the name shown by the browser (`Freelancer` by default)
+    static WEBAUTHN: LazyLock<Result<Webauthn, String>> = LazyLock::new(|| {
+        let var = |name: &str, default: &str| std::env::var(name).ok().filter(|value| !value.trim().is_empty()).unwrap_or_else(|| default.to_string());
+        relying_party(&var("WEBAUTHN_RP_ID", DEFAULT_RP_ID), &var("WEBAUTHN_RP_ORIGIN", DEFAULT_RP_ORIGIN), &var("WEBAUTHN_RP_NAME", DEFAULT_RP_NAME))
+            .map_err(|e| e.to_string())
+    });
+
+    pub fn relying_party(id: &str, origin: &str, name: &str) -> Result<Webauthn, PasskeyError> {
+        let origin = Url::parse(origin).map_err(|e| PasskeyError::Config(e.to_string()))?;
+        Ok(WebauthnBuilder::new(id, &origin)?.rp_name(name).build()?)
+    }
+
+    pub fn webauthn() -> Result<&'static Webauthn, PasskeyError> {
+        WEBAUTHN.as_ref().map_err(|e| PasskeyError::Config(e.clone()))
+    }
+
+    async fn get_passkeys_repo() -> MongoRepo<PasskeyCredential> {
+        MongoRepo::<PasskeyCredential>::new("sample_mflix", PASSKEYS).await.unwrap()
+    }
+
+    fn unexpected(e: impl ToString) -> MongoRepoError {
+        MongoRepoError::UnexpectedError(e.to_string())
+    }
+
+    /// WebAuthn user handle of a member: their id, padded to the 16 bytes of a UUID. It holds nothing personal,
+    /// as the specification asks.
+    pub fn user_handle(user: &ObjectId) -> Uuid {
+        let mut bytes = [0u8; 16];
+        bytes[..12].copy_from_slice(&user.bytes());
+        Uuid::from_bytes(bytes)
+    }
+
+    /// Form of a credential id stored in `PasskeyCredential.credential_id` and used in URLs.
+    pub fn encode_credential_id(id: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(id)
+    }
+
+    /// Starts registering a passkey for `user`. Authenticators are asked for a discoverable credential, so that
+    /// signing in needs no phone number, and the member's `existing` passkeys are excluded.
+    pub fn start_registration(webauthn: &Webauthn, user: &BaseUser, existing: &[Passkey]) -> Result<(CreationChallengeResponse, PasskeyRegistration), PasskeyError> {
+        let id = user.id.ok_or(MongoRepoError::NotFoundError("User not found".to_string()))?;
+        let phone_number = decrypt(&user.password).map_err(unexpected)?;
+        let exclude = existing.iter().map(|passkey| passkey.cred_id().clone()).collect::<Vec<_>>();
+        let (mut challenge, state) = webauthn.start_passkey_registration(user_handle(&id), &phone_number, &phone_number, Some(exclude))?;
+        if let Some(selection) = challenge.public_key.authenticator_selection.as_mut() {
+            selection.resident_key = Some(ResidentKeyRequirement::Required);
+            selection.require_resident_key = true;
+        }
+        Ok((challenge, state))
+    }
+
+    pub fn finish_registration(webauthn: &Webauthn, credential: &RegisterPublicKeyCredential, state: &PasskeyRegistration) -> Result<Passkey, PasskeyError> {
+        Ok(webauthn.finish_passkey_registration(credential, state)?)
+    }
+
+    /// Starts signing in with whichever passkey the browser offers. The browser shows its own prompt rather
+    /// than waiting for autofill.
+    pub fn start_authentication(webauthn: &Webauthn) -> Result<(RequestChallengeResponse, DiscoverableAuthentication), PasskeyError> {
+        let (mut challenge, state) = webauthn.start_discoverable_authentication()?;
+        challenge.mediation = None;
+        Ok((challenge, state))
+    }
+
+    /// Checks the assertion of `credential` against the `stored` passkey it names. User verification is
+    /// required, and a signature counter that did not increase since `stored` was last used is rejected.
+    pub fn finish_authentication(webauthn: &Webauthn, credential: &PublicKeyCredential, state: DiscoverableAuthentication, stored: &Passkey) -> Result<AuthenticationResult, PasskeyError> {
+        if credential.get_credential_id() != stored.cred_id().as_ref() {
+            return Err(PasskeyError::UnknownCredential);
+        }
+        Ok(webauthn.finish_discoverable_authentication(credential, state, &[DiscoverableKey::from(stored)])?)
+    }
+
+    pub fn to_summary(credential: &PasskeyCredential) -> PasskeySummary {
+        PasskeySummary {
+            id: credential.credential_id.clone(),
+            name: credential.name.clone(),
+            created: credential.created,
+            last_used: credential.last_used,
+        }
+    }
+
+    pub async fn list(user: &ObjectId) -> Result<Vec<PasskeyCredential>, MongoRepoError> {
+        get_passkeys_repo().await
+            .find_many(doc! { "user": user }, Some(doc! { "created": 1 }), None, None)
+            .await
+            .map_err(unexpected)
+    }
+
+    /// Stored passkey the credential presented at sign-in names, whoever it belongs to.
+    pub async fn find_for(credential: &PublicKeyCredential) -> Result<PasskeyCredential, PasskeyError> {
+        get_passkeys_repo().await
+            .find_one(doc! { "credential_id": encode_credential_id(credential.get_credential_id()) })
+            .await
+            .map_err(unexpected)?
+            .ok_or(PasskeyError::UnknownCredential)
+    }
+
+    pub async fn save(user: &ObjectId, name: &str, passkey: Passkey) -> Result<PasskeyCredential, PasskeyError> {
+        let col = get_passkeys_repo().await;
+        if col.count(doc! { "user": user }).await.map_err(unexpected)? as usize >= MAX_PASSKEYS {
+            return Err(PasskeyError::TooMany);
+        }
+        let credential_id = encode_credential_id(passkey.cred_id().as_ref());
+        if col.count(doc! { "credential_id": &credential_id }).await.map_err(unexpected)? > 0 {
+            return Err(PasskeyError::AlreadyRegistered);
+        }
+        let mut credential = PasskeyCredential {
+            id: None,
+            user: *user,
+            credential_id,
+            name: name.trim().to_string(),
+            passkey,
+            created: Utc::now(),
+            last_used: None,
+        };
+        credential.id = Some(col.create(credential.clone()).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?);
+        Ok(credential)
+    }
+
+    /// Stores the counter and backup state reported by a successful sign-in. The update only applies while the
+    /// stored counter is still below the new one, so two sign-ins racing cannot move it backwards.
+    pub async fn record_use(credential: &PasskeyCredential, result: &AuthenticationResult) -> Result<(), MongoRepoError> {
+        let mut passkey = credential.passkey.clone();
+        passkey.update_credential(result);
+        let mut filter = doc! { "_id": credential.id };
+        if result.counter() > 0 {
+            filter.insert("passkey.cred.counter", doc! { "$lt": i64::from(result.counter()) });
+        }
+        let update = doc! {
+            "passkey": to_bson(&passkey).map_err(unexpected)?,
+            "last_used": to_bson(&Utc::now()).map_err(unexpected)?,
+        };
+        get_passkeys_repo().await
+            .update_one(filter, doc! { "$set": update })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Removes the passkey `credential_id` of `user`, returning whether there was one.
+    pub async fn remove(user: &ObjectId, credential_id: &str) -> Result<bool, MongoRepoError> {
+        let deleted = get_passkeys_repo().await
+            .delete_many(doc! { "user": user, "credential_id": credential_id })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(deleted > 0)
+    }
+}
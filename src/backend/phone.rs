@@ -0,0 +1,216 @@
+use phonenumber::{country::Id, metadata::DATABASE, Mode, PhoneNumber, Type};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use validator::ValidationError;
+
+/// Country assumed for numbers typed without a country code, which is how members registered before numbers
+/// were stored in E.164.
+pub const DEFAULT_COUNTRY: Id = Id::TZ;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhoneCountry {
+    pub id: Id,
+    pub name: &'static str,
+    pub dial_code: u16,
+}
+
+/// Countries offered by the phone number input: the East African Community first, then the neighbours and the
+/// countries members most often come from.
+pub const COUNTRIES: [PhoneCountry; 16] = [
+    PhoneCountry { id: Id::TZ, name: "Tanzania", dial_code: 255 },
+    PhoneCountry { id: Id::KE, name: "Kenya", dial_code: 254 },
+    PhoneCountry { id: Id::UG, name: "Uganda", dial_code: 256 },
+    PhoneCountry { id: Id::RW, name: "Rwanda", dial_code: 250 },
+    PhoneCountry { id: Id::BI, name: "Burundi", dial_code: 257 },
+    PhoneCountry { id: Id::SS, name: "South Sudan", dial_code: 211 },
+    PhoneCountry { id: Id::CD, name: "DR Congo", dial_code: 243 },
+    PhoneCountry { id: Id::SO, name: "Somalia", dial_code: 252 },
+    PhoneCountry { id: Id::ET, name: "Ethiopia", dial_code: 251 },
+    PhoneCountry { id: Id::MZ, name: "Mozambique", dial_code: 258 },
+    PhoneCountry { id: Id::MW, name: "Malawi", dial_code: 265 },
+    PhoneCountry { id: Id::ZM, name: "Zambia", dial_code: 260 },
+    PhoneCountry { id: Id::ZA, name: "South Africa", dial_code: 27 },
+    PhoneCountry { id: Id::AE, name: "United Arab Emirates", dial_code: 971 },
+    PhoneCountry { id: Id::GB, name: "United Kingdom", dial_code: 44 },
+    PhoneCountry { id: Id::US, name: "United States", dial_code: 1 },
+];
+
+pub fn find_country(id: Id) -> Option<&'static PhoneCountry> {
+    COUNTRIES.iter().find(|country| country.id == id)
+}
+
+/// Mobile network that issued a number, by the prefix of its national number. Numbers ported to another network
+/// keep showing the one that issued them.
+const CARRIERS: [(Id, &str, &str); 40] = [
+    (Id::TZ, "61", "Halotel"),
+    (Id::TZ, "62", "Halotel"),
+    (Id::TZ, "65", "Yas"),
+    (Id::TZ, "67", "Yas"),
+    (Id::TZ, "71", "Yas"),
+    (Id::TZ, "68", "Airtel"),
+    (Id::TZ, "69", "Airtel"),
+    (Id::TZ, "78", "Airtel"),
+    (Id::TZ, "73", "TTCL"),
+    (Id::TZ, "74", "Vodacom"),
+    (Id::TZ, "75", "Vodacom"),
+    (Id::TZ, "76", "Vodacom"),
+    (Id::TZ, "77", "Zantel"),
+    (Id::KE, "70", "Safaricom"),
+    (Id::KE, "71", "Safaricom"),
+    (Id::KE, "72", "Safaricom"),
+    (Id::KE, "740", "Safaricom"),
+    (Id::KE, "741", "Safaricom"),
+    (Id::KE, "742", "Safaricom"),
+    (Id::KE, "743", "Safaricom"),
+    (Id::KE, "757", "Safaricom"),
+    (Id::KE, "758", "Safaricom"),
+    (Id::KE, "759", "Safaricom"),
+    (Id::KE, "79", "Safaricom"),
+    (Id::KE, "11", "Safaricom"),
+    (Id::KE, "73", "Airtel"),
+    (Id::KE, "75", "Airtel"),
+    (Id::KE, "78", "Airtel"),
+    (Id::KE, "10", "Airtel"),
+    (Id::KE, "77", "Telkom"),
+    (Id::UG, "76", "MTN"),
+    (Id::UG, "77", "MTN"),
+    (Id::UG, "78", "MTN"),
+    (Id::UG, "70", "Airtel"),
+    (Id::UG, "74", "Airtel"),
+    (Id::UG, "75", "Airtel"),
+    (Id::RW, "78", "MTN"),
+    (Id::RW, "79", "MTN"),
+    (Id::RW, "72", "Airtel"),
+    (Id::RW, "73", "Airtel"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub enum NumberKind {
+    Mobile,
+    FixedLine,
+    /// The numbering plan does not tell mobile and fixed lines apart, as in the United States.
+    FixedLineOrMobile,
+    Other,
+}
+
+impl NumberKind {
+    /// Whether the number can receive text messages, such as the codes phone number changes rely on.
+    pub fn receives_sms(&self) -> bool {
+        matches!(self, NumberKind::Mobile | NumberKind::FixedLineOrMobile)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+pub enum PhoneNumberError {
+    #[error("Not a phone number")]
+    Unparseable,
+    #[error("The phone number does not exist in its country")]
+    Invalid,
+    #[error("The phone number cannot receive text messages")]
+    NotMobile,
+}
+
+/// What the number itself tells about a phone number.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct PhoneNumberDetails {
+    pub e164: String,
+    /// ISO 3166 code of the country, e.g. `TZ`.
+    pub country: Option<String>,
+    pub kind: NumberKind,
+    pub carrier: Option<String>,
+}
+
+/// `phonenumber::parse` with hyphens and dots separating digits like spaces do, as in `+1 650-253-0000`; it only
+/// accepts spaces after a country code.
+fn parse_typed(country: Option<Id>, input: &str) -> Result<PhoneNumber, phonenumber::ParseError> {
+    phonenumber::parse(country, input.trim().replace(['-', '.'], " "))
+}
+
+/// Parses `input`, assuming `country` when it has no country code, and keeps it only if it is a valid number.
+pub fn parse(input: &str, country: Id) -> Result<PhoneNumber, PhoneNumberError> {
+    let number = parse_typed(Some(country), input).map_err(|_| PhoneNumberError::Unparseable)?;
+    if number.is_valid() { Ok(number) } else { Err(PhoneNumberError::Invalid) }
+}
+
+/// `input` in E.164, e.g. `+255712345678`: the one form phone numbers are sent to the server and stored in.
+pub fn to_e164(input: &str, country: Id) -> Result<String, PhoneNumberError> {
+    Ok(parse(input, country)?.format().mode(Mode::E164).to_string())
+}
+
+/// `input` in E.164 when it is a valid number, else as typed without whitespace, so that a lookup of an invalid
+/// number finds nothing instead of failing.
+pub fn normalize(input: &str) -> String {
+    to_e164(input, DEFAULT_COUNTRY).unwrap_or_else(|_| input.chars().filter(|c| !c.is_whitespace()).collect())
+}
+
+/// `input` laid out the international way, e.g. `+255 712 345 678`, once it parses; otherwise as typed.
+pub fn format_international(input: &str, country: Id) -> String {
+    match parse_typed(Some(country), input) {
+        Ok(number) => number.format().mode(Mode::International).to_string(),
+        Err(_) => input.to_string(),
+    }
+}
+
+/// Country of a number typed with its country code, when it is one of `COUNTRIES`.
+pub fn country_of(input: &str) -> Option<Id> {
+    if !input.trim_start().starts_with('+') {
+        return None;
+    }
+    let id = parse_typed(None, input).ok()?.country().id()?;
+    find_country(id).map(|country| country.id)
+}
+
+/// The national part of `input` moved to `country`, for when the member picks another country after typing.
+pub fn with_country(input: &str, from: Id, to: Id) -> String {
+    let national = match parse_typed(Some(from), input) {
+        Ok(number) => number.national().value().to_string(),
+        Err(_) => input.chars().filter(|c| c.is_ascii_digit()).collect(),
+    };
+    format_international(&national, to)
+}
+
+pub fn kind(number: &PhoneNumber) -> NumberKind {
+    match number.number_type(&DATABASE) {
+        Type::Mobile => NumberKind::Mobile,
+        Type::FixedLine => NumberKind::FixedLine,
+        Type::FixedLineOrMobile => NumberKind::FixedLineOrMobile,
+        _ => NumberKind::Other,
+    }
+}
+
+/// Network that issued `number`, by the longest known prefix of its national number.
+pub fn carrier(number: &PhoneNumber) -> Option<&'static str> {
+    let id = number.country().id()?;
+    let national = number.national().value().to_string();
+    CARRIERS.iter()
+        .filter(|(country, prefix, _)| *country == id && national.starts_with(prefix))
+        .max_by_key(|(_, prefix, _)| prefix.len())
+        .map(|(_, _, carrier)| *carrier)
+}
+
+pub fn details(input: &str, country: Id) -> Result<PhoneNumberDetails, PhoneNumberError> {
+    let number = parse(input, country)?;
+    Ok(PhoneNumberDetails {
+        e164: number.format().mode(Mode::E164).to_string(),
+        country: number.country().id().map(|id| id.as_ref().to_string()),
+        kind: kind(&number),
+        carrier: carrier(&number).map(str::to_string),
+    })
+}
+
+/// A number members can sign in with: valid, and able to receive text messages.
+pub fn validate_phone_number(input: &str) -> Result<(), PhoneNumberError> {
+    let number = parse(input, DEFAULT_COUNTRY)?;
+    if kind(&number).receives_sms() { Ok(()) } else { Err(PhoneNumberError::NotMobile) }
+}
+
+/// `validate_phone_number` for `#[validate(custom(...))]` on forms.
+pub fn validate_phone_field(input: &str) -> Result<(), ValidationError> {
+    validate_phone_number(input).map_err(|e| match e {
+        PhoneNumberError::Unparseable => ValidationError::new("unparseable_phone_number"),
+        PhoneNumberError::Invalid => ValidationError::new("invalid_phone_number"),
+        PhoneNumberError::NotMobile => ValidationError::new("not_mobile"),
+    })
+}
@@ -0,0 +1,146 @@
+#[cfg(feature = "server")]
+pub mod PhoneChange {
+    use std::{collections::HashMap, sync::{LazyLock, Mutex}};
+
+    use bson::oid::ObjectId;
+    use chrono::{DateTime, Duration, Utc};
+    use rand::Rng;
+    use sha2::{Digest, Sha256};
+    use thiserror::Error;
+
+    use crate::backend::{audit::Audit, mongo_crud::MongoRepoError, mongo_models::Docs::BaseUser, sms::Sms::{self, SmsError}, two_factor::TwoFactor::{self, TwoFactorError}, users::Users, utils::server_utils::decrypt};
+
+    const CODE_DIGITS: usize = 6;
+    /// Least time between two changes started by a member, each of which sends two text messages.
+    pub const START_COOLDOWN_SECONDS: i64 = 60;
+    /// Changes a member may start within an hour.
+    pub const MAX_STARTS_PER_HOUR: usize = 5;
+
+    /// When each member started their changes of the last hour.
+    static RECENT_STARTS: LazyLock<Mutex<HashMap<ObjectId, Vec<DateTime<Utc>>>>> = LazyLock::new(Default::default);
+
+    #[derive(Debug, Error)]
+    pub enum PhoneChangeError {
+        #[error("The new phone number is the current one")]
+        SameNumber,
+        #[error("The phone number belongs to another member")]
+        NumberTaken,
+        #[error("Invalid verification code")]
+        InvalidCode,
+        #[error("Too many phone number changes started; try again after {0}")]
+        TooSoon(DateTime<Utc>),
+        #[error(transparent)]
+        Sms(#[from] SmsError),
+        #[error(transparent)]
+        Repo(#[from] MongoRepoError),
+    }
+
+    /// How the member showed they still control their account when changing its phone number.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Proof {
+        /// The code sent to the current number.
+        CurrentNumber,
+        /// A two-factor recovery code, for a member who lost the current number.
+        RecoveryCode { remaining: usize },
+    }
+
+    /// Codes sent to the current and the new number of a change, as hashes for keeping in the session.
+    #[derive(Debug, Clone)]
+    pub struct SentCodes {
+        pub current: String,
+        pub new: String,
+    }
+
+    pub fn hash_code(code: &str) -> String {
+        hex::encode(Sha256::digest(code.trim().as_bytes()))
+    }
+
+    pub fn is_code(code: &str) -> bool {
+        let code = code.trim();
+        code.len() == CODE_DIGITS && code.chars().all(|c| c.is_ascii_digit())
+    }
+
+    pub fn code_matches(hash: &str, code: &str) -> bool {
+        is_code(code) && hash_code(code) == hash
+    }
+
+    fn new_code() -> String {
+        let mut rng = rand::rng();
+        (0..CODE_DIGITS).map(|_| char::from(b'0' + rng.random_range(0..10u8))).collect()
+    }
+
+    pub fn current_phone_number(user: &BaseUser) -> Result<String, MongoRepoError> {
+        decrypt(&user.password).map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))
+    }
+
+    /// Records a change started by `user` at `now`, unless they started one less than `START_COOLDOWN_SECONDS` ago or
+    /// `MAX_STARTS_PER_HOUR` within the last hour, in which case the error tells when they may start the next one.
+    /// Starts are counted by each server process.
+    pub fn reserve_start(user: &ObjectId, now: DateTime<Utc>) -> Result<(), PhoneChangeError> {
+        let hour_ago = now - Duration::hours(1);
+        let mut recent = RECENT_STARTS.lock().unwrap_or_else(|e| e.into_inner());
+        recent.retain(|_, starts| {
+            starts.retain(|started| *started > hour_ago);
+            !starts.is_empty()
+        });
+        let starts = recent.entry(*user).or_default();
+        if let Some(last) = starts.last() {
+            let next = *last + Duration::seconds(START_COOLDOWN_SECONDS);
+            if next > now {
+                return Err(PhoneChangeError::TooSoon(next));
+            }
+        }
+        if starts.len() >= MAX_STARTS_PER_HOUR {
+            return Err(PhoneChangeError::TooSoon(starts[0] + Duration::hours(1)));
+        }
+        starts.push(now);
+        Ok(())
+    }
+
+    /// Checks that `user` may move to `new_phone_number` and sends a code to it and to their current number.
+    pub async fn send_codes(user: &BaseUser, new_phone_number: &str) -> Result<SentCodes, PhoneChangeError> {
+        let id = user.id.ok_or(MongoRepoError::NotFoundError("User not found".to_string()))?;
+        let current = current_phone_number(user)?;
+        let new_phone_number = Users::normalize_phone_number(new_phone_number);
+        if new_phone_number == current {
+            return Err(PhoneChangeError::SameNumber);
+        }
+        match Users::find_by_phone_number(&new_phone_number).await {
+            Ok(_) => return Err(PhoneChangeError::NumberTaken),
+            Err(MongoRepoError::NotFoundError(_)) => {},
+            Err(e) => return Err(e.into()),
+        }
+        reserve_start(&id, Utc::now())?;
+        let (current_code, new_code) = (new_code(), new_code());
+        Sms::send(&new_phone_number, &format!("{} is your Freelancer code to use this number on your account.", new_code)).await?;
+        Sms::send(&current, &format!("{} is your Freelancer code to move your account to the number ending {}.", current_code, Audit::mask_phone_number(&new_phone_number))).await?;
+        Ok(SentCodes { current: hash_code(&current_code), new: hash_code(&new_code) })
+    }
+
+    /// Checks proof of the current number: the code sent to it, or else a recovery code of `user`, which is used up.
+    pub async fn verify_current(user: &ObjectId, sent: &SentCodes, code: &str) -> Result<Proof, PhoneChangeError> {
+        if is_code(code) {
+            return if code_matches(&sent.current, code) { Ok(Proof::CurrentNumber) } else { Err(PhoneChangeError::InvalidCode) };
+        }
+        match TwoFactor::redeem_recovery_code(user, code).await {
+            Ok(remaining) => Ok(Proof::RecoveryCode { remaining }),
+            Err(TwoFactorError::Repo(e)) => Err(e.into()),
+            Err(_) => Err(PhoneChangeError::InvalidCode),
+        }
+    }
+
+    /// Moves `user` to `new_phone_number` and lets the number they leave know, in case the change was not theirs.
+    pub async fn apply(user: &BaseUser, new_phone_number: &str) -> Result<BaseUser, PhoneChangeError> {
+        let id = user.id.ok_or(MongoRepoError::NotFoundError("User not found".to_string()))?;
+        let previous = current_phone_number(user)?;
+        let updated = Users::set_phone_number(&id, new_phone_number).await.map_err(|e| match e {
+            MongoRepoError::PasswordExistsError(_) => PhoneChangeError::NumberTaken,
+            e => PhoneChangeError::Repo(e),
+        })?;
+        let notice = "The phone number of your Freelancer account was changed. If this was not you, contact support.";
+        if let Err(e) = Sms::send(&previous, notice).await {
+            tracing::warn!(error = %e, "Could not notify the previous phone number of a change");
+        }
+        Ok(updated)
+    }
+}
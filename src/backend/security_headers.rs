@@ -0,0 +1,151 @@
+#[cfg(feature = "server")]
+pub mod SecurityHeaders {
+    use std::sync::{Arc, LazyLock};
+
+    use axum::{body::Bytes, extract::{Request, State}, http::{header, HeaderName, HeaderValue, StatusCode}, middleware::Next, response::Response};
+
+    /// Where browsers send Content Security Policy violations.
+    pub const CSP_REPORT_PATH: &str = "/api/csp-report";
+    const DEFAULT_HSTS_MAX_AGE: u64 = 365 * 24 * 60 * 60;
+
+    /// A Content Security Policy. Handlers needing a different policy from `app` return one in their response
+    /// extensions, e.g. `(Extension(policy), body)`, and `apply` sends it instead.
+    #[derive(Clone, Debug)]
+    pub struct ContentSecurityPolicy {
+        directives: Vec<(&'static str, Vec<String>)>,
+    }
+
+    impl ContentSecurityPolicy {
+        /// Policy of the Dioxus app. Dioxus writes its hydration data into inline scripts that change with every
+        /// page, so they cannot be hashed and need `'unsafe-inline'`; the WASM bundle needs `'wasm-unsafe-eval'`,
+        /// and components set inline `style` attributes. Everything else is restricted to the app's own origin.
+        pub fn app() -> ContentSecurityPolicy {
+            ContentSecurityPolicy { directives: Vec::new() }
+                .with("default-src", &["'self'"])
+                .with("script-src", &["'self'", "'unsafe-inline'", "'wasm-unsafe-eval'"])
+                .with("style-src", &["'self'", "'unsafe-inline'"])
+                .with("img-src", &["'self'", "data:", "blob:"])
+                .with("font-src", &["'self'", "data:"])
+                .with("connect-src", &["'self'"])
+                .with("object-src", &["'none'"])
+                .with("base-uri", &["'self'"])
+                .with("form-action", &["'self'"])
+                .with("frame-ancestors", &["'none'"])
+        }
+
+        /// Replaces the sources of `directive`.
+        pub fn with(mut self, directive: &'static str, sources: &[&str]) -> ContentSecurityPolicy {
+            let sources = sources.iter().map(|source| source.to_string()).collect();
+            match self.directives.iter_mut().find(|(name, _)| *name == directive) {
+                Some((_, existing)) => *existing = sources,
+                None => self.directives.push((directive, sources)),
+            }
+            self
+        }
+
+        /// Adds `source` to `directive`, starting from `default-src` when the directive is not set yet.
+        pub fn allow(mut self, directive: &'static str, source: &str) -> ContentSecurityPolicy {
+            match self.directives.iter_mut().find(|(name, _)| *name == directive) {
+                Some((_, sources)) => sources.push(source.to_string()),
+                None => {
+                    let mut sources = self.directives.iter()
+                        .find(|(name, _)| *name == "default-src")
+                        .map(|(_, sources)| sources.clone())
+                        .unwrap_or_default();
+                    sources.push(source.to_string());
+                    self.directives.push((directive, sources));
+                },
+            }
+            self
+        }
+
+        pub fn header_value(&self) -> String {
+            self.directives.iter()
+                .map(|(name, sources)| format!("{} {}", name, sources.join(" ")))
+                .chain([format!("report-uri {}", CSP_REPORT_PATH), String::from("report-to csp")])
+                .collect::<Vec<String>>()
+                .join("; ")
+        }
+    }
+
+    /// What `apply` sends.
+    #[derive(Clone, Debug)]
+    pub struct SecurityConfig {
+        /// Policy of responses that do not bring their own.
+        pub policy: ContentSecurityPolicy,
+        pub report_only: bool,
+        /// In seconds; `0` leaves the `Strict-Transport-Security` header out.
+        pub hsts_max_age: u64,
+    }
+
+    impl SecurityConfig {
+        /// From the environment:
+        /// - `CSP_REPORT_ONLY=true` reports violations to `CSP_REPORT_PATH` without blocking anything, for trying out a policy
+        /// - `CSP_CONNECT_SRC` lists further origins, space separated, the app may call, such as a separate API host
+        /// - `HSTS_MAX_AGE` in seconds, one year by default; `0` leaves the header out
+        pub fn from_env() -> SecurityConfig {
+            dotenvy::dotenv().ok();
+            let policy = std::env::var("CSP_CONNECT_SRC").unwrap_or_default()
+                .split_whitespace()
+                .fold(ContentSecurityPolicy::app(), |policy, origin| policy.allow("connect-src", origin));
+            SecurityConfig {
+                policy,
+                report_only: std::env::var("CSP_REPORT_ONLY").is_ok_and(|v| v == "true"),
+                hsts_max_age: std::env::var("HSTS_MAX_AGE").ok().and_then(|v| v.parse::<u64>().ok()).unwrap_or(DEFAULT_HSTS_MAX_AGE),
+            }
+        }
+    }
+
+    /// Read once from the environment.
+    static CONFIG: LazyLock<SecurityConfig> = LazyLock::new(SecurityConfig::from_env);
+
+    /// The policy configured for the app, `CSP_CONNECT_SRC` origins included, for handlers to extend into
+    /// their own.
+    pub fn configured_policy() -> ContentSecurityPolicy {
+        CONFIG.policy.clone()
+    }
+
+    /// Middleware adding the security headers to every response that does not set them itself: the Content
+    /// Security Policy (report-only when configured), HSTS, `X-Content-Type-Options`, `Referrer-Policy` and
+    /// `X-Frame-Options` for browsers predating `frame-ancestors`.
+    pub async fn apply(request: Request, next: Next) -> Response {
+        add_headers(&CONFIG, next.run(request).await)
+    }
+
+    /// `apply` with `config` instead of the one from the environment, for `middleware::from_fn_with_state`.
+    pub async fn apply_with(State(config): State<Arc<SecurityConfig>>, request: Request, next: Next) -> Response {
+        add_headers(&config, next.run(request).await)
+    }
+
+    fn add_headers(config: &SecurityConfig, mut response: Response) -> Response {
+        let hsts = (config.hsts_max_age > 0)
+            .then(|| HeaderValue::from_str(&format!("max-age={}; includeSubDomains", config.hsts_max_age)).ok())
+            .flatten();
+        let csp_header = if config.report_only { header::CONTENT_SECURITY_POLICY_REPORT_ONLY } else { header::CONTENT_SECURITY_POLICY };
+        let policy = response.extensions().get::<ContentSecurityPolicy>().unwrap_or(&config.policy).header_value();
+
+        let headers = response.headers_mut();
+        let mut set_default = |name: HeaderName, value: Option<HeaderValue>| {
+            if let (false, Some(value)) = (headers.contains_key(&name), value) {
+                headers.insert(name, value);
+            }
+        };
+        set_default(csp_header, HeaderValue::from_str(&policy).ok());
+        set_default(HeaderName::from_static("reporting-endpoints"), HeaderValue::from_str(&format!("csp=\"{}\"", CSP_REPORT_PATH)).ok());
+        set_default(header::STRICT_TRANSPORT_SECURITY, hsts);
+        set_default(header::X_CONTENT_TYPE_OPTIONS, Some(HeaderValue::from_static("nosniff")));
+        set_default(header::REFERRER_POLICY, Some(HeaderValue::from_static("strict-origin-when-cross-origin")));
+        set_default(header::X_FRAME_OPTIONS, Some(HeaderValue::from_static("DENY")));
+        response
+    }
+
+    /// Receives violation reports, both the `application/csp-report` bodies of `report-uri` and the
+    /// `application/reports+json` batches of `report-to`, and logs them.
+    pub async fn report(body: Bytes) -> StatusCode {
+        match serde_json::from_slice::<serde_json::Value>(&body) {
+            Ok(report) => tracing::warn!(target: "csp", %report, "Content Security Policy violation"),
+            Err(_) => tracing::debug!(target: "csp", "Ignored malformed CSP report"),
+        }
+        StatusCode::NO_CONTENT
+    }
+}
@@ -0,0 +1,313 @@
+#[cfg(feature = "server")]
+pub mod Seed {
+    use std::{collections::HashMap, path::{Path, PathBuf}};
+
+    use bson::oid::ObjectId;
+    use chrono::{DateTime, Duration, NaiveDate, Utc};
+    use rand::{rngs::StdRng, seq::IndexedRandom, Rng, SeedableRng};
+    use serde::{de::DeserializeOwned, Serialize};
+    use sha2::{Digest, Sha256};
+    use thiserror::Error;
+
+    use crate::backend::{
+        api::media::Media::kind_prefix,
+        forms::{AccountStatus, StorefrontStatus, UserRole},
+        media::{blob_store::Blobs::{BlobStore, LocalBlobStore}, images::Images::{self, ProcessedImage}},
+        mongo_crud::MongoRepo,
+        mongo_models::Docs::{BaseUser, Booking, BookingStatus, ImageKind, Listing, MediaDoc, MediaVariant, Review, Storefront},
+        utils::server_utils::encrypt,
+        BASE_USERS, BOOKINGS, LISTINGS, MEDIA, REVIEWS, STOREFRONTS,
+    };
+
+    pub const DEFAULT_SEED: u64 = 42;
+    /// Day the seeded dates are laid out around unless another one is given.
+    pub const DEFAULT_DAY: NaiveDate = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+    /// Phone numbers hold two digits of the seed and three of the member's position.
+    pub const MAX_MEMBERS: usize = 999;
+    const SELLER_PHONE_PREFIX: &str = "+2557990";
+    const BUYER_PHONE_PREFIX: &str = "+2557991";
+    const BOOKING_WINDOW_DAYS: i64 = 30;
+
+    const STOREFRONT_NAMES: &[&str] = &[
+        "Crown & Coil Studio", "Kariakoo Braids", "Msasani Hair Lounge", "Silk & Sheen", "Twende Twists",
+        "Mikocheni Locs", "Sinza Style Bar", "Upanga Hair House", "Coco Curl Co.", "Zanzibar Plaits",
+    ];
+    const STOREFRONT_BLURBS: &[&str] = &[
+        "Protective styles done gently, with edges left intact.",
+        "Home visits across Dar es Salaam, products included.",
+        "Natural hair specialists: wash days, trims and treatments.",
+        "Quick, neat braids for busy mornings and big events.",
+    ];
+    /// Hairstyle title, base price in TZS and duration in minutes.
+    const STYLES: &[(&str, i64, u32)] = &[
+        ("Knotless box braids", 90_000, 360),
+        ("Cornrows", 25_000, 90),
+        ("Fulani braids", 70_000, 240),
+        ("Silk press", 45_000, 120),
+        ("Locs retwist", 40_000, 150),
+        ("Two-strand twists", 50_000, 180),
+        ("Bantu knots", 30_000, 90),
+        ("Passion twists", 80_000, 300),
+        ("Wash and deep condition", 20_000, 60),
+    ];
+    const LISTING_DETAILS: &[&str] = &[
+        "Price includes extensions in natural black; other colours on request.",
+        "Please arrive with clean, detangled hair.",
+        "Suitable for short and medium length hair.",
+        "Finished with a light oil and edge styling.",
+    ];
+    const REVIEW_COMMENTS: &[&str] = &[
+        "Lovely work and very gentle, will book again.",
+        "Took a little longer than planned but the result is great.",
+        "Neat parts and the style lasted for weeks.",
+        "Friendly and on time. Highly recommended.",
+        "Good value for the price.",
+    ];
+
+    #[derive(Debug, Error)]
+    pub enum SeedError {
+        #[error("Database error: {0}")]
+        DatabaseError(#[from] tower_sessions_mongodb_store::mongodb::error::Error),
+
+        #[error("Failed to read seed images: {0}")]
+        AssetError(#[from] std::io::Error),
+
+        #[error("Failed to process {0}: {1}")]
+        ImageError(String, String),
+
+        #[error("No images found in {0}")]
+        NoAssetsError(String),
+
+        #[error("At most {0} sellers and {0} buyers can be seeded")]
+        LimitError(usize),
+    }
+
+    pub struct SeedOptions {
+        pub seed: u64,
+        pub sellers: usize,
+        pub buyers: usize,
+        pub assets: PathBuf,
+        pub today: NaiveDate,
+    }
+
+    impl Default for SeedOptions {
+        fn default() -> Self {
+            SeedOptions { seed: DEFAULT_SEED, sellers: 6, buyers: 20, assets: PathBuf::from("assets/img"), today: DEFAULT_DAY }
+        }
+    }
+
+    /// Number of documents inserted, documents already present being replaced in place.
+    #[derive(Debug, Default)]
+    pub struct SeedReport {
+        pub users: u64,
+        pub storefronts: u64,
+        pub listings: u64,
+        pub media: u64,
+        pub bookings: u64,
+        pub reviews: u64,
+    }
+
+    /// Ids depend only on the seed, the entity kind and its position, so a re-run with the same seed replaces the
+    /// documents of the previous run instead of adding new ones, while another seed adds its own.
+    pub fn seeded_id(seed: u64, kind: &str, index: usize) -> ObjectId {
+        let hash = Sha256::digest(format!("freelance-seed/{}/{}/{}", seed, kind, index).as_bytes());
+        let mut bytes = [0u8; 12];
+        bytes.copy_from_slice(&hash[..12]);
+        ObjectId::from_bytes(bytes)
+    }
+
+    /// Phone number of the member at `index`, distinct for seeds that differ in their last two digits, since
+    /// phone numbers are unique across members.
+    pub fn seeded_phone_number(prefix: &str, seed: u64, index: usize) -> String {
+        format!("{}{:02}{:03}", prefix, seed % 100, index + 1)
+    }
+
+    async fn upsert<T>(collection: &str, id: ObjectId, item: T) -> Result<u64, SeedError>
+    where
+        T: Serialize + DeserializeOwned + Unpin + Send + Sync,
+    {
+        let repo = MongoRepo::<T>::new("sample_mflix", collection).await?;
+        Ok(repo.upsert_by_id(&id, item).await? as u64)
+    }
+
+    fn seed_images(assets: &Path) -> Result<Vec<PathBuf>, SeedError> {
+        let mut images = std::fs::read_dir(assets)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("hair-")))
+            .collect::<Vec<PathBuf>>();
+        if images.is_empty() {
+            return Err(SeedError::NoAssetsError(assets.display().to_string()));
+        }
+        images.sort();
+        Ok(images)
+    }
+
+    /// Stores `source` as a listing image owned by `owner`. Images seeded by an earlier run are kept
+    /// as they are, and each bundled file is only decoded and resized once per run.
+    async fn seed_media(
+        id: ObjectId,
+        owner: ObjectId,
+        source: &Path,
+        processed: &mut HashMap<PathBuf, Vec<ProcessedImage>>,
+        created: DateTime<Utc>,
+    ) -> Result<u64, SeedError> {
+        let repo = MongoRepo::<MediaDoc>::new("sample_mflix", MEDIA).await?;
+        if repo.get_by_id(&id.to_hex()).await?.is_some() {
+            return Ok(0);
+        }
+        if !processed.contains_key(source) {
+            let bytes = std::fs::read(source)?;
+            let variants = Images::process(ImageKind::Listing, &bytes)
+                .map_err(|e| SeedError::ImageError(source.display().to_string(), e.to_string()))?;
+            processed.insert(source.to_path_buf(), variants);
+        }
+
+        let store = LocalBlobStore::from_env();
+        let mut variants = Vec::new();
+        for variant in &processed[source] {
+            let key = format!("{}/{}/{}.{}", kind_prefix(ImageKind::Listing), id.to_hex(), variant.name, variant.extension);
+            store.put(&key, &variant.bytes).await.map_err(|e| SeedError::ImageError(key.clone(), e.to_string()))?;
+            variants.push(MediaVariant {
+                name: variant.name.clone(),
+                key,
+                content_type: variant.content_type.to_string(),
+                width: variant.width,
+                height: variant.height,
+                size: variant.bytes.len() as u64,
+            });
+        }
+        upsert(MEDIA, id, MediaDoc { id: Some(id), owner, kind: ImageKind::Listing, variants, created }).await
+    }
+
+    async fn seed_user(id: ObjectId, phone_number: String, role: UserRole, created: DateTime<Utc>) -> Result<u64, SeedError> {
+        let user = BaseUser {
+            id: Some(id),
+            password: encrypt(&phone_number),
+            role,
+            status: AccountStatus::Active,
+            created,
+            modified: created,
+        };
+        upsert(BASE_USERS, id, user).await
+    }
+
+    /// Fills the database with sellers, storefronts, listings, bookings and reviews generated from `options.seed`.
+    /// Dates are laid out around `options.today`, so bookings before that day are completed or cancelled and later ones
+    /// are upcoming. Running it again with the same options leaves the same documents in place.
+    pub async fn run(options: &SeedOptions) -> Result<SeedReport, SeedError> {
+        if options.sellers > MAX_MEMBERS || options.buyers > MAX_MEMBERS {
+            return Err(SeedError::LimitError(MAX_MEMBERS));
+        }
+        let seed = options.seed;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut report = SeedReport::default();
+        let today = options.today.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        let images = seed_images(&options.assets)?;
+        let mut processed = HashMap::new();
+
+        let mut listings: Vec<Listing> = Vec::new();
+        for seller_index in 0..options.sellers {
+            let seller = seeded_id(seed, "seller", seller_index);
+            let joined = today - Duration::days(rng.random_range(60..365));
+            let phone_number = seeded_phone_number(SELLER_PHONE_PREFIX, seed, seller_index);
+            report.users += seed_user(seller, phone_number, UserRole::Seller, joined).await?;
+
+            let storefront_id = seeded_id(seed, "storefront", seller_index);
+            let name = STOREFRONT_NAMES[seller_index % STOREFRONT_NAMES.len()];
+            // The last storefront waits for review so the admin queue is not empty.
+            let status = if seller_index + 1 == options.sellers { StorefrontStatus::Pending } else { StorefrontStatus::Approved };
+            let storefront = Storefront {
+                id: Some(storefront_id),
+                owner: seller,
+                name: name.to_string(),
+                description: STOREFRONT_BLURBS.choose(&mut rng).copied().unwrap_or_default().to_string(),
+                status: status.clone(),
+                created: joined,
+                modified: joined,
+            };
+            report.storefronts += upsert(STOREFRONTS, storefront_id, storefront).await?;
+
+            for _ in 0..rng.random_range(2..=4) {
+                let listing_index = listings.len();
+                let listing_id = seeded_id(seed, "listing", listing_index);
+                let (title, base_price, duration) = *STYLES.choose(&mut rng).unwrap_or(&STYLES[0]);
+                let created = joined + Duration::days(rng.random_range(0..30));
+
+                let mut media = Vec::new();
+                for image_index in 0..rng.random_range(1..=3) {
+                    let media_id = seeded_id(seed, "media", listing_index * 3 + image_index);
+                    let source = images.choose(&mut rng).unwrap_or(&images[0]);
+                    report.media += seed_media(media_id, seller, source, &mut processed, created).await?;
+                    media.push(media_id);
+                }
+
+                let listing = Listing {
+                    id: Some(listing_id),
+                    storefront: storefront_id,
+                    seller,
+                    title: title.to_string(),
+                    description: LISTING_DETAILS.choose(&mut rng).copied().unwrap_or_default().to_string(),
+                    // Round to the nearest 1,000 TZS like a price list would.
+                    price_tzs: (base_price * rng.random_range(80..=130) / 100) / 1_000 * 1_000,
+                    duration_minutes: duration,
+                    images: media,
+                    created,
+                    modified: created,
+                };
+                report.listings += upsert(LISTINGS, listing_id, listing.clone()).await?;
+                if status == StorefrontStatus::Approved {
+                    listings.push(listing);
+                }
+            }
+        }
+
+        let mut booking_index = 0;
+        for buyer_index in 0..options.buyers {
+            let buyer = seeded_id(seed, "buyer", buyer_index);
+            let joined = today - Duration::days(rng.random_range(BOOKING_WINDOW_DAYS..180));
+            let phone_number = seeded_phone_number(BUYER_PHONE_PREFIX, seed, buyer_index);
+            report.users += seed_user(buyer, phone_number, UserRole::Buyer, joined).await?;
+
+            for _ in 0..rng.random_range(0..=4) {
+                let Some(listing) = listings.choose(&mut rng) else { break };
+                let listing_id = listing.id.unwrap_or_default();
+                let scheduled = today
+                    + Duration::days(rng.random_range(-BOOKING_WINDOW_DAYS..=BOOKING_WINDOW_DAYS))
+                    + Duration::hours(rng.random_range(8..=17));
+                let status = match (scheduled < today, rng.random_bool(0.85)) {
+                    (true, true) => BookingStatus::Completed,
+                    (true, false) => BookingStatus::Cancelled,
+                    (false, true) => BookingStatus::Confirmed,
+                    (false, false) => BookingStatus::Pending,
+                };
+                let booking_id = seeded_id(seed, "booking", booking_index);
+                let booking = Booking {
+                    id: Some(booking_id),
+                    listing: listing_id,
+                    buyer,
+                    seller: listing.seller,
+                    scheduled,
+                    status,
+                    created: scheduled - Duration::days(rng.random_range(1..=14)),
+                };
+                report.bookings += upsert(BOOKINGS, booking_id, booking).await?;
+
+                if status == BookingStatus::Completed && rng.random_bool(0.7) {
+                    let review_id = seeded_id(seed, "review", booking_index);
+                    let review = Review {
+                        id: Some(review_id),
+                        booking: booking_id,
+                        listing: listing_id,
+                        author: buyer,
+                        rating: *[3, 4, 4, 5, 5, 5].choose(&mut rng).unwrap_or(&5),
+                        comment: REVIEW_COMMENTS.choose(&mut rng).copied().unwrap_or_default().to_string(),
+                        created: scheduled + Duration::days(rng.random_range(0..=3)),
+                    };
+                    report.reviews += upsert(REVIEWS, review_id, review).await?;
+                }
+                booking_index += 1;
+            }
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(feature = "server")]
+pub mod Shutdown {
+    use std::{sync::LazyLock, time::Duration};
+
+    use tokio::{sync::watch, task::JoinHandle};
+
+    const DEFAULT_DRAIN_SECS: u64 = 30;
+
+    /// Flips to `true` once shutdown begins and never back.
+    static STARTED: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::channel(false).0);
+
+    fn env_secs(name: &str, default: u64) -> Duration {
+        let secs = std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok()).unwrap_or(default);
+        Duration::from_secs(secs)
+    }
+
+    /// How long in-flight requests and background tasks get to finish, from `SHUTDOWN_DRAIN_SECS` (30 by default).
+    pub fn drain_timeout() -> Duration {
+        env_secs("SHUTDOWN_DRAIN_SECS", DEFAULT_DRAIN_SECS)
+    }
+
+    /// How long the server keeps accepting requests after readiness fails, from `SHUTDOWN_DELAY_SECS` (none by
+    /// default). Gives the load balancer time to notice before connections are refused.
+    pub fn readiness_delay() -> Duration {
+        env_secs("SHUTDOWN_DELAY_SECS", 0)
+    }
+
+    pub fn is_shutting_down() -> bool {
+        *STARTED.borrow()
+    }
+
+    /// Starts shutdown: readiness fails from now on and every `requested` future resolves.
+    pub fn begin() {
+        STARTED.send_replace(true);
+    }
+
+    /// Resolves once shutdown begins. Background tasks and long-lived connections such as WebSockets select
+    /// on it to stop on their own instead of being cut off when the drain timeout runs out.
+    pub async fn requested() {
+        let mut started = STARTED.subscribe();
+        let _ = started.wait_for(|started| *started).await;
+    }
+
+    /// Waits for SIGTERM or SIGINT (Ctrl+C), begins shutdown and, after `readiness_delay`, resolves so that the
+    /// server stops accepting connections.
+    pub async fn signal() {
+        let ctrl_c = async {
+            tokio::signal::ctrl_c().await.expect("Failed to install Ctrl+C handler");
+        };
+
+        #[cfg(unix)]
+        let terminate = async {
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("Failed to install SIGTERM handler")
+                .recv()
+                .await;
+        };
+
+        #[cfg(not(unix))]
+        let terminate = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = ctrl_c => tracing::info!("Received SIGINT, shutting down"),
+            _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+            _ = requested() => {},
+        }
+        begin();
+        tokio::time::sleep(readiness_delay()).await;
+    }
+
+    /// Resolves `drain_timeout` after the server stopped accepting connections, bounding how long it waits for
+    /// in-flight requests.
+    pub async fn drain_deadline() {
+        drain_deadline_after(readiness_delay(), drain_timeout()).await;
+    }
+
+    /// Resolves `drain` after `delay` has passed since shutdown began. The delay is waited out in full, as `signal`
+    /// keeps accepting connections until then, however it compares with `drain`.
+    pub async fn drain_deadline_after(delay: Duration, drain: Duration) {
+        requested().await;
+        tokio::time::sleep(delay + drain).await;
+    }
+
+    /// Waits for a background task that stops on `requested`, aborting it if it outlives `timeout`.
+    pub async fn join_task<T: std::fmt::Debug>(name: &str, task: JoinHandle<T>, timeout: Duration) {
+        let abort = task.abort_handle();
+        match tokio::time::timeout(timeout, task).await {
+            Ok(Ok(result)) => tracing::info!(task = name, ?result, "Background task stopped"),
+            Ok(Err(e)) => tracing::error!(task = name, error = %e, "Background task failed"),
+            Err(_) => {
+                abort.abort();
+                tracing::warn!(task = name, "Background task did not stop in time and was aborted");
+            },
+        }
+    }
+}
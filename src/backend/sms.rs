@@ -0,0 +1,129 @@
+#[cfg(feature = "server")]
+pub mod Sms {
+    use std::{future::Future, time::Duration};
+
+    use serde::Serialize;
+    use thiserror::Error;
+
+    use crate::backend::audit::Audit;
+
+    const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+    const DEFAULT_SENDER: &str = "Freelancer";
+
+    #[derive(Debug, Error)]
+    pub enum SmsError {
+        #[error("No SMS gateway is configured; set SMS_GATEWAY_URL, or SMS_LOG_MESSAGES=true in development")]
+        NotConfigured,
+        #[error("The SMS gateway could not be reached: {0}")]
+        Unreachable(String),
+        #[error("The SMS gateway refused the message with status {0}")]
+        Rejected(u16),
+    }
+
+    /// Delivers text messages to phone numbers in E.164. `HttpGateway` posts them to an SMS provider and
+    /// `LogGateway` writes them to the log; another provider only has to implement this trait.
+    pub trait SmsGateway: Send + Sync {
+        fn send(&self, phone_number: &str, message: &str) -> impl Future<Output = Result<(), SmsError>> + Send;
+    }
+
+    /// Body posted to the provider.
+    #[derive(Serialize)]
+    struct OutgoingMessage<'a> {
+        from: &'a str,
+        to: &'a str,
+        text: &'a str,
+    }
+
+    /// Provider taking messages as JSON `{"from", "to", "text"}` posted to `url` with a bearer token, which is how
+    /// most SMS APIs, or a small adapter in front of them, accept them. Any 2xx answer counts as accepted.
+    pub struct HttpGateway {
+        client: reqwest::Client,
+        url: String,
+        token: String,
+        sender: String,
+    }
+
+    impl HttpGateway {
+        pub fn new(url: impl Into<String>, token: impl Into<String>, sender: impl Into<String>) -> Result<Self, SmsError> {
+            let client = reqwest::Client::builder()
+                .timeout(SEND_TIMEOUT)
+                .build()
+                .map_err(|e| SmsError::Unreachable(e.to_string()))?;
+            Ok(Self { client, url: url.into(), token: token.into(), sender: sender.into() })
+        }
+
+        /// From `SMS_GATEWAY_URL`, `SMS_GATEWAY_TOKEN` and `SMS_SENDER_ID` (`Freelancer` by default), when a URL is set.
+        pub fn from_env() -> Option<Result<Self, SmsError>> {
+            let url = std::env::var("SMS_GATEWAY_URL").ok().filter(|url| !url.trim().is_empty())?;
+            let token = std::env::var("SMS_GATEWAY_TOKEN").unwrap_or_default();
+            let sender = std::env::var("SMS_SENDER_ID").unwrap_or_else(|_| DEFAULT_SENDER.to_string());
+            Some(Self::new(url.trim(), token, sender))
+        }
+    }
+
+    impl SmsGateway for HttpGateway {
+        async fn send(&self, phone_number: &str, message: &str) -> Result<(), SmsError> {
+            let response = self.client.post(&self.url)
+                .bearer_auth(&self.token)
+                .json(&OutgoingMessage { from: &self.sender, to: phone_number, text: message })
+                .send()
+                .await
+                .map_err(|e| SmsError::Unreachable(e.to_string()))?;
+            if response.status().is_success() {
+                Ok(())
+            } else {
+                Err(SmsError::Rejected(response.status().as_u16()))
+            }
+        }
+    }
+
+    /// Writes messages to the log under the `sms` target at debug level, for development. The number is masked,
+    /// but the messages, and so the codes, are logged as they are.
+    pub struct LogGateway;
+
+    impl SmsGateway for LogGateway {
+        async fn send(&self, phone_number: &str, message: &str) -> Result<(), SmsError> {
+            tracing::debug!(target: "sms", phone_number = %Audit::mask_phone_number(phone_number), message, "SMS");
+            Ok(())
+        }
+    }
+
+    /// The gateway configured through the environment.
+    pub enum Gateway {
+        Http(HttpGateway),
+        Log(LogGateway),
+    }
+
+    impl Gateway {
+        /// `HttpGateway` when `SMS_GATEWAY_URL` is set, else `LogGateway` when `SMS_LOG_MESSAGES=true`.
+        pub fn from_env() -> Result<Self, SmsError> {
+            if let Some(gateway) = HttpGateway::from_env() {
+                return gateway.map(Gateway::Http);
+            }
+            if std::env::var("SMS_LOG_MESSAGES").is_ok_and(|v| v == "true") {
+                return Ok(Gateway::Log(LogGateway));
+            }
+            Err(SmsError::NotConfigured)
+        }
+    }
+
+    impl SmsGateway for Gateway {
+        async fn send(&self, phone_number: &str, message: &str) -> Result<(), SmsError> {
+            match self {
+                Gateway::Http(gateway) => gateway.send(phone_number, message).await,
+                Gateway::Log(gateway) => gateway.send(phone_number, message).await,
+            }
+        }
+    }
+
+    /// Whether messages can be sent, for readiness and startup.
+    pub fn check_configuration() -> Result<(), SmsError> {
+        Gateway::from_env().map(|_| ())
+    }
+
+    /// Sends `message` to `phone_number` through the configured gateway. Without one the caller is told, rather than
+    /// leaving the member waiting for a code.
+    pub async fn send(phone_number: &str, message: &str) -> Result<(), SmsError> {
+        Gateway::from_env()?.send(phone_number, message).await
+    }
+}
@@ -0,0 +1,64 @@
+#[cfg(feature="server")]
+pub mod Storefronts {
+    use bson::{doc, oid::ObjectId, to_bson};
+    use chrono::Utc;
+    use crate::backend::{forms::{StorefrontStatus, StorefrontSummary, Forms::StorefrontForm}, mongo_crud::{MongoRepo, MongoRepoError}, mongo_models::Docs::Storefront, STOREFRONTS};
+
+    pub async fn get_storefronts_repo() -> MongoRepo<Storefront> {
+        MongoRepo::<Storefront>::new("sample_mflix", STOREFRONTS).await.unwrap()
+    }
+
+    pub fn to_summary(storefront: &Storefront) -> StorefrontSummary {
+        StorefrontSummary {
+            id: storefront.id.map(|id| id.to_hex()).unwrap_or_default(),
+            owner_id: storefront.owner.to_hex(),
+            name: storefront.name.clone(),
+            description: storefront.description.clone(),
+            status: storefront.status.clone(),
+            created: storefront.created,
+        }
+    }
+
+    pub async fn create_storefront(owner: ObjectId, form: StorefrontForm) -> Result<Storefront, MongoRepoError> {
+        let now = Utc::now();
+        let storefront = Storefront {
+            id: None,
+            owner,
+            name: form.name,
+            description: form.description,
+            status: StorefrontStatus::Pending,
+            created: now,
+            modified: now,
+        };
+        let col = get_storefronts_repo().await;
+        let id = col.create(storefront.clone()).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(Storefront { id: Some(id), ..storefront })
+    }
+
+    pub async fn list_by_owner(owner: &ObjectId) -> Result<Vec<Storefront>, MongoRepoError> {
+        let col = get_storefronts_repo().await;
+        col.find_many(doc! { "owner": owner }, Some(doc! { "created": -1 }), None, None)
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))
+    }
+
+    pub async fn list_by_status(status: StorefrontStatus) -> Result<Vec<Storefront>, MongoRepoError> {
+        let col = get_storefronts_repo().await;
+        let status = to_bson(&status).map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        col.find_many(doc! { "status": status }, Some(doc! { "created": 1 }), None, None)
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))
+    }
+
+    pub async fn set_status(id: &ObjectId, status: StorefrontStatus) -> Result<Storefront, MongoRepoError> {
+        let col = get_storefronts_repo().await;
+        let update = doc! {
+            "status": to_bson(&status).map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?,
+            "modified": to_bson(&Utc::now()).map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?,
+        };
+        col.update_by_id(&id.to_hex(), update).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        col.find_one(doc! { "_id": id }).await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?
+            .ok_or(MongoRepoError::NotFoundError("Storefront not found".to_string()))
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(feature = "server")]
+pub mod Telemetry {
+    use std::time::Duration;
+
+    use axum::{body::Body, http::{Request, Response}, Router};
+    use bson::oid::ObjectId;
+    use tower_http::{request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer}, trace::TraceLayer};
+    use tracing::{field::Empty, Span};
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+    #[cfg(feature = "otlp")]
+    use opentelemetry::{propagation::Extractor, trace::TracerProvider};
+    #[cfg(feature = "otlp")]
+    use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+    #[cfg(feature = "otlp")]
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    pub const REQUEST_ID_HEADER: &str = "x-request-id";
+    const DEFAULT_FILTER: &str = "info";
+    #[cfg(feature = "otlp")]
+    const SERVICE_NAME: &str = env!("CARGO_PKG_NAME");
+
+    /// Keeps span export running; dropping it flushes the spans still buffered.
+    pub struct TelemetryGuard {
+        #[cfg(feature = "otlp")]
+        provider: Option<SdkTracerProvider>,
+    }
+
+    impl Drop for TelemetryGuard {
+        fn drop(&mut self) {
+            #[cfg(feature = "otlp")]
+            if let Some(provider) = self.provider.take() {
+                if let Err(e) = provider.shutdown() {
+                    eprintln!("Failed to flush exported spans: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Installs the global subscriber. `RUST_LOG` selects what is logged (`info` by default) and `LOG_FORMAT=json`
+    /// writes one JSON object per line for production. With the `otlp` feature, spans are also exported to the
+    /// collector at `OTEL_EXPORTER_OTLP_ENDPOINT` when that variable is set, e.g. `http://localhost:4318`.
+    pub fn init() -> TelemetryGuard {
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+        let json = std::env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"));
+        let output = tracing_subscriber::fmt::layer();
+        let output = if json {
+            output.json().flatten_event(true).with_current_span(true).with_span_list(false).boxed()
+        } else {
+            output.boxed()
+        };
+        let subscriber = tracing_subscriber::registry().with(output).with(filter);
+
+        #[cfg(feature = "otlp")]
+        {
+            let provider = otlp_provider();
+            let exporter = provider.as_ref().map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+            subscriber.with(exporter).init();
+            TelemetryGuard { provider }
+        }
+        #[cfg(not(feature = "otlp"))]
+        {
+            subscriber.init();
+            TelemetryGuard {}
+        }
+    }
+
+    #[cfg(feature = "otlp")]
+    fn otlp_provider() -> Option<SdkTracerProvider> {
+        std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+        let exporter = match opentelemetry_otlp::SpanExporter::builder().with_http().build() {
+            Ok(exporter) => exporter,
+            Err(e) => {
+                eprintln!("OTLP export disabled: {}", e);
+                return None;
+            },
+        };
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let resource = Resource::builder().with_service_name(SERVICE_NAME).build();
+        Some(SdkTracerProvider::builder().with_batch_exporter(exporter).with_resource(resource).build())
+    }
+
+    /// Incoming `traceparent` headers, so that spans join a trace started by the caller.
+    #[cfg(feature = "otlp")]
+    struct RequestHeaders<'a>(&'a axum::http::HeaderMap);
+
+    #[cfg(feature = "otlp")]
+    impl Extractor for RequestHeaders<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|key| key.as_str()).collect()
+        }
+    }
+
+    fn request_span(request: &Request<Body>) -> Span {
+        let request_id = request.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok()).unwrap_or_default();
+        let span = tracing::info_span!(
+            "http_request",
+            method = %request.method(),
+            path = %request.uri().path(),
+            request_id,
+            user_id = Empty,
+            status = Empty,
+            latency_ms = Empty,
+        );
+        #[cfg(feature = "otlp")]
+        {
+            let parent = opentelemetry::global::get_text_map_propagator(|propagator| propagator.extract(&RequestHeaders(request.headers())));
+            span.set_parent(parent);
+        }
+        span
+    }
+
+    fn record_response(response: &Response<Body>, latency: Duration, span: &Span) {
+        span.record("status", response.status().as_u16());
+        span.record("latency_ms", latency.as_millis() as u64);
+        tracing::info!("request finished");
+    }
+
+    /// Wraps every request of `router` in an `http_request` span carrying its method, path, request id, user and
+    /// latency. The request id is taken from `x-request-id` when the caller sends one, generated otherwise, and
+    /// returned on the response.
+    pub fn trace_requests(router: Router) -> Router {
+        router
+            .layer(TraceLayer::new_for_http().make_span_with(request_span).on_response(record_response))
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+    }
+
+    /// Attaches the logged in member to the current request span.
+    pub fn record_user(id: &ObjectId) {
+        Span::current().record("user_id", id.to_hex());
+    }
+}
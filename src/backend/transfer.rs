@@ -0,0 +1,59 @@
+#[cfg(feature = "server")]
+pub mod Transfer {
+    use std::{fs::File, io::{BufRead, BufReader, BufWriter, Write}, path::Path};
+
+    use bson::{doc, Bson, Document};
+    use thiserror::Error;
+    use tower_sessions_mongodb_store::mongodb::{options::ReplaceOptions, Database};
+
+    #[derive(Debug, Error)]
+    pub enum TransferError {
+        #[error("Database error: {0}")]
+        DatabaseError(#[from] tower_sessions_mongodb_store::mongodb::error::Error),
+
+        #[error("File error: {0}")]
+        FileError(#[from] std::io::Error),
+
+        #[error("Invalid document on line {0}: {1}")]
+        ParseError(usize, String),
+    }
+
+    /// Writes every document of `collection` to `path`, one relaxed extended JSON document per line.
+    pub async fn export_collection(db: &Database, collection: &str, path: &Path) -> Result<u64, TransferError> {
+        let mut cursor = db.collection::<Document>(collection).find(None, None).await?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        let mut exported = 0;
+        while cursor.advance().await? {
+            let document = cursor.deserialize_current()?;
+            let line = Bson::Document(document).into_relaxed_extjson();
+            writeln!(writer, "{}", line)?;
+            exported += 1;
+        }
+        writer.flush()?;
+        Ok(exported)
+    }
+
+    /// Loads a file written by `export_collection` into `collection`. Documents are upserted by `_id`,
+    /// so importing the same file twice leaves the collection unchanged.
+    pub async fn import_collection(db: &Database, collection: &str, path: &Path) -> Result<u64, TransferError> {
+        let col = db.collection::<Document>(collection);
+        let options = ReplaceOptions::builder().upsert(true).build();
+        let mut imported = 0;
+        for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parse_error = |e: String| TransferError::ParseError(index + 1, e);
+            let value: serde_json::Value = serde_json::from_str(&line).map_err(|e| parse_error(e.to_string()))?;
+            let document = match Bson::try_from(value).map_err(|e| parse_error(e.to_string()))? {
+                Bson::Document(document) => document,
+                _ => return Err(parse_error(String::from("expected a JSON object"))),
+            };
+            let id = document.get("_id").cloned().ok_or_else(|| parse_error(String::from("missing _id")))?;
+            col.replace_one(doc! { "_id": id }, document, options.clone()).await?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+}
@@ -0,0 +1,345 @@
+#[cfg(feature = "server")]
+pub mod TwoFactor {
+    use bson::{doc, oid::ObjectId, to_bson, Document};
+    use chrono::{DateTime, Duration, Utc};
+    use rand::seq::IndexedRandom;
+    use sha2::{Digest, Sha256};
+    use thiserror::Error;
+    use totp_rs::{Algorithm, Secret, TOTP};
+
+    use crate::backend::{forms::{RecoveryCodes, TwoFactorEnrollment, TwoFactorStep, Forms::UserRole}, mongo_crud::{MongoRepo, MongoRepoError}, mongo_models::Docs::{BaseUser, TwoFactorCredential}, utils::server_utils::{decrypt, decrypt_with_key, encrypt, encrypt_with_key}, TWO_FACTOR};
+
+    const DEFAULT_ISSUER: &str = "Freelancer";
+    const DIGITS: usize = 6;
+    const STEP_SECS: u64 = 30;
+    /// Codes of the previous and next time step are accepted too, for clocks running slightly off.
+    const SKEW_STEPS: u64 = 1;
+    const RECOVERY_CODE_COUNT: usize = 10;
+    const RECOVERY_CODE_LENGTH: usize = 10;
+    /// Without characters that are easily mistaken for one another, such as `0`/`o` and `1`/`l`.
+    const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+    /// Wrong codes, of any kind, after which a member's credential is locked.
+    pub const MAX_FAILED_ATTEMPTS: u32 = 5;
+    pub const LOCKOUT_MINUTES: i64 = 15;
+
+    #[derive(Debug, Error)]
+    pub enum TwoFactorError {
+        #[error("Two-factor authentication is already enabled")]
+        AlreadyEnabled,
+        #[error("Two-factor authentication is not set up")]
+        NotEnrolled,
+        #[error("Invalid two-factor code")]
+        InvalidCode,
+        #[error("Too many invalid two-factor codes, locked until {0}")]
+        LockedOut(DateTime<Utc>),
+        #[error(transparent)]
+        Repo(#[from] MongoRepoError),
+    }
+
+    /// How a login was verified.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Verified {
+        Code,
+        RecoveryCode { remaining: usize },
+    }
+
+    async fn get_credentials_repo() -> MongoRepo<TwoFactorCredential> {
+        MongoRepo::<TwoFactorCredential>::new("sample_mflix", TWO_FACTOR).await.unwrap()
+    }
+
+    fn unexpected(e: impl ToString) -> MongoRepoError {
+        MongoRepoError::UnexpectedError(e.to_string())
+    }
+
+    /// Name shown next to the codes in authenticator apps, from `TOTP_ISSUER`.
+    fn issuer() -> String {
+        std::env::var("TOTP_ISSUER").ok()
+            .map(|issuer| issuer.replace(':', ""))
+            .filter(|issuer| !issuer.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_ISSUER.to_string())
+    }
+
+    fn totp(secret: Vec<u8>, account_name: String) -> Result<TOTP, MongoRepoError> {
+        TOTP::new(Algorithm::SHA1, DIGITS, 0, STEP_SECS, secret, Some(issuer()), account_name).map_err(unexpected)
+    }
+
+    fn stored_totp(credential: &TwoFactorCredential) -> Result<TOTP, MongoRepoError> {
+        let secret = decrypt(&credential.secret).map_err(unexpected)?;
+        totp(Secret::Encoded(secret).to_bytes().map_err(unexpected)?, String::new())
+    }
+
+    /// Time step `code` was generated for, within the accepted skew around `now`, if it comes after `last_step`:
+    /// a code is accepted only once, and none older than the last one accepted is.
+    pub fn accepted_step(totp: &TOTP, code: &str, last_step: i64, now: DateTime<Utc>) -> Option<i64> {
+        let current = now.timestamp().max(0) as u64 / STEP_SECS;
+        (current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+            .find(|step| totp.check(code, step * STEP_SECS))
+            .map(|step| step as i64)
+            .filter(|step| *step > last_step)
+    }
+
+    fn is_totp_code(code: &str) -> bool {
+        code.len() == DIGITS && code.chars().all(|c| c.is_ascii_digit())
+    }
+
+    fn hash_recovery_code(code: &str) -> String {
+        let normalized = code.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>();
+        hex::encode(Sha256::digest(normalized.as_bytes()))
+    }
+
+    /// Fresh recovery codes such as `k7mqd-x3wpa`, with the hashes to store for them. The codes are random
+    /// enough that a fast hash is safe, which lets a code be looked up by its hash.
+    pub fn new_recovery_codes() -> (Vec<String>, Vec<String>) {
+        let mut rng = rand::rng();
+        let codes = (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let code = (0..RECOVERY_CODE_LENGTH)
+                    .map(|_| *RECOVERY_CODE_ALPHABET.choose(&mut rng).unwrap_or(&b'a') as char)
+                    .collect::<String>();
+                format!("{}-{}", &code[..RECOVERY_CODE_LENGTH / 2], &code[RECOVERY_CODE_LENGTH / 2..])
+            })
+            .collect::<Vec<String>>();
+        let hashes = codes.iter().map(|code| hash_recovery_code(code)).collect();
+        (codes, hashes)
+    }
+
+    /// Removes the hash of `code` from `hashes`, those of the recovery codes left, if it is one of them: a
+    /// recovery code is used once.
+    pub fn take_recovery_code(hashes: &mut Vec<String>, code: &str) -> bool {
+        let hash = hash_recovery_code(code);
+        let before = hashes.len();
+        hashes.retain(|stored| *stored != hash);
+        hashes.len() < before
+    }
+
+    /// Whether `credential` refuses every code at `now`, after too many wrong ones.
+    pub fn is_locked(credential: &TwoFactorCredential, now: DateTime<Utc>) -> bool {
+        credential.locked_until.is_some_and(|until| until > now)
+    }
+
+    /// End of the lockout starting at `now` once a member has entered `failed_attempts` wrong codes, if they
+    /// entered enough for one.
+    pub fn lockout_after(failed_attempts: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (failed_attempts >= MAX_FAILED_ATTEMPTS).then(|| now + Duration::minutes(LOCKOUT_MINUTES))
+    }
+
+    /// Enabled credential of `user` that may be checked now.
+    async fn unlocked_credential(user: &ObjectId) -> Result<TwoFactorCredential, TwoFactorError> {
+        let credential = find(user).await?.filter(|credential| credential.enabled).ok_or(TwoFactorError::NotEnrolled)?;
+        match credential.locked_until {
+            Some(until) if is_locked(&credential, Utc::now()) => Err(TwoFactorError::LockedOut(until)),
+            _ => Ok(credential),
+        }
+    }
+
+    /// Counts the outcome of checking a code of `credential`: a wrong code adds to its failed attempts and locks
+    /// it once there are `MAX_FAILED_ATTEMPTS`, an accepted one clears them.
+    async fn counted<T>(credential: &TwoFactorCredential, result: Result<T, TwoFactorError>) -> Result<T, TwoFactorError> {
+        let col = get_credentials_repo().await;
+        let modified = to_bson(&Utc::now()).map_err(unexpected)?;
+        match result {
+            Ok(value) => {
+                if credential.failed_attempts > 0 || credential.locked_until.is_some() {
+                    col.update_one(doc! { "_id": credential.id }, doc! { "$set": { "failed_attempts": 0, "locked_until": null, "modified": modified } })
+                        .await
+                        .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+                }
+                Ok(value)
+            },
+            Err(TwoFactorError::InvalidCode) => {
+                col.update_one(doc! { "_id": credential.id }, doc! { "$inc": { "failed_attempts": 1 }, "$set": { "modified": modified } })
+                    .await
+                    .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+                let failed_attempts = find(&credential.user).await?.map_or(0, |credential| credential.failed_attempts);
+                let Some(until) = lockout_after(failed_attempts, Utc::now()) else {
+                    return Err(TwoFactorError::InvalidCode);
+                };
+                let locked_until = to_bson(&until).map_err(unexpected)?;
+                col.update_one(doc! { "_id": credential.id }, doc! { "$set": { "failed_attempts": 0, "locked_until": locked_until } })
+                    .await
+                    .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+                Err(TwoFactorError::LockedOut(until))
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether members with `role` must use two-factor authentication. Admins must, as they act on every account.
+    pub fn required_for(role: &UserRole) -> bool {
+        *role == UserRole::Admin
+    }
+
+    /// Whether members with `role` may set up two-factor authentication: those handling money or other accounts.
+    pub fn available_for(role: &UserRole) -> bool {
+        matches!(role, UserRole::Admin | UserRole::Seller)
+    }
+
+    pub async fn find(user: &ObjectId) -> Result<Option<TwoFactorCredential>, MongoRepoError> {
+        get_credentials_repo().await.find_one(doc! { "user": user }).await.map_err(unexpected)
+    }
+
+    pub async fn is_enabled(user: &ObjectId) -> Result<bool, MongoRepoError> {
+        Ok(find(user).await?.is_some_and(|credential| credential.enabled))
+    }
+
+    /// Step a login by `user` must pass before a session is created for them, if any.
+    pub async fn login_step(user: &BaseUser) -> Result<Option<TwoFactorStep>, MongoRepoError> {
+        let id = user.id.ok_or(MongoRepoError::NotFoundError("User not found".to_string()))?;
+        if is_enabled(&id).await? {
+            Ok(Some(TwoFactorStep::Verify))
+        } else if required_for(&user.role) {
+            Ok(Some(TwoFactorStep::Enroll))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Generates a new secret for `user`, replacing one that was never confirmed. The account name shown in
+    /// authenticator apps is the member's phone number.
+    pub async fn start_enrollment(user: &BaseUser) -> Result<TwoFactorEnrollment, TwoFactorError> {
+        let id = user.id.ok_or(MongoRepoError::NotFoundError("User not found".to_string()))?;
+        let existing = find(&id).await?;
+        if existing.as_ref().is_some_and(|credential| credential.enabled) {
+            return Err(TwoFactorError::AlreadyEnabled);
+        }
+        let phone_number = decrypt(&user.password).map_err(unexpected)?;
+        let totp = totp(Secret::generate_secret().to_bytes().map_err(unexpected)?, phone_number)?;
+        let secret = totp.get_secret_base32();
+        let now = Utc::now();
+        let credential = TwoFactorCredential {
+            id: existing.as_ref().and_then(|credential| credential.id).or_else(|| Some(ObjectId::new())),
+            user: id,
+            secret: encrypt(&secret),
+            enabled: false,
+            recovery_codes: Vec::new(),
+            last_step: 0,
+            failed_attempts: 0,
+            locked_until: None,
+            created: existing.map(|credential| credential.created).unwrap_or(now),
+            modified: now,
+        };
+        let credential_id = credential.id.unwrap_or_default();
+        get_credentials_repo().await.upsert_by_id(&credential_id, credential).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(TwoFactorEnrollment { secret, otpauth_uri: totp.get_url() })
+    }
+
+    /// Accepts a TOTP `code` for `credential`, once: the code's time step must be later than that of the last code accepted.
+    async fn accept_code(credential: &TwoFactorCredential, code: &str, mut update: Document) -> Result<(), TwoFactorError> {
+        let step = accepted_step(&stored_totp(credential)?, code, credential.last_step, Utc::now()).ok_or(TwoFactorError::InvalidCode)?;
+        // Checked again on write, for two logins sending the same code at once.
+        update.insert("last_step", step);
+        update.insert("modified", to_bson(&Utc::now()).map_err(unexpected)?);
+        let accepted = get_credentials_repo().await
+            .update_one(doc! { "_id": credential.id, "last_step": { "$lt": step } }, doc! { "$set": update })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        if accepted { Ok(()) } else { Err(TwoFactorError::InvalidCode) }
+    }
+
+    /// Turns on two-factor authentication once `code` shows the secret from `start_enrollment` reached the
+    /// authenticator app, and returns the recovery codes.
+    pub async fn confirm_enrollment(user: &ObjectId, code: &str) -> Result<RecoveryCodes, TwoFactorError> {
+        let credential = find(user).await?.ok_or(TwoFactorError::NotEnrolled)?;
+        if credential.enabled {
+            return Err(TwoFactorError::AlreadyEnabled);
+        }
+        let (codes, hashes) = new_recovery_codes();
+        accept_code(&credential, code.trim(), doc! { "enabled": true, "recovery_codes": hashes }).await?;
+        Ok(RecoveryCodes { codes })
+    }
+
+    /// Checks the second factor of a login: a code from the authenticator app, or a recovery code, which is used up.
+    /// Wrong codes count towards the lockout of the member, however many logins they are spread over.
+    pub async fn verify(user: &ObjectId, code: &str) -> Result<Verified, TwoFactorError> {
+        let credential = unlocked_credential(user).await?;
+        let code = code.trim();
+        let result = if is_totp_code(code) {
+            accept_code(&credential, code, Document::new()).await.map(|_| Verified::Code)
+        } else {
+            use_recovery_code(&credential, code).await.map(|remaining| Verified::RecoveryCode { remaining })
+        };
+        counted(&credential, result).await
+    }
+
+    async fn use_recovery_code(credential: &TwoFactorCredential, code: &str) -> Result<usize, TwoFactorError> {
+        let mut remaining = credential.recovery_codes.clone();
+        if !take_recovery_code(&mut remaining, code) {
+            return Err(TwoFactorError::InvalidCode);
+        }
+        // Pulled only if still there, for two logins sending the same code at once.
+        let hash = hash_recovery_code(code);
+        let modified = to_bson(&Utc::now()).map_err(unexpected)?;
+        let used = get_credentials_repo().await
+            .update_one(
+                doc! { "_id": credential.id, "recovery_codes": &hash },
+                doc! { "$pull": { "recovery_codes": &hash }, "$set": { "modified": modified } },
+            )
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        if used {
+            Ok(remaining.len())
+        } else {
+            Err(TwoFactorError::InvalidCode)
+        }
+    }
+
+    /// Uses up a recovery code of `user` outside of a login, such as to stand in for a lost phone number.
+    /// Returns the number of codes left.
+    pub async fn redeem_recovery_code(user: &ObjectId, code: &str) -> Result<usize, TwoFactorError> {
+        let credential = unlocked_credential(user).await?;
+        let result = use_recovery_code(&credential, code.trim()).await;
+        counted(&credential, result).await
+    }
+
+    /// Checks a code from the authenticator app only, for confirming changes to the credential itself.
+    pub async fn verify_code(user: &ObjectId, code: &str) -> Result<(), TwoFactorError> {
+        let credential = unlocked_credential(user).await?;
+        let result = accept_code(&credential, code.trim(), Document::new()).await;
+        counted(&credential, result).await
+    }
+
+    /// Replaces every recovery code of `user`, used or not.
+    pub async fn regenerate_recovery_codes(user: &ObjectId) -> Result<RecoveryCodes, TwoFactorError> {
+        let credential = find(user).await?.filter(|credential| credential.enabled).ok_or(TwoFactorError::NotEnrolled)?;
+        let (codes, hashes) = new_recovery_codes();
+        let update = doc! { "recovery_codes": hashes, "modified": to_bson(&Utc::now()).map_err(unexpected)? };
+        get_credentials_repo().await
+            .update_by_id(&credential.id.unwrap_or_default().to_hex(), update)
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(RecoveryCodes { codes })
+    }
+
+    pub async fn disable(user: &ObjectId) -> Result<(), MongoRepoError> {
+        get_credentials_repo().await
+            .delete_many(doc! { "user": user })
+            .await
+            .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Re-encrypts every TOTP secret from `old_key` to `new_key`, skipping those already rotated, the way
+    /// `Users::rotate_encryption_key` does for phone numbers. Returns the number of rotated and skipped secrets.
+    pub async fn rotate_encryption_key(old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<(u64, u64), MongoRepoError> {
+        let col = get_credentials_repo().await;
+        let credentials = col.find_many(Document::new(), None, None, None).await.map_err(unexpected)?;
+        let (mut rotated, mut skipped) = (0, 0);
+        for credential in credentials {
+            let id = credential.id.ok_or(unexpected("Two-factor credential without id"))?;
+            match decrypt_with_key(&credential.secret, old_key) {
+                Ok(secret) => {
+                    col.update_by_id(&id.to_hex(), doc! { "secret": encrypt_with_key(&secret, new_key) })
+                        .await
+                        .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+                    rotated += 1;
+                },
+                Err(_) if decrypt_with_key(&credential.secret, new_key).is_ok() => skipped += 1,
+                Err(_) => return Err(unexpected(format!("Two-factor secret {} is encrypted with an unknown key", id.to_hex()))),
+            }
+        }
+        Ok((rotated, skipped))
+    }
+}
@@ -3,27 +3,27 @@
 pub mod Users {
     use bson::{doc, oid::ObjectId, to_bson, Document};
     use tower_sessions_mongodb_store::mongodb::{Collection, error::{ErrorKind,WriteError,WriteFailure}};
-    use  crate::backend::{forms::Forms::{AuthUserForm, UserRole}, mongo_crud::{MongoRepo, MongoRepoError}, mongo_models::Docs::BaseUser, utils::server_utils::{decrypt, encrypt}, BASE_USERS};
+    use  crate::backend::{forms::{AccountStatus, UserSummary, UserView, UsersQuery, Forms::{AuthUserForm, UserRole}}, mongo_crud::{MongoRepo, MongoRepoError}, mongo_models::Docs::BaseUser, phone, utils::{server_utils::{decrypt, decrypt_with_key, encrypt, encrypt_with_key}}, BASE_USERS};
     use chrono::{Utc};
 
+    const DEFAULT_PAGE_SIZE: u64 = 20;
+    const MAX_PAGE_SIZE: u64 = 100;
+
 
     fn create_user_body(user: &AuthUserForm) -> BaseUser {
         let now = Utc::now();
         BaseUser {
             id: None,
-            password: encrypt(&user.get_password()),
+            password: encrypt(&normalize_phone_number(&user.get_password())),
             role: user.get_role().unwrap_or(UserRole::Buyer),
+            status: AccountStatus::Active,
             created: now,
             modified: now
         }
     }
 
     pub async fn get_users_repo() -> MongoRepo<BaseUser> {
-        let repo  = MongoRepo::<BaseUser>::new("sample_mflix", "base_users").await.unwrap();
-        repo.create_unique_index(doc! {
-            "password": 1
-        }).await.unwrap();
-        repo
+        MongoRepo::<BaseUser>::new("sample_mflix", BASE_USERS).await.unwrap()
     }
 
 
@@ -56,15 +56,160 @@ pub mod Users {
 
     pub async fn login_user(user: AuthUserForm) -> Result<BaseUser, MongoRepoError> {
         let col = get_users_repo().await;
-        let filter = doc! { "password": encrypt(&user.get_password()) };
+        let filter = doc! { "password": encrypt(&normalize_phone_number(&user.get_password())) };
         match col.find_one(filter).await {
             Ok(value) => {
                 let user = value.ok_or(MongoRepoError::NotFoundError("User not found".to_string()))?;
+                if !user.status.is_active() {
+                    return Err(MongoRepoError::AccountInactiveError(user.status));
+                }
                 Ok(user)
             },
             Err(e) => Err(MongoRepoError::UnexpectedError(e.to_string())),
         }
     }
-}
 
+    pub fn to_view(user: &BaseUser) -> UserView {
+        UserView {
+            id: user.id.map(|id| id.to_hex()).unwrap_or_default(),
+            role: user.role.clone(),
+            status: user.status.clone(),
+            created: user.created,
+        }
+    }
+
+    pub fn to_summary(user: &BaseUser) -> UserSummary {
+        let phone_number = decrypt(&user.password).unwrap_or_default();
+        UserSummary {
+            id: user.id.map(|id| id.to_hex()).unwrap_or_default(),
+            phone_number: phone_number.clone(),
+            phone_details: phone::details(&phone_number, phone::DEFAULT_COUNTRY).ok(),
+            role: user.role.clone(),
+            status: user.status.clone(),
+            created: user.created,
+        }
+    }
+
+    /// The phone number in E.164, which is the form it is encrypted in.
+    pub fn normalize_phone_number(input: &str) -> String {
+        phone::normalize(input)
+    }
+
+    pub async fn find_by_phone_number(phone_number: &str) -> Result<BaseUser, MongoRepoError> {
+        let col = get_users_repo().await;
+        col.find_one(doc! { "password": encrypt(&normalize_phone_number(phone_number)) }).await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?
+            .ok_or(MongoRepoError::NotFoundError("User not found".to_string()))
+    }
 
+    /// Promotes the member with `phone_number` to admin, registering them first if needed.
+    pub async fn create_admin(phone_number: &str) -> Result<BaseUser, MongoRepoError> {
+        match find_by_phone_number(phone_number).await {
+            Ok(user) => set_role(&user.id.ok_or(MongoRepoError::NotFoundError("User not found".to_string()))?, UserRole::Admin).await,
+            Err(MongoRepoError::NotFoundError(_)) => {
+                let mut admin = create_user_body(&AuthUserForm::new(normalize_phone_number(phone_number)));
+                admin.role = UserRole::Admin;
+                let col = get_users_repo().await;
+                let id = col.create(admin).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+                get_user(&id).await
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Re-encrypts every stored phone number from `old_key` to `new_key`. Numbers that only decrypt
+    /// with `new_key` were rotated by an earlier, interrupted run and are skipped, so it can be re-run.
+    /// Returns the number of rotated and skipped users.
+    pub async fn rotate_encryption_key(old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<(u64, u64), MongoRepoError> {
+        let col = get_users_repo().await;
+        let users = col.find_many(Document::new(), None, None, None).await.map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        let (mut rotated, mut skipped) = (0, 0);
+        for user in users {
+            let id = user.id.ok_or(MongoRepoError::UnexpectedError("User without id".to_string()))?;
+            match decrypt_with_key(&user.password, old_key) {
+                Ok(phone_number) => {
+                    col.update_by_id(&id.to_hex(), doc! { "password": encrypt_with_key(&phone_number, new_key) })
+                        .await
+                        .map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+                    rotated += 1;
+                },
+                Err(_) if decrypt_with_key(&user.password, new_key).is_ok() => skipped += 1,
+                Err(_) => return Err(MongoRepoError::UnexpectedError(format!("User {} is encrypted with an unknown key", id.to_hex()))),
+            }
+        }
+        Ok((rotated, skipped))
+    }
+
+    fn search_filter(query: &UsersQuery) -> Document {
+        let mut filter = Document::new();
+        if let Some(role) = &query.role {
+            filter.insert("role", to_bson(role).unwrap_or_default());
+        }
+        if let Some(search) = query.search.as_ref().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            // Phone numbers are stored encrypted, so only an exact number or an id can be matched.
+            match ObjectId::parse_str(search) {
+                Ok(id) => filter.insert("_id", id),
+                Err(_) => filter.insert("password", encrypt(&normalize_phone_number(search))),
+            };
+        }
+        filter
+    }
+
+    pub fn page_size(query: &UsersQuery) -> u64 {
+        query.per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub async fn list_users(query: &UsersQuery) -> Result<(Vec<BaseUser>, u64), MongoRepoError> {
+        let col = get_users_repo().await;
+        let filter = search_filter(query);
+        let per_page = page_size(query);
+        let page = query.page.unwrap_or(0);
+        let total = col.count(filter.clone()).await.map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        let users = col.find_many(filter, Some(doc! { "created": -1 }), Some(page * per_page), Some(per_page as i64))
+            .await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        Ok((users, total))
+    }
+
+    pub async fn get_user(id: &ObjectId) -> Result<BaseUser, MongoRepoError> {
+        let col = get_users_repo().await;
+        col.find_one(doc! { "_id": id }).await
+            .map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?
+            .ok_or(MongoRepoError::NotFoundError("User not found".to_string()))
+    }
+
+    async fn update_user(id: &ObjectId, update: Document) -> Result<BaseUser, MongoRepoError> {
+        let col = get_users_repo().await;
+        let mut update = update;
+        update.insert("modified", to_bson(&Utc::now()).unwrap_or_default());
+        col.update_by_id(&id.to_hex(), update).await.map_err(|e| MongoRepoError::WriteError(e.to_string()))?;
+        get_user(id).await
+    }
+
+    pub async fn set_role(id: &ObjectId, role: UserRole) -> Result<BaseUser, MongoRepoError> {
+        let role = to_bson(&role).map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        update_user(id, doc! { "role": role }).await
+    }
+
+    /// Replaces the phone number of `id`. The unique index on `password` refuses a number another member has,
+    /// which comes back as `MongoRepoError::PasswordExistsError`.
+    pub async fn set_phone_number(id: &ObjectId, phone_number: &str) -> Result<BaseUser, MongoRepoError> {
+        let phone_number = normalize_phone_number(phone_number);
+        let col = get_users_repo().await;
+        let update = doc! { "password": encrypt(&phone_number), "modified": to_bson(&Utc::now()).unwrap_or_default() };
+        match col.update_by_id(&id.to_hex(), update).await {
+            Ok(_) => get_user(id).await,
+            Err(e) => match e.kind.as_ref() {
+                ErrorKind::Write(WriteFailure::WriteError(write_err)) if write_err.code == 11000 => {
+                    Err(MongoRepoError::PasswordExistsError(phone_number))
+                },
+                _ => Err(MongoRepoError::WriteError(e.to_string())),
+            }
+        }
+    }
+
+    pub async fn set_status(id: &ObjectId, status: AccountStatus) -> Result<BaseUser, MongoRepoError> {
+        let status = to_bson(&status).map_err(|e| MongoRepoError::UnexpectedError(e.to_string()))?;
+        update_user(id, doc! { "status": status }).await
+    }
+}
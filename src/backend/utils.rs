@@ -15,15 +15,26 @@ pub mod server_utils {
     fn get_key() -> [u8; 32] {
         dotenv().ok();
         let key = std::env::var("ENCRYPTION_KEY").expect("ENCRYPTION_KEY must be set");
-        let key_bytes = hex::decode(key).expect("ENCRYPTION_KEY must be a valid hex string");
+        parse_key(&key).expect("ENCRYPTION_KEY must be a 32 byte hex string")
+    }
+
+    pub fn parse_key(hex_key: &str) -> Result<[u8; 32], String> {
+        let key_bytes = hex::decode(hex_key.trim()).map_err(|e| e.to_string())?;
         key_bytes
             .try_into()
-            .expect("ENCRYPTION_KEY must be exactly 32 bytes")
+            .map_err(|_| String::from("key must be exactly 32 bytes"))
     }
 
     pub fn encrypt(plain: &str) -> String {
-        let key_bytes = get_key();
-        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+        encrypt_with_key(plain, &get_key())
+    }
+
+    pub fn decrypt(encoded: &str) -> Result<String, aes_gcm::Error> {
+        decrypt_with_key(encoded, &get_key())
+    }
+
+    pub fn encrypt_with_key(plain: &str, key_bytes: &[u8; 32]) -> String {
+        let key = Key::<Aes256Gcm>::from_slice(key_bytes);
         let cipher = Aes256Gcm::new(key);
 
         let hash = Sha256::digest(plain.as_bytes());
@@ -37,19 +48,21 @@ pub mod server_utils {
         general_purpose::STANDARD.encode(&combined)
     }
 
-    pub fn decrypt(encoded: &str) -> Result<String, aes_gcm::Error> {
-        let key_bytes = get_key();
-        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+    pub fn decrypt_with_key(encoded: &str, key_bytes: &[u8; 32]) -> Result<String, aes_gcm::Error> {
+        let key = Key::<Aes256Gcm>::from_slice(key_bytes);
         let cipher = Aes256Gcm::new(key);
 
-        let combined = general_purpose::STANDARD.decode(encoded).expect("Invalid base64");
+        let combined = general_purpose::STANDARD.decode(encoded).map_err(|_| aes_gcm::Error)?;
+        if combined.len() < 12 {
+            return Err(aes_gcm::Error);
+        }
 
         let (nonce_bytes, ciphertext) = combined.split_at(12);
         let nonce = Nonce::from_slice(nonce_bytes);
 
         let plaintext_bytes = cipher.decrypt(nonce, ciphertext)?;
 
-        Ok(String::from_utf8(plaintext_bytes).expect("Invalid UTF-8"))
+        String::from_utf8(plaintext_bytes).map_err(|_| aes_gcm::Error)
     }
 }
 
@@ -75,15 +88,3 @@ pub fn read_local_storage_value(key:&str) -> String{
         .and_then(|storage| storage.get(key).ok().flatten())
         .unwrap_or_else(|| get_system_theme_mode())
 }
-
-use phonenumber::{parse,country};
-
-
-pub fn format_phone_number(input: &str) -> String{
-    if let Ok(num) = parse(Some(country::TZ), input) {
-        //num.format().mode(phonenumber::Mode::National).to_string()
-        num.format().mode(phonenumber::Mode::International).to_string()
-    } else {
-        input.to_string()
-    }
-}
\ No newline at end of file
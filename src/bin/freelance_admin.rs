@@ -0,0 +1,160 @@
+use std::{path::Path, process::exit};
+
+use dotenvy::dotenv;
+use freelance_test::backend::{
+    api::routes::Routes,
+    audit::Audit::{self, RequestMeta},
+    auths::auth_session::AuthSession,
+    forms::{AuditAction, UserRole},
+    migrations::runner::Runner,
+    seed::Seed::{self, SeedOptions},
+    transfer::Transfer,
+    two_factor::TwoFactor,
+    users::Users,
+    utils::server_utils::parse_key,
+};
+
+const USAGE: &str = "Usage: freelance-admin <command> [args]
+
+Commands:
+    create-admin <phone>                 register <phone> as an admin, or promote the existing member
+    set-role <phone> <buyer|seller|admin>
+    enroll-two-factor <phone>            set up the authenticator app of a seller or admin who cannot sign in without it
+    migrate [up | down <version> | status]
+    rotate-key <new-hex-key>             re-encrypt phone numbers and TOTP secrets from ENCRYPTION_KEY to <new-hex-key>
+    seed [--seed <n>] [--sellers <n>] [--buyers <n>] [--assets <dir>] [--today <YYYY-MM-DD>]
+                                         fill the database with demo data around <today> (2026-10-19 by default); safe to re-run
+    purge-sessions                       delete expired sessions and their user links
+    export <collection> <file>           write <collection> to <file> as extended JSON lines
+    import <collection> <file>           upsert documents from <file> into <collection> by _id
+    openapi                              print the REST API's OpenAPI document (docs/openapi.json)";
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    exit(1);
+}
+
+fn arg(args: &[String], index: usize) -> &str {
+    args.get(index).map(String::as_str).unwrap_or_else(|| fail(USAGE))
+}
+
+fn parse_role(role: &str) -> UserRole {
+    match role.to_ascii_lowercase().as_str() {
+        "buyer" => UserRole::Buyer,
+        "seller" => UserRole::Seller,
+        "admin" => UserRole::Admin,
+        _ => fail(format!("Unknown role `{}`; expected buyer, seller or admin", role)),
+    }
+}
+
+fn seed_options(args: &[String]) -> SeedOptions {
+    let mut options = SeedOptions::default();
+    for pair in args.chunks(2) {
+        let value = pair.get(1).map(String::as_str).unwrap_or_else(|| fail(USAGE));
+        let count = || value.parse::<usize>().unwrap_or_else(|_| fail(format!("{} expects a number", pair[0])));
+        match pair[0].as_str() {
+            "--seed" => options.seed = value.parse().unwrap_or_else(|_| fail("--seed expects a number")),
+            "--sellers" => options.sellers = count(),
+            "--buyers" => options.buyers = count(),
+            "--assets" => options.assets = value.into(),
+            "--today" => options.today = value.parse().unwrap_or_else(|_| fail("--today expects a date as YYYY-MM-DD")),
+            other => fail(format!("Unknown seed option `{}`\n\n{}", other, USAGE)),
+        }
+    }
+    options
+}
+
+fn cli_meta() -> RequestMeta {
+    RequestMeta { ip: None, user_agent: Some(Audit::CLI_ACTOR.to_string()) }
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    // Logs go to stderr so that command output such as `openapi` stays clean
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    match args.first().map(String::as_str) {
+        Some("create-admin") => {
+            let user = Users::create_admin(arg(&args, 1)).await.unwrap_or_else(|e| fail(e));
+            Audit::record(AuditAction::RoleChanged, None, user.id.map(|id| id.to_hex()), &cli_meta(), Some(String::from("create-admin -> Admin"))).await;
+            println!("{} is now an admin", user.id.map(|id| id.to_hex()).unwrap_or_default());
+        },
+        Some("set-role") => {
+            let role = parse_role(arg(&args, 2));
+            let user = Users::find_by_phone_number(arg(&args, 1)).await.unwrap_or_else(|e| fail(e));
+            let id = user.id.unwrap_or_else(|| fail("User has no id"));
+            Users::set_role(&id, role.clone()).await.unwrap_or_else(|e| fail(e));
+            Audit::record(AuditAction::RoleChanged, None, Some(id.to_hex()), &cli_meta(), Some(format!("{:?} -> {:?}", user.role, role))).await;
+            println!("{} is now {:?}", id.to_hex(), role);
+        },
+        Some("enroll-two-factor") => {
+            let user = Users::find_by_phone_number(arg(&args, 1)).await.unwrap_or_else(|e| fail(e));
+            let id = user.id.unwrap_or_else(|| fail("User has no id"));
+            if !TwoFactor::available_for(&user.role) {
+                fail(format!("{} is a {:?}; only sellers and admins use two-factor authentication", id.to_hex(), user.role));
+            }
+            let enrollment = TwoFactor::start_enrollment(&user).await.unwrap_or_else(|e| fail(e));
+            println!("Add this account to the member's authenticator app:");
+            println!("  {}", enrollment.otpauth_uri);
+            println!("  secret: {}", enrollment.secret);
+            eprint!("Code shown by the app: ");
+            let mut code = String::new();
+            std::io::stdin().read_line(&mut code).unwrap_or_else(|e| fail(e));
+            let codes = TwoFactor::confirm_enrollment(&id, &code).await.unwrap_or_else(|e| fail(e));
+            Audit::record(AuditAction::TwoFactorEnabled, None, Some(id.to_hex()), &cli_meta(), Some(String::from("enroll-two-factor"))).await;
+            println!("Two-factor authentication is on. Give the member their recovery codes, shown only this once:");
+            for code in codes.codes {
+                println!("  {}", code);
+            }
+        },
+        Some("migrate") => {
+            if let Err(e) = Runner::run_cli(&args[1..]).await {
+                fail(e);
+            }
+        },
+        Some("rotate-key") => {
+            let old_key = std::env::var("ENCRYPTION_KEY").unwrap_or_else(|_| fail("ENCRYPTION_KEY must be set"));
+            let old_key = parse_key(&old_key).unwrap_or_else(|e| fail(format!("ENCRYPTION_KEY: {}", e)));
+            let new_key = parse_key(arg(&args, 1)).unwrap_or_else(|e| fail(format!("New key: {}", e)));
+            let (rotated, skipped) = Users::rotate_encryption_key(&old_key, &new_key).await.unwrap_or_else(|e| fail(e));
+            println!("Re-encrypted {} users ({} already used the new key)", rotated, skipped);
+            let (rotated, skipped) = TwoFactor::rotate_encryption_key(&old_key, &new_key).await.unwrap_or_else(|e| fail(e));
+            println!("Re-encrypted {} two-factor secrets ({} already used the new key)", rotated, skipped);
+            println!("Set ENCRYPTION_KEY to the new key before restarting the server");
+        },
+        Some("seed") => {
+            let options = seed_options(&args[1..]);
+            let report = Seed::run(&options).await.unwrap_or_else(|e| fail(e));
+            println!("Seeded with --seed {}; newly inserted:", options.seed);
+            println!("  {} users, {} storefronts, {} listings, {} images", report.users, report.storefronts, report.listings, report.media);
+            println!("  {} bookings, {} reviews", report.bookings, report.reviews);
+        },
+        Some("purge-sessions") => {
+            let (sessions, links) = AuthSession::purge_expired_sessions().await.unwrap_or_else(|e| fail(e));
+            println!("Deleted {} expired sessions and {} stale user links", sessions, links);
+        },
+        Some("export") => {
+            let (collection, file) = (arg(&args, 1), arg(&args, 2));
+            let db = Runner::database().await;
+            let exported = Transfer::export_collection(&db, collection, Path::new(file)).await.unwrap_or_else(|e| fail(e));
+            let details = format!("{} documents from {} to {}", exported, collection, file);
+            Audit::record(AuditAction::DataExported, None, Some(collection.to_string()), &cli_meta(), Some(details)).await;
+            println!("Exported {} documents", exported);
+        },
+        Some("import") => {
+            let (collection, file) = (arg(&args, 1), arg(&args, 2));
+            let db = Runner::database().await;
+            let imported = Transfer::import_collection(&db, collection, Path::new(file)).await.unwrap_or_else(|e| fail(e));
+            let details = format!("{} documents from {} to {}", imported, file, collection);
+            Audit::record(AuditAction::DataImported, None, Some(collection.to_string()), &cli_meta(), Some(details)).await;
+            println!("Imported {} documents", imported);
+        },
+        Some("openapi") => {
+            let document = Routes::openapi().to_pretty_json().unwrap_or_else(|e| fail(e));
+            println!("{}", document);
+        },
+        Some("help") | Some("--help") | Some("-h") => println!("{}", USAGE),
+        _ => fail(USAGE),
+    }
+}
@@ -0,0 +1,59 @@
+use dioxus::prelude::*;
+
+use crate::backend::forms::AccountStatus;
+use crate::frontend::navbar;
+
+/// Status returned by the server when the current member was refused because of a suspension or ban.
+pub static ACCOUNT_STATUS: GlobalSignal<Option<AccountStatus>> = Signal::global(|| None);
+
+#[component]
+pub fn AccountStatusPage() -> Element {
+    rsx! {
+        div { "data-theme": "light", class: "w-screen h-screen flex flex-col",
+            navbar::NavigationBar {}
+            div { class: "flex flex-col items-center justify-center h-full p-8",
+                match ACCOUNT_STATUS() {
+                    Some(status) => rsx! {
+                        AccountStatusNotice { status }
+                    },
+                    None => rsx! {
+                        p { class: "text-lg", "Your account is active." }
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn AccountStatusNotice(status: AccountStatus) -> Element {
+    let (title, detail, reason) = match &status {
+        AccountStatus::Suspended { until, reason } => (
+            "Your account is suspended",
+            format!("You will be able to sign in again on {}.", until.format("%d %B %Y at %H:%M UTC")),
+            reason.clone(),
+        ),
+        AccountStatus::Banned { reason } => (
+            "Your account has been closed",
+            String::from("This account can no longer be used on Freelancer."),
+            reason.clone(),
+        ),
+        AccountStatus::Active => ("Your account is active", String::new(), String::new()),
+    };
+    rsx! {
+        div { class: "card bg-base-100 shadow-sm w-full max-w-xl",
+            div { class: "card-body space-y-2",
+                p { class: "card-title text-2xl", "{title}" }
+                p { "{detail}" }
+                if !reason.is_empty() {
+                    div { role: "alert", class: "alert alert-soft alert-warning",
+                        span { "Reason: {reason}" }
+                    }
+                }
+                p { class: "text-sm text-[var(--color-neutral)]",
+                    "If you believe this is a mistake, contact our support team."
+                }
+            }
+        }
+    }
+}
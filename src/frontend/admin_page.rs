@@ -1,9 +1,1124 @@
-
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use dioxus::prelude::*;
+use http::StatusCode;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use freelance_test::api_client::ApiClient;
+use crate::{backend::{api::auth, forms::{AccountStatus, ApiError, AuditAction, AuditPage, AuditQuery, BanForm, BookingStatus, ChangeRoleForm, PhoneChangeForm, RecoverPhoneNumberForm, SuspendForm, StorefrontStatus, StorefrontSummary, UserDetail, UserRole, UserSummary, UsersPage, UsersQuery}, phone}, frontend::form_builder::{AsyncValidator, FormControl, FormGroup, GroupValidator, ValidateOn, Validator}};
+
+const PAGE_SIZE: u64 = 20;
+/// Largest page of audit entries the server returns.
+const MAX_AUDIT_PAGE_SIZE: u64 = 200;
+const ROLES: [UserRole; 3] = [UserRole::Buyer, UserRole::Seller, UserRole::Admin];
+
+#[derive(Clone, Copy, PartialEq)]
+enum AdminTab {
+    Users,
+    Storefronts,
+    Audit,
+}
+
+#[derive(Clone, Copy, Default)]
+struct AdminRequests;
+
+impl AdminRequests {
+    fn client(&self) -> ApiClient {
+        ApiClient::default()
+    }
+
+    async fn users(&self, query: UsersQuery) -> Result<UsersPage, ApiError> {
+        let mut params = vec![
+            ("page", query.page.unwrap_or(0).to_string()),
+            ("per_page", query.per_page.unwrap_or(PAGE_SIZE).to_string()),
+        ];
+        if let Some(search) = query.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            params.push(("search", search.to_string()));
+        }
+        if let Some(role) = &query.role {
+            params.push(("role", role_label(role).to_string()));
+        }
+        self.client().get("/admin/users", &params).await
+    }
+
+    async fn user(&self, id: &str) -> Result<UserDetail, ApiError> {
+        self.client().get(&format!("/admin/users/{id}"), &[]).await
+    }
+
+    async fn change_role(&self, id: &str, role: UserRole) -> Result<UserSummary, ApiError> {
+        self.client().put(&format!("/admin/users/{id}/role"), Some(&ChangeRoleForm { role })).await
+    }
+
+    async fn suspend(&self, id: &str, form: SuspendForm) -> Result<UserSummary, ApiError> {
+        self.client().post(&format!("/admin/users/{id}/suspend"), Some(&form)).await
+    }
+
+    async fn ban(&self, id: &str, form: BanForm) -> Result<UserSummary, ApiError> {
+        self.client().post(&format!("/admin/users/{id}/ban"), Some(&form)).await
+    }
+
+    async fn reactivate(&self, id: &str) -> Result<UserSummary, ApiError> {
+        self.client().post::<(), _>(&format!("/admin/users/{id}/reactivate"), None).await
+    }
+
+    async fn recover_phone_number(&self, id: &str, form: RecoverPhoneNumberForm) -> Result<UserSummary, ApiError> {
+        self.client().post(&format!("/admin/users/{id}/phone-number"), Some(&form)).await
+    }
+
+    async fn storefronts(&self) -> Result<Vec<StorefrontSummary>, ApiError> {
+        self.client().get("/admin/storefronts", &[("status", String::from("Pending"))]).await
+    }
+
+    async fn review_storefront(&self, id: &str, approve: bool) -> Result<StorefrontSummary, ApiError> {
+        let action = if approve { "approve" } else { "reject" };
+        self.client().post::<(), _>(&format!("/admin/storefronts/{id}/{action}"), None).await
+    }
+
+    async fn audit_log(&self, query: &AuditQuery) -> Result<AuditPage, ApiError> {
+        self.client().get("/admin/audit", &audit_params(query)).await
+    }
+}
+
+fn audit_params(query: &AuditQuery) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        ("page", query.page.unwrap_or(0).to_string()),
+        ("per_page", query.per_page.unwrap_or(PAGE_SIZE).to_string()),
+    ];
+    if let Some(action) = &query.action {
+        params.push(("action", action.as_str().to_string()));
+    }
+    for (key, value) in [("actor", &query.actor), ("target", &query.target)] {
+        if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            params.push((key, value.to_string()));
+        }
+    }
+    if let Some(from) = query.from {
+        params.push(("from", from.to_rfc3339()));
+    }
+    if let Some(to) = query.to {
+        params.push(("to", to.to_rfc3339()));
+    }
+    params
+}
+
+/// Link downloading the audit log as CSV; a plain navigation so the browser handles the attachment.
+fn audit_export_url(query: &AuditQuery) -> String {
+    let encode = |value: &str| {
+        value.bytes().map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        }).collect::<String>()
+    };
+    let params = audit_params(query)
+        .iter()
+        .filter(|(key, _)| *key != "page" && *key != "per_page")
+        .map(|(key, value)| format!("{}={}", key, encode(value)))
+        .collect::<Vec<String>>()
+        .join("&");
+    format!("{}?{params}", ApiClient::default().url("/admin/audit/export"))
+}
+
+/// Start of `day` in UTC.
+fn start_of_day(day: NaiveDate) -> DateTime<Utc> {
+    day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+/// The control at `path` of `form`, which the form is built with.
+fn control(form: &FormGroup, path: &str) -> FormControl {
+    *form.get_control(path).expect("control of the form").lock().unwrap()
+}
+
+/// Message for the first error of `control`, once the admin is done with it.
+fn field_error(control: &FormControl) -> Option<&'static str> {
+    if !control.touched() {
+        return None;
+    }
+    control.errors().first().map(|error| match error.as_str() {
+        "required" => "Required",
+        "invalidPhoneNumber" => "Enter a valid mobile number",
+        "alreadyRegistered" => "This phone number is already registered",
+        "unchecked" => "Confirm that you checked the member's identity",
+        "invalidDate" => "Enter a valid date",
+        "notAnInteger" | "outOfRange" => "Enter a number from 1 to 200",
+        _ => "Invalid value",
+    })
+}
+
+#[component]
+fn FieldError(control: FormControl) -> Element {
+    rsx! {
+        if let Some(error) = field_error(&control) {
+            p { class: "label text-sm text-[var(--color-error)]", "{error}" }
+        }
+    }
+}
+
+/// Message for the first error of the group validators of `group`, once `validate_all` ran them and the admin
+/// touched one of its controls.
+fn group_error(group: &FormGroup) -> Option<&'static str> {
+    if !group.is_touched() {
+        return None;
+    }
+    group.errors().first().map(|error| match error.as_str() {
+        "numbersDiffer" => "The phone numbers do not match",
+        "invalidRange" => "The start date has to come before the end date",
+        _ => "Invalid value",
+    })
+}
+
+#[component]
+fn GroupError(group: FormGroup) -> Element {
+    rsx! {
+        if let Some(error) = group_error(&group) {
+            p { class: "label text-sm text-[var(--color-error)]", "{error}" }
+        }
+    }
+}
+
+/// Input editing the text of `control`, `kind` being its type, like `date` or `number`.
+#[component]
+fn ControlInput(control: FormControl, kind: &'static str, placeholder: &'static str, class: &'static str) -> Element {
+    let mut control = control;
+    rsx! {
+        input {
+            class,
+            r#type: kind,
+            placeholder,
+            value: control.get_value(),
+            oninput: move |evt| control.handle_input(evt.value()),
+            onfocusout: move |_| control.handle_blur(),
+        }
+    }
+}
+
+fn role_label(role: &UserRole) -> &'static str {
+    match role {
+        UserRole::Buyer => "Buyer",
+        UserRole::Seller => "Seller",
+        UserRole::Admin => "Admin",
+    }
+}
+
+fn short_date(date: &DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn short_datetime(date: &DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d %H:%M").to_string()
+}
+
+fn error_message(error: &ApiError) -> String {
+    match StatusCode::from_u16(error.status) {
+        Ok(StatusCode::UNAUTHORIZED) => String::from("Sign in with an administrator account to continue."),
+        Ok(StatusCode::FORBIDDEN) => String::from("Your account is not allowed to use the admin console."),
+        Ok(StatusCode::CONFLICT) => String::from("You cannot change your own account."),
+        Ok(StatusCode::ALREADY_REPORTED) => String::from("That phone number belongs to another member."),
+        Ok(StatusCode::NOT_FOUND) => String::from("The record no longer exists."),
+        Ok(StatusCode::SERVICE_UNAVAILABLE) => String::from("The server could not be reached."),
+        _ => format!("Request failed ({}): {}", error.status, error.message),
+    }
+}
 
 #[component]
 pub fn AdminPage() -> Element{
+    let mut tab = use_signal(|| AdminTab::Users);
+    let tab_class = move |value: AdminTab| if tab() == value { "tab tab-active" } else { "tab" };
+    rsx! {
+        div { "data-theme": "light", class: "w-screen min-h-screen flex flex-col",
+            div { class: "navbar bg-base-100 shadow-sm px-4",
+                a { href: "/", class: "btn btn-ghost text-xl", "Freelancer" }
+                p { class: "text-lg font-semibold", "Admin console" }
+            }
+            div { class: "p-4 lg:p-8 flex flex-col space-y-4",
+                div { role: "tablist", class: "tabs tabs-box w-fit",
+                    a {
+                        role: "tab",
+                        class: tab_class(AdminTab::Users),
+                        onclick: move |_| tab.set(AdminTab::Users),
+                        "Users"
+                    }
+                    a {
+                        role: "tab",
+                        class: tab_class(AdminTab::Storefronts),
+                        onclick: move |_| tab.set(AdminTab::Storefronts),
+                        "Storefront approvals"
+                    }
+                    a {
+                        role: "tab",
+                        class: tab_class(AdminTab::Audit),
+                        onclick: move |_| tab.set(AdminTab::Audit),
+                        "Audit log"
+                    }
+                }
+                match tab() {
+                    AdminTab::Users => rsx! {
+                        UsersPanel {}
+                    },
+                    AdminTab::Storefronts => rsx! {
+                        StorefrontsPanel {}
+                    },
+                    AdminTab::Audit => rsx! {
+                        AuditPanel {}
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn UsersPanel() -> Element {
+    let requests = AdminRequests;
+    let mut search_input = use_signal(String::new);
+    let mut search = use_signal(String::new);
+    let mut role_filter: Signal<Option<UserRole>> = use_signal(|| None);
+    let mut page = use_signal(|| 0u64);
+    let mut selected: Signal<Option<String>> = use_signal(|| None);
+    let mut blocking: Signal<Option<UserSummary>> = use_signal(|| None);
+    let mut recovering: Signal<Option<UserSummary>> = use_signal(|| None);
+    let mut action_error: Signal<Option<String>> = use_signal(|| None);
+    let mut users = use_resource(move || async move {
+        let query = UsersQuery {
+            page: Some(page()),
+            per_page: Some(PAGE_SIZE),
+            search: Some(search()),
+            role: role_filter(),
+        };
+        requests.users(query).await
+    });
+
+    let mut on_action = move |result: Result<UserSummary, ApiError>| {
+        match result {
+            Ok(_) => action_error.set(None),
+            Err(error) => action_error.set(Some(error_message(&error))),
+        }
+        users.restart();
+    };
+
+    rsx! {
+        form {
+            class: "flex flex-col lg:flex-row gap-2",
+            onsubmit: move |_| {
+                page.set(0);
+                search.set(search_input());
+            },
+            input {
+                class: "input w-full lg:w-96",
+                placeholder: "Search by phone number or user id",
+                r#type: "search",
+                value: search_input,
+                oninput: move |evt| search_input.set(evt.value()),
+            }
+            select {
+                class: "select w-full lg:w-48",
+                onchange: move |evt| {
+                    page.set(0);
+                    role_filter.set(ROLES.iter().find(|r| role_label(r) == evt.value()).cloned());
+                },
+                option { value: "", "All roles" }
+                for role in ROLES.iter() {
+                    option { value: role_label(role), "{role_label(role)}" }
+                }
+            }
+            button { class: "btn btn-secondary", r#type: "submit", "Search" }
+        }
+        if let Some(message) = action_error() {
+            div { role: "alert", class: "alert alert-soft alert-error", span { "{message}" } }
+        }
+        match &*users.read() {
+            None => rsx! {
+                span { class: "loading loading-spinner" }
+            },
+            Some(Err(error)) => rsx! {
+                div { role: "alert", class: "alert alert-soft alert-error", span { "{error_message(error)}" } }
+            },
+            Some(Ok(result)) => {
+                let last_page = result.total.saturating_sub(1) / result.per_page.max(1);
+                let current = result.page;
+                rsx! {
+                    div { class: "overflow-x-auto",
+                        table { class: "table",
+                            thead {
+                                tr {
+                                    th { "Phone number" }
+                                    th { "Role" }
+                                    th { "Status" }
+                                    th { "Joined" }
+                                    th {}
+                                }
+                            }
+                            tbody {
+                                for user in result.users.iter().cloned() {
+                                    UserRow {
+                                        key: "{user.id}",
+                                        user: user.clone(),
+                                        on_role: move |(id, role): (String, UserRole)| async move {
+                                            on_action(requests.change_role(&id, role).await);
+                                        },
+                                        on_reactivate: move |id: String| async move {
+                                            on_action(requests.reactivate(&id).await);
+                                        },
+                                        on_block: move |user: UserSummary| blocking.set(Some(user)),
+                                        on_recover: move |user: UserSummary| recovering.set(Some(user)),
+                                        on_select: move |id: String| selected.set(Some(id)),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div { class: "flex flex-row items-center justify-between",
+                        p { class: "text-sm", "{result.total} users" }
+                        div { class: "join",
+                            button {
+                                class: "join-item btn btn-sm",
+                                disabled: current == 0,
+                                onclick: move |_| page.set(current.saturating_sub(1)),
+                                "«"
+                            }
+                            button { class: "join-item btn btn-sm", "Page {current + 1} of {last_page + 1}" }
+                            button {
+                                class: "join-item btn btn-sm",
+                                disabled: current >= last_page,
+                                onclick: move |_| page.set(current + 1),
+                                "»"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(user) = blocking() {
+            BlockUserModal {
+                user,
+                on_close: move |_| blocking.set(None),
+                on_submit: move |(id, block): (String, BlockAction)| async move {
+                    blocking.set(None);
+                    let result = match block {
+                        BlockAction::Suspend(form) => requests.suspend(&id, form).await,
+                        BlockAction::Ban(form) => requests.ban(&id, form).await,
+                    };
+                    on_action(result);
+                },
+            }
+        }
+        if let Some(user) = recovering() {
+            RecoverPhoneModal {
+                user,
+                on_close: move |_| recovering.set(None),
+                on_submit: move |(id, form): (String, RecoverPhoneNumberForm)| async move {
+                    recovering.set(None);
+                    on_action(requests.recover_phone_number(&id, form).await);
+                },
+            }
+        }
+        if let Some(id) = selected() {
+            UserDetailModal { id, on_close: move |_| selected.set(None) }
+        }
+    }
+}
+
+#[component]
+fn UserRow(user: UserSummary, on_role: EventHandler<(String, UserRole)>, on_reactivate: EventHandler<String>, on_block: EventHandler<UserSummary>, on_recover: EventHandler<UserSummary>, on_select: EventHandler<String>) -> Element {
+    let active = user.status.is_active();
+    let role_id = user.id.clone();
+    let reactivate_id = user.id.clone();
+    let select_id = user.id.clone();
+    let blocked_user = user.clone();
+    let recovered_user = user.clone();
+    rsx! {
+        tr {
+            td {
+                p { "{user.phone_number}" }
+                if let Some(carrier) = user.phone_details.as_ref().and_then(|d| d.carrier.clone()) {
+                    p { class: "text-xs text-[var(--color-neutral)]", "{carrier}" }
+                }
+            }
+            td {
+                select {
+                    class: "select select-sm",
+                    onchange: move |evt| {
+                        if let Some(role) = ROLES.iter().find(|r| role_label(r) == evt.value()) {
+                            on_role.call((role_id.clone(), role.clone()));
+                        }
+                    },
+                    for role in ROLES.iter() {
+                        option {
+                            value: role_label(role),
+                            selected: *role == user.role,
+                            "{role_label(role)}"
+                        }
+                    }
+                }
+            }
+            td {
+                StatusBadge { status: user.status.clone() }
+            }
+            td { "{short_date(&user.created)}" }
+            td { class: "flex flex-row gap-2 justify-end",
+                button {
+                    class: "btn btn-sm btn-ghost",
+                    onclick: move |_| on_select.call(select_id.clone()),
+                    "Details"
+                }
+                button {
+                    class: "btn btn-sm btn-ghost",
+                    onclick: move |_| on_recover.call(recovered_user.clone()),
+                    "Change number"
+                }
+                if active {
+                    button {
+                        class: "btn btn-sm btn-outline btn-error",
+                        onclick: move |_| on_block.call(blocked_user.clone()),
+                        "Suspend or ban"
+                    }
+                } else {
+                    button {
+                        class: "btn btn-sm btn-outline btn-success",
+                        onclick: move |_| on_reactivate.call(reactivate_id.clone()),
+                        "Reactivate"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn StatusBadge(status: AccountStatus) -> Element {
+    match status {
+        AccountStatus::Suspended { until, reason } if !status.is_active() => rsx! {
+            span { class: "badge badge-warning", title: "{reason}", "Suspended until {short_date(&until)}" }
+        },
+        AccountStatus::Banned { reason } => rsx! {
+            span { class: "badge badge-error", title: "{reason}", "Banned" }
+        },
+        _ => rsx! {
+            span { class: "badge badge-success", "Active" }
+        },
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum BlockAction {
+    Suspend(SuspendForm),
+    Ban(BanForm),
+}
+
+const SUSPENSION_DAYS: [i64; 4] = [1, 7, 30, 90];
+
+#[component]
+fn BlockUserModal(user: UserSummary, on_close: EventHandler<()>, on_submit: EventHandler<(String, BlockAction)>) -> Element {
+    let mut reason = use_signal(String::new);
+    // `None` bans the account, `Some(days)` suspends it for that many days.
+    let mut days: Signal<Option<i64>> = use_signal(|| Some(SUSPENSION_DAYS[1]));
+    let id = user.id.clone();
+    let submit = move |_| {
+        let reason = reason().trim().to_string();
+        if reason.is_empty() {
+            return;
+        }
+        let action = match days() {
+            Some(days) => BlockAction::Suspend(SuspendForm { until: Utc::now() + Duration::days(days), reason }),
+            None => BlockAction::Ban(BanForm { reason }),
+        };
+        on_submit.call((id.clone(), action));
+    };
+    rsx! {
+        div { class: "modal modal-open",
+            div { class: "modal-box space-y-4",
+                p { class: "text-2xl font-semibold", "Restrict {user.phone_number}" }
+                select {
+                    class: "select w-full",
+                    onchange: move |evt| days.set(evt.value().parse::<i64>().ok()),
+                    for option_days in SUSPENSION_DAYS {
+                        option {
+                            value: "{option_days}",
+                            selected: days() == Some(option_days),
+                            "Suspend for {option_days} days"
+                        }
+                    }
+                    option { value: "ban", "Ban permanently" }
+                }
+                textarea {
+                    class: "textarea w-full",
+                    placeholder: "Reason shown to the member",
+                    value: reason,
+                    oninput: move |evt| reason.set(evt.value()),
+                }
+                div { class: "modal-action",
+                    button { class: "btn", onclick: move |_| on_close.call(()), "Cancel" }
+                    button {
+                        class: "btn btn-error",
+                        disabled: reason().trim().is_empty(),
+                        onclick: submit,
+                        if days().is_some() { "Suspend" } else { "Ban" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Gives a member who lost their phone number a new one, once their identity was checked some other way. The
+/// reason is kept in the audit log.
+#[component]
+fn RecoverPhoneModal(user: UserSummary, on_close: EventHandler<()>, on_submit: EventHandler<(String, RecoverPhoneNumberForm)>) -> Element {
+    let form = use_hook(recover_phone_form);
+    let (phone_number, confirm_phone_number, reason) = (control(&form, "phone_number"), control(&form, "confirm_phone_number"), control(&form, "reason"));
+    let mut identity_checked = control(&form, "identity_checked");
+    let id = user.id.clone();
+    let submit = move |_| {
+        if form.is_pending() || !form.validate_all().is_empty() {
+            return;
+        }
+        if let Ok(mut recovery) = serde_json::from_value::<RecoverPhoneNumberForm>(Value::Object(form.to_json())) {
+            recovery.reason = recovery.reason.trim().to_string();
+            on_submit.call((id.clone(), recovery));
+        }
+    };
+    rsx! {
+        div { class: "modal modal-open",
+            div { class: "modal-box space-y-4",
+                p { class: "text-2xl font-semibold", "Change the number of {user.phone_number}" }
+                p { class: "text-sm",
+                    "Only for members who lost their number. They are signed out everywhere and sign in again with the new one."
+                }
+                div {
+                    ControlInput { control: phone_number, kind: "tel", placeholder: "New phone number", class: "input w-full" }
+                    FieldError { control: phone_number }
+                }
+                div {
+                    ControlInput { control: confirm_phone_number, kind: "tel", placeholder: "New phone number again", class: "input w-full" }
+                    FieldError { control: confirm_phone_number }
+                    GroupError { group: form }
+                }
+                div {
+                    textarea {
+                        class: "textarea w-full",
+                        placeholder: "How the member's identity was checked",
+                        value: reason.get_value(),
+                        oninput: move |evt| { let mut reason = reason; reason.handle_input(evt.value()) },
+                        onfocusout: move |_| { let mut reason = reason; reason.handle_blur() },
+                    }
+                    FieldError { control: reason }
+                }
+                div {
+                    label { class: "label",
+                        input {
+                            class: "checkbox",
+                            r#type: "checkbox",
+                            checked: identity_checked.get_value() == "true",
+                            onchange: move |evt| {
+                                identity_checked.mark_touched();
+                                identity_checked.handle_input(evt.checked().to_string());
+                            },
+                        }
+                        "I checked the member's identity"
+                    }
+                    FieldError { control: identity_checked }
+                }
+                div { class: "modal-action",
+                    button { class: "btn", onclick: move |_| on_close.call(()), "Cancel" }
+                    button {
+                        class: "btn btn-secondary",
+                        disabled: form.is_pending(),
+                        onclick: submit,
+                        if form.is_pending() {
+                            span { class: "loading loading-spinner" }
+                            "Checking the number"
+                        } else {
+                            "Change number"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn recover_phone_form() -> FormGroup {
+    let mut form = FormGroup::builder();
+    form.add_control("phone_number", FormControl::phone_number(None, vec![
+        Validator::new("required", Validator::required()),
+        Validator::new("invalidPhoneNumber", Validator::phone_number()),
+    ]).with_async_validators(vec![
+        AsyncValidator::new("alreadyRegistered", std::time::Duration::from_millis(400), |value: String| async move {
+            let form = PhoneChangeForm { phone_number: phone::normalize(&value) };
+            matches!(auth::phone_number_available(form).await, Ok(false))
+        }),
+    ]));
+    // Typed twice, as a mistyped number locks the member out. Both are compared in E.164, however they were typed.
+    form.add_control("confirm_phone_number", FormControl::phone_number(None, vec![
+        Validator::new("required", Validator::required()),
+    ]));
+    form.add_validator(GroupValidator::new("numbersDiffer", GroupValidator::fields_match("phone_number", "confirm_phone_number")));
+    form.add_control("reason", FormControl::control(String::new(), vec![
+        Validator::new("required", |value: &String| value.trim().is_empty()),
+    ]));
+    form.add_control("identity_checked", FormControl::boolean(false, vec![
+        Validator::new("unchecked", |value: &String| value != "true"),
+    ]).with_validate_on(ValidateOn::Change));
+    form
+}
+
+#[component]
+fn UserDetailModal(id: String, on_close: EventHandler<()>) -> Element {
+    let requests = AdminRequests;
+    let id = use_signal(|| id);
+    let detail = use_resource(move || async move { requests.user(&id()).await });
+    rsx! {
+        div { class: "modal modal-open",
+            div { class: "modal-box w-11/12 max-w-3xl space-y-4",
+                match &*detail.read() {
+                    None => rsx! {
+                        span { class: "loading loading-spinner" }
+                    },
+                    Some(Err(error)) => rsx! {
+                        p { "{error_message(error)}" }
+                    },
+                    Some(Ok(detail)) => rsx! {
+                        p { class: "text-2xl font-semibold", "{detail.user.phone_number}" }
+                        p { class: "text-sm", "{role_label(&detail.user.role)} · joined {short_date(&detail.user.created)}" }
+                        div {
+                            p { class: "text-lg font-semibold", "Active sessions" }
+                            if detail.sessions.is_empty() {
+                                p { class: "text-sm", "No active sessions." }
+                            }
+                            ul { class: "list",
+                                for session in detail.sessions.iter() {
+                                    li { class: "list-row",
+                                        span { class: "font-mono text-xs", "{session.session_id}" }
+                                        span { class: "text-sm", "since {short_datetime(&session.created)}" }
+                                    }
+                                }
+                            }
+                        }
+                        div {
+                            p { class: "text-lg font-semibold", "Storefronts" }
+                            if detail.storefronts.is_empty() {
+                                p { class: "text-sm", "No storefronts." }
+                            }
+                            ul { class: "list",
+                                for storefront in detail.storefronts.iter() {
+                                    li { class: "list-row",
+                                        span { "{storefront.name}" }
+                                        span { class: "badge", "{storefront_status_label(&storefront.status)}" }
+                                    }
+                                }
+                            }
+                        }
+                        div {
+                            p { class: "text-lg font-semibold", "Bookings" }
+                            if detail.bookings.is_empty() {
+                                p { class: "text-sm", "No bookings." }
+                            }
+                            ul { class: "list",
+                                for booking in detail.bookings.iter() {
+                                    li { class: "list-row",
+                                        span { {booking.listing_title.as_deref().unwrap_or("Deleted listing")} }
+                                        span { class: "text-sm",
+                                            if booking.buyer_id == detail.user.id { "as buyer" } else { "as seller" }
+                                            " · {short_datetime(&booking.scheduled)}"
+                                        }
+                                        span { class: "badge", "{booking_status_label(&booking.status)}" }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                }
+                div { class: "modal-action",
+                    button { class: "btn", onclick: move |_| on_close.call(()), "Close" }
+                }
+            }
+        }
+    }
+}
+
+fn booking_status_label(status: &BookingStatus) -> &'static str {
+    match status {
+        BookingStatus::Pending => "Pending",
+        BookingStatus::Confirmed => "Confirmed",
+        BookingStatus::Completed => "Completed",
+        BookingStatus::Cancelled => "Cancelled",
+    }
+}
+
+fn storefront_status_label(status: &StorefrontStatus) -> &'static str {
+    match status {
+        StorefrontStatus::Pending => "Pending",
+        StorefrontStatus::Approved => "Approved",
+        StorefrontStatus::Rejected => "Rejected",
+    }
+}
+
+#[component]
+fn StorefrontsPanel() -> Element {
+    let requests = AdminRequests;
+    let mut action_error: Signal<Option<String>> = use_signal(|| None);
+    let mut storefronts = use_resource(move || async move { requests.storefronts().await });
+    let review = move |id: String, approve: bool| async move {
+        match requests.review_storefront(&id, approve).await {
+            Ok(_) => action_error.set(None),
+            Err(error) => action_error.set(Some(error_message(&error))),
+        }
+        storefronts.restart();
+    };
     rsx! {
-        div { "Big shot" }
+        if let Some(message) = action_error() {
+            div { role: "alert", class: "alert alert-soft alert-error", span { "{message}" } }
+        }
+        match &*storefronts.read() {
+            None => rsx! {
+                span { class: "loading loading-spinner" }
+            },
+            Some(Err(error)) => rsx! {
+                div { role: "alert", class: "alert alert-soft alert-error", span { "{error_message(error)}" } }
+            },
+            Some(Ok(pending)) if pending.is_empty() => rsx! {
+                p { "No storefronts are waiting for approval." }
+            },
+            Some(Ok(pending)) => rsx! {
+                div { class: "grid grid-cols-1 lg:grid-cols-2 gap-4",
+                    for storefront in pending.iter().cloned() {
+                        div { key: "{storefront.id}", class: "card bg-base-100 shadow-sm",
+                            div { class: "card-body",
+                                p { class: "card-title", "{storefront.name}" }
+                                p { "{storefront.description}" }
+                                p { class: "text-sm", "Submitted {short_date(&storefront.created)}" }
+                                div { class: "card-actions justify-end",
+                                    button {
+                                        class: "btn btn-sm btn-outline btn-error",
+                                        onclick: {
+                                            let id = storefront.id.clone();
+                                            move |_| review(id.clone(), false)
+                                        },
+                                        "Reject"
+                                    }
+                                    button {
+                                        class: "btn btn-sm btn-secondary",
+                                        onclick: {
+                                            let id = storefront.id.clone();
+                                            move |_| review(id.clone(), true)
+                                        },
+                                        "Approve"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Filters of the audit log as its form gives them.
+#[derive(Debug, Deserialize, PartialEq)]
+struct AuditFilter {
+    action: Option<AuditAction>,
+    actor: String,
+    target: String,
+    period: Period,
+    per_page: Option<u64>,
+}
+
+/// Days the audit log is filtered to, both included.
+#[derive(Debug, Deserialize, PartialEq)]
+struct Period {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+impl AuditFilter {
+    fn query(self) -> AuditQuery {
+        AuditQuery {
+            action: self.action,
+            actor: Some(self.actor),
+            target: Some(self.target),
+            from: self.period.from.map(start_of_day),
+            // The "to" day is inclusive, so the range ends at the start of the next one.
+            to: self.period.to.map(|day| start_of_day(day) + Duration::days(1)),
+            page: None,
+            per_page: self.per_page,
+        }
+    }
+}
+
+fn audit_filter_form() -> FormGroup {
+    let mut form = FormGroup::builder();
+    let actions = AuditAction::ALL.iter().map(|action| action.as_str().to_string()).collect();
+    form.add_control("action", FormControl::select(actions, None, Vec::new()));
+    form.add_control("actor", FormControl::control(String::new(), Vec::new()));
+    form.add_control("target", FormControl::control(String::new(), Vec::new()));
+    let mut period = FormGroup::builder();
+    period.add_control("from", FormControl::date(None, Vec::new()));
+    period.add_control("to", FormControl::date(None, Vec::new()));
+    period.add_validator(GroupValidator::new("invalidRange", GroupValidator::fields_ordered("from", "to")));
+    form.add_group("period", period);
+    form.add_control("per_page", FormControl::integer(Some(PAGE_SIZE as i64), vec![
+        Validator::new("outOfRange", |value: &String| {
+            !value.trim().is_empty() && !value.trim().parse::<u64>().is_ok_and(|size| (1..=MAX_AUDIT_PAGE_SIZE).contains(&size))
+        }),
+    ]));
+    form
+}
+
+#[component]
+fn AuditPanel() -> Element {
+    let requests = AdminRequests;
+    let form = use_hook(audit_filter_form);
+    let mut action = control(&form, "action");
+    let mut page = use_signal(|| 0u64);
+    let mut applied: Signal<AuditQuery> = use_signal(|| AuditQuery { per_page: Some(PAGE_SIZE), ..AuditQuery::default() });
+    let entries = use_resource(move || async move {
+        let query = AuditQuery { page: Some(page()), ..applied() };
+        requests.audit_log(&query).await
+    });
+    // The applied filters become the form's initial values, so it is dirty only while it differs from them.
+    let mut apply = move || {
+        if !form.validate_all().is_empty() {
+            return;
+        }
+        let values = form.to_json();
+        if let Ok(filter) = serde_json::from_value::<AuditFilter>(Value::Object(values.clone())) {
+            form.reset_to(&values);
+            page.set(0);
+            applied.set(filter.query());
+        }
+    };
+    let mut filter_by_actor = move |actor: String| {
+        if let Value::Object(values) = json!({ "actor": actor }) {
+            form.patch_value(&values);
+        }
+        apply();
+    };
+    rsx! {
+        form { class: "flex flex-col lg:flex-row flex-wrap gap-2", onsubmit: move |_| apply(),
+            select {
+                class: "select w-full lg:w-56",
+                onchange: move |evt| action.handle_input(evt.value()),
+                option { value: "", "All actions" }
+                for option_action in action.options() {
+                    option { value: "{option_action}", selected: action.get_value() == option_action, "{option_action}" }
+                }
+            }
+            ControlInput { control: control(&form, "actor"), kind: "text", placeholder: "Actor id", class: "input w-full lg:w-64" }
+            ControlInput { control: control(&form, "target"), kind: "text", placeholder: "Target", class: "input w-full lg:w-64" }
+            ControlInput { control: control(&form, "period.from"), kind: "date", placeholder: "", class: "input w-full lg:w-44" }
+            ControlInput { control: control(&form, "period.to"), kind: "date", placeholder: "", class: "input w-full lg:w-44" }
+            label { class: "input w-full lg:w-36",
+                span { class: "label", "Per page" }
+                ControlInput { control: control(&form, "per_page"), kind: "number", placeholder: "", class: "" }
+            }
+            button { class: "btn btn-secondary", r#type: "submit", disabled: form.is_pristine(), "Filter" }
+            button {
+                class: "btn btn-ghost",
+                r#type: "button",
+                disabled: form.is_pristine(),
+                onclick: move |_| form.reset(),
+                "Undo changes"
+            }
+            a { class: "btn btn-ghost btn-outline", href: audit_export_url(&applied()), "Export CSV" }
+        }
+        for path in ["period.from", "period.to", "per_page"] {
+            FieldError { control: control(&form, path) }
+        }
+        if let Some(period) = form.get_group("period") {
+            GroupError { group: period }
+        }
+        match &*entries.read() {
+            None => rsx! {
+                span { class: "loading loading-spinner" }
+            },
+            Some(Err(error)) => rsx! {
+                div { role: "alert", class: "alert alert-soft alert-error", span { "{error_message(error)}" } }
+            },
+            Some(Ok(result)) => {
+                let last_page = result.total.saturating_sub(1) / result.per_page.max(1);
+                let current = result.page;
+                rsx! {
+                    div { class: "overflow-x-auto",
+                        table { class: "table table-sm",
+                            thead {
+                                tr {
+                                    th { "Time" }
+                                    th { "Action" }
+                                    th { "Actor" }
+                                    th { "Target" }
+                                    th { "IP" }
+                                    th { "User agent" }
+                                    th { "Details" }
+                                }
+                            }
+                            tbody {
+                                for entry in result.entries.iter() {
+                                    tr { key: "{entry.id}",
+                                        td { class: "whitespace-nowrap", "{short_datetime(&entry.created)}" }
+                                        td { span { class: "badge badge-soft", "{entry.action.as_str()}" } }
+                                        td {
+                                            if let Some(actor) = entry.actor_id.clone() {
+                                                button {
+                                                    class: "link font-mono text-xs",
+                                                    title: "Show only this actor's entries",
+                                                    onclick: move |_| filter_by_actor(actor.clone()),
+                                                    "{actor}"
+                                                }
+                                            }
+                                        }
+                                        td { class: "font-mono text-xs", "{entry.target.clone().unwrap_or_default()}" }
+                                        td { "{entry.ip.clone().unwrap_or_default()}" }
+                                        td { class: "max-w-48 truncate", title: "{entry.user_agent.clone().unwrap_or_default()}",
+                                            "{entry.user_agent.clone().unwrap_or_default()}"
+                                        }
+                                        td { "{entry.details.clone().unwrap_or_default()}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div { class: "flex flex-row items-center justify-between",
+                        p { class: "text-sm", "{result.total} entries" }
+                        div { class: "join",
+                            button {
+                                class: "join-item btn btn-sm",
+                                disabled: current == 0,
+                                onclick: move |_| page.set(current.saturating_sub(1)),
+                                "«"
+                            }
+                            button { class: "join-item btn btn-sm", "Page {current + 1} of {last_page + 1}" }
+                            button {
+                                class: "join-item btn btn-sm",
+                                disabled: current >= last_page,
+                                onclick: move |_| page.set(current + 1),
+                                "»"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Runs `f` where signals can be created, as inside a component.
+    fn in_runtime(f: impl FnOnce()) {
+        let dom = VirtualDom::new(|| rsx! {});
+        dom.in_runtime(|| ScopeId::ROOT.in_runtime(f));
+    }
+
+    fn filter(form: &FormGroup) -> AuditFilter {
+        serde_json::from_value(Value::Object(form.to_json())).unwrap()
+    }
+
+    #[test]
+    fn an_untouched_audit_filter_filters_nothing() {
+        in_runtime(|| {
+            let query = filter(&audit_filter_form()).query();
+            assert_eq!(query.action, None);
+            assert_eq!((query.from, query.to), (None, None));
+            assert_eq!(query.per_page, Some(PAGE_SIZE));
+        });
+    }
+
+    #[test]
+    fn audit_filters_give_a_typed_query() {
+        in_runtime(|| {
+            let form = audit_filter_form();
+            for (path, text) in [("action", "RoleChanged"), ("period.from", "2026-10-01"), ("period.to", "2026-10-19"), ("per_page", "50")] {
+                control(&form, path).set_value(text.to_string());
+            }
+            assert!(form.validate_all().is_empty());
+            let query = filter(&form).query();
+            assert_eq!(query.action, Some(AuditAction::RoleChanged));
+            assert_eq!(query.from, Some(Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap()));
+            assert_eq!(query.to, Some(Utc.with_ymd_and_hms(2026, 10, 20, 0, 0, 0).unwrap()));
+            assert_eq!(query.per_page, Some(50));
+        });
+    }
+
+    #[test]
+    fn applied_audit_filters_become_the_form_baseline() {
+        in_runtime(|| {
+            let form = audit_filter_form();
+            assert!(form.is_pristine());
+            control(&form, "actor").set_value(String::from("admin"));
+            assert!(form.is_dirty());
+            form.reset_to(&form.to_json());
+            assert!(form.is_pristine());
+            control(&form, "actor").set_value(String::from("other"));
+            form.reset();
+            assert_eq!(control(&form, "actor").get_value(), "admin");
+        });
+    }
+
+    #[test]
+    fn period_errors_wait_for_the_period_to_be_touched() {
+        in_runtime(|| {
+            let form = audit_filter_form();
+            let period = form.get_group("period").unwrap();
+            control(&form, "period.from").set_value(String::from("2026-10-19"));
+            control(&form, "period.to").set_value(String::from("2026-10-01"));
+            assert_eq!(period.validate_group(), [String::from("invalidRange")]);
+            assert_eq!(group_error(&period), None);
+            control(&form, "period.to").mark_touched();
+            assert_eq!(group_error(&period), Some("The start date has to come before the end date"));
+        });
+    }
+
+    #[test]
+    fn audit_page_sizes_stay_within_what_the_server_returns() {
+        in_runtime(|| {
+            let form = audit_filter_form();
+            control(&form, "per_page").set_value(String::from("500"));
+            assert_eq!(form.validate_all().get("per_page"), Some(&vec![String::from("outOfRange")]));
+            control(&form, "per_page").set_value(String::from("ten"));
+            assert!(form.validate_all().contains_key("per_page"));
+        });
+    }
+
+    #[test]
+    fn recovered_numbers_are_sent_in_e164() {
+        in_runtime(|| {
+            let form = recover_phone_form();
+            control(&form, "phone_number").set_value(String::from("0712 345 678"));
+            control(&form, "confirm_phone_number").set_value(String::from("+255 712 345 678"));
+            control(&form, "reason").set_value(String::from("ID checked in person"));
+            assert!(form.validate_all().contains_key("identity_checked"));
+            control(&form, "identity_checked").set_value(String::from("true"));
+            assert!(form.validate_all().is_empty());
+            let recovery: RecoverPhoneNumberForm = serde_json::from_value(Value::Object(form.to_json())).unwrap();
+            assert_eq!(recovery.phone_number, "+255712345678");
+        });
+    }
+
+    #[test]
+    fn recovered_numbers_have_to_be_typed_twice() {
+        in_runtime(|| {
+            let form = recover_phone_form();
+            control(&form, "phone_number").set_value(String::from("0712 345 678"));
+            control(&form, "confirm_phone_number").set_value(String::from("0712 345 679"));
+            assert_eq!(form.validate_all().get(FormGroup::GROUP_PATH), Some(&vec![String::from("numbersDiffer")]));
+            assert_eq!(group_error(&form), Some("The phone numbers do not match"));
+        });
+    }
+
+    #[test]
+    fn audit_periods_cannot_end_before_they_start() {
+        in_runtime(|| {
+            let form = audit_filter_form();
+            control(&form, "period.from").set_value(String::from("2026-10-19"));
+            control(&form, "period.to").set_value(String::from("2026-10-01"));
+            assert_eq!(form.validate_all().get("period"), Some(&vec![String::from("invalidRange")]));
+            control(&form, "period.to").set_value(String::from("2026-10-19"));
+            assert!(form.validate_all().is_empty());
+            assert_eq!(group_error(&form.get_group("period").unwrap()), None);
+        });
     }
-}
\ No newline at end of file
+}
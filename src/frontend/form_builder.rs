@@ -1,11 +1,29 @@
-use std::{collections::HashMap, ops::Mul, sync::{Arc, Mutex}};
-use serde_json::{Value, json,Map};
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, future::Future, ops::Mul, pin::Pin, rc::Rc, sync::{Arc, Mutex}, time::Duration};
+use serde_json::{Value, Map};
 
 
 use modx::{store,props};
+use chrono::NaiveDate;
 use dioxus::prelude::*;
+use wasm_bindgen::{closure::Closure, JsCast};
 
 type ValidatorFn = Arc<dyn Fn(&String) -> bool + Send + Sync>;
+type AsyncValidatorFn = Rc<dyn Fn(String) -> Pin<Box<dyn Future<Output = bool>>>>;
+type GroupValidatorFn = Arc<dyn Fn(&Map<String, Value>) -> bool + Send + Sync>;
+type ParseFn = Arc<dyn Fn(&str) -> Result<Value, String> + Send + Sync>;
+type FormatFn = Arc<dyn Fn(&Value) -> String + Send + Sync>;
+type BeforeUnloadListener = Rc<Closure<dyn Fn(web_sys::BeforeUnloadEvent)>>;
+
+/// A page registered by `use_leave_warning`: the route it is shown at and whether leaving it loses changes.
+struct LeaveGuard {
+    id: usize,
+    route: String,
+    is_dirty: Rc<dyn Fn() -> bool>,
+}
+
+thread_local! {
+    static LEAVE_GUARDS: RefCell<Vec<LeaveGuard>> = const { RefCell::new(Vec::new()) };
+}
 
 #[derive(Clone)]
 pub struct Validator {
@@ -43,27 +61,261 @@ impl Validator {
             }
         }
     } 
+
+    /// Fails unless the value is a valid number able to receive text messages, as the server requires.
+    pub fn phone_number() -> impl Fn(&String) -> bool {
+        |value: &String| crate::backend::phone::validate_phone_number(value).is_err()
+    }
+}
+
+/// Validator that has to wait for something, usually the server. Like `Validator`, `func` returns true when the
+/// value is invalid.
+#[derive(Clone)]
+pub struct AsyncValidator {
+    name: String,
+    func: AsyncValidatorFn,
+    /// How long the value has to stay unchanged before it is checked.
+    debounce: Duration,
+}
+
+impl AsyncValidator {
+    pub fn new<F, Fut>(name: &str, debounce: Duration, func: F) -> Self
+    where
+        F: Fn(String) -> Fut + 'static,
+        Fut: Future<Output = bool> + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            func: Rc::new(move |value| Box::pin(func(value))),
+            debounce
+        }
+    }
+}
+
+/// Validator of a whole `FormGroup`, for rules spanning several controls. It sees the group as `to_json` gives it
+/// and returns true when the group is invalid.
+#[derive(Clone)]
+pub struct GroupValidator {
+    name: String,
+    func: GroupValidatorFn,
+}
+
+fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) if !a.is_empty() && !b.is_empty() => Some(a.cmp(b)),
+        _ => None
+    }
+}
+
+impl GroupValidator {
+    pub fn new<F>(name: &str, func: F) -> Self
+    where
+        F: Fn(&Map<String, Value>) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            func: Arc::new(func)
+        }
+    }
+
+    /// Fails when the two controls differ, as for a confirmation field.
+    pub fn fields_match(first: &str, second: &str) -> impl Fn(&Map<String, Value>) -> bool {
+        let (first, second) = (first.to_string(), second.to_string());
+        move |values: &Map<String, Value>| values.get(&first) != values.get(&second)
+    }
+
+    /// Fails when `first` comes after `last`, as for the start and end of a date range. Dates compare as the ISO
+    /// 8601 strings date inputs give; empty controls are left to `Validator::required`.
+    pub fn fields_ordered(first: &str, last: &str) -> impl Fn(&Map<String, Value>) -> bool {
+        let (first, last) = (first.to_string(), last.to_string());
+        move |values: &Map<String, Value>| match (values.get(&first), values.get(&last)) {
+            (Some(first), Some(last)) => compare_values(first, last) == Some(Ordering::Greater),
+            _ => false
+        }
+    }
+}
+
+/// How a control turns the text of its input into the value `FormGroup::to_json` gives, and back. `parse` fails
+/// with the name of the error the control then reports, like a validator's. Empty text parses to `null` for
+/// optional values, so `Validator::required` keeps working on every kind of control.
+#[derive(Clone)]
+pub struct ValueCodec {
+    parse: ParseFn,
+    format: FormatFn,
+}
+
+impl Default for ValueCodec {
+    fn default() -> Self {
+        Self::text()
+    }
+}
+
+fn format_plain(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl ValueCodec {
+    pub fn new<P, F>(parse: P, format: F) -> Self
+    where
+        P: Fn(&str) -> Result<Value, String> + Send + Sync + 'static,
+        F: Fn(&Value) -> String + Send + Sync + 'static,
+    {
+        Self {
+            parse: Arc::new(parse),
+            format: Arc::new(format)
+        }
+    }
+
+    pub fn text() -> Self {
+        Self::new(|text| Ok(Value::String(text.to_string())), format_plain)
+    }
+
+    /// Whole numbers, given as JSON integers, for DTO fields such as `i64` or `u32`.
+    pub fn integer() -> Self {
+        Self::new(|text| match text.trim() {
+            "" => Ok(Value::Null),
+            text => text.parse::<i64>().map(Value::from).map_err(|_| String::from("notAnInteger")),
+        }, format_plain)
+    }
+
+    /// The text is what checkboxes give: `true` when checked.
+    pub fn boolean() -> Self {
+        Self::new(|text| Ok(Value::Bool(text == "true")), |value| value.as_bool().unwrap_or(false).to_string())
+    }
+
+    /// Dates as `YYYY-MM-DD`, which is both what date inputs give and how `NaiveDate` deserializes.
+    pub fn date() -> Self {
+        Self::new(|text| match text.trim() {
+            "" => Ok(Value::Null),
+            text => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map(|date| Value::String(date.format("%Y-%m-%d").to_string()))
+                .map_err(|_| String::from("invalidDate")),
+        }, format_plain)
+    }
+
+    pub fn select(options: Vec<String>) -> Self {
+        Self::new(move |text| match text {
+            "" => Ok(Value::Null),
+            text if options.iter().any(|option| option == text) => Ok(Value::String(text.to_string())),
+            _ => Err(String::from("unknownOption")),
+        }, format_plain)
+    }
+
+    /// Phone numbers in E.164, as the server stores them. Numbers without a country code are taken to be
+    /// Tanzanian, like `phone::normalize` does.
+    pub fn phone_number() -> Self {
+        Self::new(|text| match text.trim() {
+            "" => Ok(Value::Null),
+            text => crate::backend::phone::to_e164(text, crate::backend::phone::DEFAULT_COUNTRY)
+                .map(Value::String)
+                .map_err(|_| String::from("invalidPhoneNumber")),
+        }, format_plain)
+    }
+}
+
+/// When a control checks its value as the member interacts with it. `FormGroup::validate_all` checks every
+/// control whatever their strategy.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ValidateOn {
+    /// On every change.
+    Change,
+    /// When the input loses focus after the member changed it, then on every change so that fixing the value
+    /// clears its errors.
+    #[default]
+    Blur,
+    /// Only when the form is submitted.
+    Submit,
 }
 
 #[derive(PartialEq)]
 #[props(value,errors)]
 #[store]
 pub struct FormControl {
+    /// Text of the input, which `codec` parses into the control's value.
     value: String,
+    /// Text the control was created or last reset with, which tells whether it is dirty.
+    initial: String,
+    /// Whether the input lost focus at least once, or the form was submitted.
+    touched: bool,
+    validate_on: ValidateOn,
+    codec: ValueCodec,
+    /// Choices of a select control.
+    options: Vec<String>,
     validators: Vec<Validator>,
-    errors: Vec<String>
+    async_validators: Vec<AsyncValidator>,
+    errors: Vec<String>,
+    /// Whether the asynchronous validators are running for the current value.
+    pending: bool,
+    pending_task: Option<Task>,
+    /// Value the asynchronous validators last finished with, and what they found.
+    async_checked: Option<String>,
+    async_errors: Vec<String>
 }
 
 impl FormControl {
 
     pub fn control(initial: String, validators: Vec<Validator>) -> Self {
+        Self::typed(ValueCodec::text(), &Value::String(initial), validators)
+    }
+
+    /// A control whose value is `initial` once formatted by `codec` into the text of its input.
+    pub fn typed(codec: ValueCodec, initial: &Value, validators: Vec<Validator>) -> Self {
+        let initial = (codec.format)(initial);
         Self {
-            value: Signal::new(initial),
+            value: Signal::new(initial.clone()),
+            initial: Signal::new(initial),
+            touched: Signal::new(false),
+            validate_on: Signal::new(ValidateOn::default()),
+            codec: Signal::new(codec),
+            options: Signal::new(Vec::new()),
             validators: Signal::new(validators),
-            errors: Signal::new([].to_vec())
+            async_validators: Signal::new(Vec::new()),
+            errors: Signal::new([].to_vec()),
+            pending: Signal::new(false),
+            pending_task: Signal::new(None),
+            async_checked: Signal::new(None),
+            async_errors: Signal::new(Vec::new())
         }
     }
 
+    pub fn integer(initial: Option<i64>, validators: Vec<Validator>) -> Self {
+        Self::typed(ValueCodec::integer(), &initial.map(Value::from).unwrap_or_default(), validators)
+    }
+
+    pub fn boolean(initial: bool, validators: Vec<Validator>) -> Self {
+        Self::typed(ValueCodec::boolean(), &Value::Bool(initial), validators)
+    }
+
+    pub fn date(initial: Option<NaiveDate>, validators: Vec<Validator>) -> Self {
+        let initial = initial.map(|date| Value::String(date.format("%Y-%m-%d").to_string())).unwrap_or_default();
+        Self::typed(ValueCodec::date(), &initial, validators)
+    }
+
+    pub fn select(options: Vec<String>, initial: Option<String>, validators: Vec<Validator>) -> Self {
+        let mut control = Self::typed(ValueCodec::select(options.clone()), &initial.map(Value::String).unwrap_or_default(), validators);
+        control.options.set(options);
+        control
+    }
+
+    pub fn phone_number(initial: Option<String>, validators: Vec<Validator>) -> Self {
+        Self::typed(ValueCodec::phone_number(), &initial.map(Value::String).unwrap_or_default(), validators)
+    }
+
+    pub fn with_async_validators(mut self, validators: Vec<AsyncValidator>) -> Self {
+        self.async_validators.set(validators);
+        self
+    }
+
+    pub fn with_validate_on(mut self, validate_on: ValidateOn) -> Self {
+        self.validate_on.set(validate_on);
+        self
+    }
+
     pub fn set_value(&mut self,value: String) {
         self.value.set(value);
     }
@@ -76,17 +328,131 @@ impl FormControl {
         &self.value
     }
 
-    pub fn validate(&mut self) -> Vec<String> {
-        let errors = self.validators().iter().filter_map(|v| {
-            match (v.func)(&self.value()) {
+    /// The value as `codec` parses the text of the input, or the name of the error when it does not parse.
+    pub fn parsed_value(&self) -> Result<Value, String> {
+        (self.codec().parse)(&self.value())
+    }
+
+    /// Sets the value without validating it, as when filling the form from data loaded for it. The control
+    /// becomes dirty if the value differs from the one it was created or reset with.
+    pub fn patch_value(&mut self, value: &Value) {
+        let text = (self.codec().format)(value);
+        self.set_value(text);
+    }
+
+    /// Whether the value differs from the one the control was created or last reset with.
+    pub fn is_dirty(&self) -> bool {
+        *self.value.read() != *self.initial.read()
+    }
+
+    pub fn mark_touched(&mut self) {
+        self.touched.set(true);
+    }
+
+    /// Sets the text typed by the member and validates it as `validate_on` says.
+    pub fn handle_input(&mut self, value: String) {
+        self.set_value(value);
+        match self.validate_on() {
+            ValidateOn::Change => { self.validate(); },
+            ValidateOn::Blur if self.touched() => { self.validate(); },
+            _ => {}
+        }
+        if self.validate_on() != ValidateOn::Submit {
+            self.validate_async();
+        }
+    }
+
+    /// Marks the control touched when its input loses focus. Leaving an input without changing it shows no error.
+    pub fn handle_blur(&mut self) {
+        self.mark_touched();
+        if self.validate_on() != ValidateOn::Submit && (self.is_dirty() || !self.errors.read().is_empty()) {
+            self.validate();
+        }
+    }
+
+    /// Goes back to the value the control was created or last reset with, untouched and without errors.
+    pub fn reset(&mut self) {
+        if let Some(task) = self.pending_task() {
+            task.cancel();
+            self.pending_task.set(None);
+        }
+        self.pending.set(false);
+        let initial = self.initial();
+        self.value.set(initial);
+        self.touched.set(false);
+        self.errors.set(Vec::new());
+        self.async_checked.set(None);
+        self.async_errors.set(Vec::new());
+    }
+
+    /// Resets the control to `value`, which it then counts as pristine, as after saving the form.
+    pub fn reset_to(&mut self, value: &Value) {
+        let initial = (self.codec().format)(value);
+        self.initial.set(initial);
+        self.reset();
+    }
+
+    fn sync_errors(&self, value: &String) -> Vec<String> {
+        self.validators().iter().filter_map(|v| {
+            match (v.func)(value) {
                 true => Some(v.name.clone()),
                 false => None
             }
-        }).collect();
+        }).collect()
+    }
+
+    /// Runs the validators, after checking that the text parses. The asynchronous ones are not run, but what they
+    /// found for the current value is kept.
+    pub fn validate(&mut self) -> Vec<String> {
+        let value = self.value();
+        let mut errors = self.sync_errors(&value);
+        if let Err(error) = self.parsed_value() {
+            errors.insert(0, error);
+        }
+        if errors.is_empty() && self.async_checked().as_ref() == Some(&value) {
+            errors.extend(self.async_errors());
+        }
         self.errors.set(errors);
         self.errors()
     }
 
+    /// Runs the asynchronous validators on the current value once it passes the synchronous ones and has not
+    /// changed for their debounce. A run still going for an earlier value is cancelled. Until the run completes
+    /// `pending` is true. Its errors are then shown right away only once the control is touched or validated on
+    /// every change; otherwise they are kept for the next `validate`, as on blur.
+    pub fn validate_async(&mut self) {
+        if let Some(task) = self.pending_task() {
+            task.cancel();
+            self.pending_task.set(None);
+        }
+        self.pending.set(false);
+        let value = self.value();
+        if self.async_validators().is_empty() || self.parsed_value().is_err() || !self.sync_errors(&value).is_empty() || self.async_checked().as_ref() == Some(&value) {
+            return;
+        }
+        self.pending.set(true);
+        let mut control = *self;
+        let task = spawn(async move {
+            let validators = control.async_validators();
+            let debounce = validators.iter().map(|v| v.debounce).max().unwrap_or_default();
+            gloo_timers::future::sleep(debounce).await;
+            let mut errors = Vec::new();
+            for validator in validators.iter() {
+                if (validator.func)(value.clone()).await {
+                    errors.push(validator.name.clone());
+                }
+            }
+            control.async_checked.set(Some(value));
+            control.async_errors.set(errors);
+            control.pending.set(false);
+            control.pending_task.set(None);
+            if control.touched() || control.validate_on() == ValidateOn::Change {
+                control.validate();
+            }
+        });
+        self.pending_task.set(Some(task));
+    }
+
     pub fn has_error(&self, name: String) -> bool {
         let validator = self.validators()
         .iter()
@@ -99,40 +465,450 @@ impl FormControl {
     }
 }
 
+/// What a `FormGroup` holds: a control, or another group nested in it.
+#[derive(Clone)]
+pub enum FormItem {
+    Control(Arc<Mutex<FormControl>>),
+    Group(FormGroup),
+}
+
+impl FormItem {
+    pub fn control(control: FormControl) -> Self {
+        FormItem::Control(Arc::new(Mutex::new(control)))
+    }
+
+    fn validate_into(&self, path: &str, errors: &mut HashMap<String, Vec<String>>) {
+        match self {
+            FormItem::Control(control) => {
+                let mut control = control.lock().unwrap();
+                control.mark_touched();
+                let control_errors = control.validate();
+                if !control_errors.is_empty() {
+                    errors.insert(path.to_string(), control_errors);
+                }
+            },
+            FormItem::Group(group) => group.validate_into(path, errors),
+        }
+    }
+
+    fn is_pending(&self) -> bool {
+        match self {
+            FormItem::Control(control) => control.lock().unwrap().pending(),
+            FormItem::Group(group) => group.is_pending(),
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        match self {
+            FormItem::Control(control) => control.lock().unwrap().is_dirty(),
+            FormItem::Group(group) => group.is_dirty(),
+        }
+    }
+
+    fn is_touched(&self) -> bool {
+        match self {
+            FormItem::Control(control) => control.lock().unwrap().touched(),
+            FormItem::Group(group) => group.is_touched(),
+        }
+    }
+
+    fn reset(&self) {
+        match self {
+            FormItem::Control(control) => control.lock().unwrap().reset(),
+            FormItem::Group(group) => group.reset(),
+        }
+    }
+
+    fn patch_value(&self, value: &Value) {
+        match (self, value) {
+            (FormItem::Control(control), value) => control.lock().unwrap().patch_value(value),
+            (FormItem::Group(group), Value::Object(values)) => group.patch_value(values),
+            _ => {}
+        }
+    }
+
+    fn reset_to(&self, value: &Value) {
+        match (self, value) {
+            (FormItem::Control(control), value) => control.lock().unwrap().reset_to(value),
+            (FormItem::Group(group), Value::Object(values)) => group.reset_to(values),
+            _ => self.reset(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            FormItem::Control(control) => control.lock().unwrap().parsed_value().unwrap_or(Value::Null),
+            FormItem::Group(group) => Value::Object(group.to_json()),
+        }
+    }
+
+    /// The item at `path` below this one.
+    fn find(&self, path: &[&str]) -> Option<FormItem> {
+        let Some((name, rest)) = path.split_first() else {
+            return Some(self.clone());
+        };
+        let item = match self {
+            FormItem::Group(group) => group.controls().get(*name).cloned(),
+            _ => None,
+        };
+        item?.find(rest)
+    }
+}
+
+/// Splits a path like `period.from` into the names of the nested groups and of the control.
+fn parse_path(path: &str) -> Option<Vec<&str>> {
+    path.split('.').map(|name| (!name.is_empty()).then_some(name)).collect()
+}
+
+fn child_path(parent: &str, name: &str) -> String {
+    match parent.is_empty() {
+        true => name.to_string(),
+        false => format!("{}.{}", parent, name),
+    }
+}
+
+#[derive(PartialEq)]
 #[store]
 pub struct FormGroup {
-    controls: HashMap<String, Arc<Mutex<FormControl>>>,
+    controls: HashMap<String, FormItem>,
+    validators: Vec<GroupValidator>,
+    errors: Vec<String>,
 }
 
 impl FormGroup {
+    /// Path under which `validate_all` reports the errors of the group validators of the outermost group.
+    pub const GROUP_PATH: &'static str = "";
+
     pub fn builder() -> Self {
         Self {
             controls: Signal::new(HashMap::new()),
+            validators: Signal::new(Vec::new()),
+            errors: Signal::new(Vec::new()),
         }
     }
 
+    pub fn add_validator(&mut self, validator: GroupValidator) {
+        self.validators.with_mut(|validators| validators.push(validator));
+    }
+
     pub fn add_control(&mut self, name: &str, control: FormControl){
+        self.add_item(name, FormItem::control(control));
+    }
+
+    pub fn add_group(&mut self, name: &str, group: FormGroup) {
+        self.add_item(name, FormItem::Group(group));
+    }
+
+    pub fn add_item(&mut self, name: &str, item: FormItem) {
         self.controls.with_mut(|controls| {
-            controls.insert(name.to_string(), Arc::new(Mutex::new(control)));
+            controls.insert(name.to_string(), item);
         });
     }
 
-    pub fn get_control(&self, name: &str) -> Option<Arc<Mutex<FormControl>>> {
-        self.controls().get(name).cloned()
+    /// The item at `path`, a name or a path through nested groups like `period.from`.
+    pub fn get(&self, path: &str) -> Option<FormItem> {
+        FormItem::Group(*self).find(&parse_path(path)?)
     }
 
-    pub fn validate_all(&self) -> HashMap<String, Vec<String>> {
-        self.controls()
+    pub fn get_control(&self, path: &str) -> Option<Arc<Mutex<FormControl>>> {
+        match self.get(path)? {
+            FormItem::Control(control) => Some(control),
+            _ => None,
+        }
+    }
+
+    pub fn get_group(&self, path: &str) -> Option<FormGroup> {
+        match self.get(path)? {
+            FormItem::Group(group) => Some(group),
+            _ => None,
+        }
+    }
+
+    /// Runs the group validators, which see every control, including ones that are themselves invalid.
+    pub fn validate_group(&self) -> Vec<String> {
+        let values = self.to_json();
+        let errors: Vec<String> = self.validators()
         .iter()
-        .filter(|f| !f.1.lock().unwrap().validate().is_empty())
-        .map(|(name, control)| (name.clone(), control.lock().unwrap().validate()))
-        .collect()
+        .filter(|v| (v.func)(&values))
+        .map(|v| v.name.clone())
+        .collect();
+        let mut group_errors = self.errors;
+        group_errors.set(errors.clone());
+        errors
+    }
+
+    fn validate_into(&self, path: &str, errors: &mut HashMap<String, Vec<String>>) {
+        for (name, item) in self.controls().iter() {
+            item.validate_into(&child_path(path, name), errors);
+        }
+        let group_errors = self.validate_group();
+        if !group_errors.is_empty() {
+            errors.insert(path.to_string(), group_errors);
+        }
+    }
+
+    /// Errors of every invalid control by path, like `period.from`. Those of the group validators are
+    /// under the path of their group, `GROUP_PATH` for this one. Every control is marked touched, as on submit.
+    pub fn validate_all(&self) -> HashMap<String, Vec<String>> {
+        let mut errors = HashMap::new();
+        self.validate_into(Self::GROUP_PATH, &mut errors);
+        errors
+    }
+
+    /// Whether a control is still waiting for its asynchronous validators, so the form cannot be submitted yet.
+    pub fn is_pending(&self) -> bool {
+        self.controls().values().any(FormItem::is_pending)
+    }
+
+    /// Whether a control, here or nested, differs from the value it was created or last reset with.
+    pub fn is_dirty(&self) -> bool {
+        self.controls().values().any(FormItem::is_dirty)
+    }
+
+    pub fn is_pristine(&self) -> bool {
+        !self.is_dirty()
+    }
+
+    pub fn is_touched(&self) -> bool {
+        self.controls().values().any(FormItem::is_touched)
+    }
+
+    /// Resets every control, here and nested, and clears the errors of the group validators.
+    pub fn reset(&self) {
+        for item in self.controls().values() {
+            item.reset();
+        }
+        let mut errors = self.errors;
+        errors.set(Vec::new());
+    }
+
+    /// Sets the controls named in `values`, leaving the others as they are. Nested groups take objects; names without
+    /// a control are ignored.
+    pub fn patch_value(&self, values: &Map<String, Value>) {
+        let controls = self.controls();
+        for (name, value) in values {
+            if let Some(item) = controls.get(name) {
+                item.patch_value(value);
+            }
+        }
+    }
+
+    /// Resets the controls named in `values` to them, counting them as pristine from now on, as after saving the
+    /// form. The others go back to their own initial values.
+    pub fn reset_to(&self, values: &Map<String, Value>) {
+        for (name, item) in self.controls().iter() {
+            match values.get(name) {
+                Some(value) => item.reset_to(value),
+                None => item.reset(),
+            }
+        }
+        let mut errors = self.errors;
+        errors.set(Vec::new());
     }
 
+    /// Values of the controls, typed by their codecs, with nested groups as objects. A control whose text
+    /// does not parse gives `null`.
     pub fn to_json(&self) -> Map<String,Value> {
         self.controls()
         .iter()
-        .map(|(key,control)| (key.clone(),serde_json::to_value(control.lock().unwrap().value()).unwrap()))
+        .map(|(key,item)| (key.clone(),item.to_json()))
         .collect::<Map<String,Value>>()
     }
 }
+
+/// Has the browser ask for confirmation before the page is reloaded, closed or left for another site while
+/// `is_dirty` is true. Navigation within the app, through `Link` or `navigator()`, asks too when the router is
+/// configured with `guard_navigation`.
+pub fn use_leave_warning(is_dirty: impl Fn() -> bool + 'static) {
+    let is_dirty: Rc<dyn Fn() -> bool> = use_hook(|| Rc::new(is_dirty));
+    let mut listener: Signal<Option<BeforeUnloadListener>> = use_signal(|| None);
+    let guard_id = use_hook(|| {
+        static NEXT_ID: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    });
+    // Effects only run in the browser, never while rendering on the server.
+    use_effect(move || {
+        if listener.peek().is_some() {
+            return;
+        }
+        let route = router().full_route_string();
+        LEAVE_GUARDS.with_borrow_mut(|guards| guards.push(LeaveGuard { id: guard_id, route, is_dirty: Rc::clone(&is_dirty) }));
+        let is_dirty = Rc::clone(&is_dirty);
+        let closure = Closure::<dyn Fn(web_sys::BeforeUnloadEvent)>::new(move |event: web_sys::BeforeUnloadEvent| {
+            if is_dirty() {
+                event.prevent_default();
+                event.set_return_value("");
+            }
+        });
+        if let Some(window) = web_sys::window() {
+            let _ = window.add_event_listener_with_callback("beforeunload", closure.as_ref().unchecked_ref());
+        }
+        listener.set(Some(Rc::new(closure)));
+    });
+    use_drop(move || {
+        LEAVE_GUARDS.with_borrow_mut(|guards| guards.retain(|guard| guard.id != guard_id));
+        if let (Some(window), Some(closure)) = (web_sys::window(), listener.peek().as_ref()) {
+            let _ = window.remove_event_listener_with_callback("beforeunload", closure.as_ref().as_ref().unchecked_ref());
+        }
+    });
+}
+
+/// Routing callback for `RouterConfig::on_update` asking the member to confirm before leaving a page with a dirty
+/// `use_leave_warning`. When they stay, the route goes back to that page, which keeps its state.
+pub fn guard_navigation<R: Routable>() -> Option<NavigationTarget<R>> {
+    let target = router().full_route_string();
+    let dirty_route = LEAVE_GUARDS.with_borrow(|guards| {
+        guards.iter().find(|guard| guard.route != target && (guard.is_dirty)()).map(|guard| guard.route.clone())
+    })?;
+    let leave = web_sys::window()
+        .and_then(|window| window.confirm_with_message("Leave this page? Your changes will be lost.").ok())
+        .unwrap_or(true);
+    if leave {
+        return None;
+    }
+    R::from_str(&dirty_route).ok().map(NavigationTarget::Internal)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Runs `f` where signals can be created, as inside a component.
+    fn in_runtime(f: impl FnOnce()) {
+        let dom = VirtualDom::new(|| rsx! {});
+        dom.in_runtime(|| ScopeId::ROOT.in_runtime(f));
+    }
+
+    fn options() -> Vec<String> {
+        vec![String::from("design"), String::from("writing")]
+    }
+
+    #[test]
+    fn integers_parse_to_json_integers() {
+        let integer = ValueCodec::integer();
+        assert_eq!((integer.parse)("3"), Ok(json!(3)));
+        assert!((integer.parse)("3").unwrap().is_i64());
+        assert_eq!((integer.parse)("2.5"), Err(String::from("notAnInteger")));
+        assert_eq!((integer.format)(&json!(25000)), "25000");
+    }
+
+    #[test]
+    fn dates_parse_from_and_format_to_iso_8601() {
+        let date = ValueCodec::date();
+        assert_eq!((date.parse)("2026-11-02"), Ok(json!("2026-11-02")));
+        assert_eq!((date.parse)("02/11/2026"), Err(String::from("invalidDate")));
+        assert_eq!((date.parse)("2026-02-30"), Err(String::from("invalidDate")));
+        assert_eq!((date.format)(&json!("2026-11-02")), "2026-11-02");
+    }
+
+    #[test]
+    fn selects_only_take_their_options() {
+        let select = ValueCodec::select(options());
+        assert_eq!((select.parse)("design"), Ok(json!("design")));
+        assert_eq!((select.parse)("cooking"), Err(String::from("unknownOption")));
+    }
+
+    #[test]
+    fn phone_numbers_parse_to_e164() {
+        let phone_number = ValueCodec::phone_number();
+        assert_eq!((phone_number.parse)("0712 345 678"), Ok(json!("+255712345678")));
+        assert_eq!((phone_number.parse)("+254 712 345678"), Ok(json!("+254712345678")));
+        assert_eq!((phone_number.parse)("call me"), Err(String::from("invalidPhoneNumber")));
+        assert_eq!((phone_number.format)(&json!("+255712345678")), "+255712345678");
+    }
+
+    #[test]
+    fn empty_text_parses_to_null() {
+        for codec in [ValueCodec::integer(), ValueCodec::date(), ValueCodec::select(options()), ValueCodec::phone_number()] {
+            assert_eq!((codec.parse)(""), Ok(Value::Null));
+            assert_eq!((codec.format)(&Value::Null), "");
+        }
+        assert_eq!((ValueCodec::text().parse)(""), Ok(json!("")));
+    }
+
+    #[test]
+    fn values_compare_only_with_values_of_their_kind() {
+        assert_eq!(compare_values(&json!(2), &json!(10)), Some(Ordering::Less));
+        assert_eq!(compare_values(&json!(2.5), &json!(2)), Some(Ordering::Greater));
+        assert_eq!(compare_values(&json!("2026-11-02"), &json!("2026-11-02")), Some(Ordering::Equal));
+        assert_eq!(compare_values(&json!("2026-12-01"), &json!("2026-11-30")), Some(Ordering::Greater));
+        assert_eq!(compare_values(&json!(""), &json!("2026-11-02")), None);
+        assert_eq!(compare_values(&json!("2026-11-02"), &json!("")), None);
+        assert_eq!(compare_values(&json!("10"), &json!(2)), None);
+        assert_eq!(compare_values(&Value::Null, &json!(2)), None);
+        assert_eq!(compare_values(&json!(true), &json!(false)), None);
+    }
+
+    #[test]
+    fn ordered_fields_fail_only_when_reversed() {
+        let ordered = GroupValidator::fields_ordered("start", "end");
+        let values = |value: Value| value.as_object().unwrap().clone();
+        assert!(!ordered(&values(json!({ "start": "2026-11-02", "end": "2026-11-06" }))));
+        assert!(!ordered(&values(json!({ "start": "2026-11-02", "end": "2026-11-02" }))));
+        assert!(ordered(&values(json!({ "start": "2026-11-06", "end": "2026-11-02" }))));
+        assert!(ordered(&values(json!({ "start": 10, "end": 2 }))));
+        assert!(!ordered(&values(json!({ "start": "", "end": "2026-11-02" }))));
+        assert!(!ordered(&values(json!({ "start": "2026-11-06", "end": null }))));
+        assert!(!ordered(&values(json!({ "start": "10", "end": 2 }))));
+        assert!(!ordered(&values(json!({ "start": "2026-11-06" }))));
+    }
+
+    #[test]
+    fn paths_split_into_names() {
+        assert_eq!(parse_path("period.from"), Some(vec!["period", "from"]));
+        assert_eq!(parse_path("title"), Some(vec!["title"]));
+    }
+
+    #[test]
+    fn malformed_paths_are_rejected() {
+        for path in ["", "period.", ".from", "period..from"] {
+            assert_eq!(parse_path(path), None, "{}", path);
+        }
+    }
+
+    fn period(from: &str, to: &str) -> FormGroup {
+        let mut group = FormGroup::builder();
+        group.add_control("from", FormControl::date(NaiveDate::parse_from_str(from, "%Y-%m-%d").ok(), Vec::new()));
+        group.add_control("to", FormControl::date(NaiveDate::parse_from_str(to, "%Y-%m-%d").ok(), Vec::new()));
+        group
+    }
+
+    fn filter() -> FormGroup {
+        let mut form = FormGroup::builder();
+        form.add_control("actor", FormControl::control(String::from("admin"), Vec::new()));
+        form.add_control("per_page", FormControl::integer(Some(20), Vec::new()));
+        form.add_group("period", period("2026-11-02", "2026-11-06"));
+        form
+    }
+
+    #[test]
+    fn nested_groups_give_nested_json() {
+        in_runtime(|| {
+            let form = filter();
+            assert_eq!(Value::Object(form.to_json()), json!({
+                "actor": "admin",
+                "per_page": 20,
+                "period": { "from": "2026-11-02", "to": "2026-11-06" },
+            }));
+            let from = form.get_control("period.from").unwrap();
+            assert_eq!(from.lock().unwrap().get_value(), "2026-11-02");
+            assert!(form.get_group("period").is_some());
+            assert!(form.get_control("period").is_none());
+            assert!(form.get("period.until").is_none());
+            assert!(form.get("actor.from").is_none());
+        });
+    }
+
+    #[test]
+    fn errors_of_nested_controls_are_reported_by_path() {
+        in_runtime(|| {
+            let form = filter();
+            form.get_control("period.to").unwrap().lock().unwrap().set_value(String::from("06/11/2026"));
+            let errors = form.validate_all();
+            assert_eq!(errors.keys().collect::<Vec<_>>(), ["period.to"]);
+            assert_eq!(errors["period.to"], [String::from("invalidDate")]);
+        });
+    }
+}
@@ -2,4 +2,8 @@ pub mod landing;
 pub mod navbar;
 pub mod signin;
 pub mod form_builder;
-pub mod admin_page;
\ No newline at end of file
+pub mod admin_page;
+pub mod account_status;
+pub mod two_factor;
+pub mod passkeys;
+pub mod phone_change;
\ No newline at end of file
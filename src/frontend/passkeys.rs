@@ -0,0 +1,225 @@
+use dioxus::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use webauthn_rs_proto::{CreationChallengeResponse, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse};
+
+use crate::{backend::{api::auth, forms::{AuthError, NewPasskeyForm, PasskeyLoginForm, PasskeySummary, TwoFactorStep}}, frontend::account_status::ACCOUNT_STATUS};
+
+fn error_message(error: &ServerFnError<AuthError>) -> &'static str {
+    match error {
+        ServerFnError::WrappedServerError(AuthError::InvalidPasskey) => "This passkey was not accepted, please try again",
+        ServerFnError::WrappedServerError(AuthError::TooManyPasskeys) => "You have too many passkeys, remove one first",
+        ServerFnError::WrappedServerError(AuthError::InvalidForm) => "Give the passkey a name",
+        ServerFnError::WrappedServerError(AuthError::Unauthenticated) => "Your sign-in has expired, please sign in again",
+        ServerFnError::WrappedServerError(_) => "Something went wrong, please try again",
+        _ => "Could not reach the server",
+    }
+}
+
+/// Whether the browser can use passkeys at all. Only to be called from effects and event handlers, which do
+/// not run during server-side rendering.
+fn is_supported() -> bool {
+    web_sys::window().is_some_and(|window| js_sys::Reflect::has(&window, &"PublicKeyCredential".into()).unwrap_or(false))
+}
+
+/// `navigator.credentials`, for the browser's passkey prompts.
+fn credentials() -> Option<web_sys::CredentialsContainer> {
+    web_sys::window().map(|window| window.navigator().credentials())
+}
+
+/// Waits for the browser's prompt. The browser rejects with `NotAllowedError` when the member cancels it.
+async fn prompt(promise: Result<js_sys::Promise, wasm_bindgen::JsValue>) -> Result<web_sys::PublicKeyCredential, &'static str> {
+    let credential = JsFuture::from(promise.map_err(|_| "Passkeys are not available in this browser")?).await
+        .map_err(|_| "The passkey prompt was cancelled or timed out")?;
+    credential.dyn_into::<web_sys::PublicKeyCredential>().map_err(|_| "The browser did not return a passkey")
+}
+
+async fn create_credential(challenge: CreationChallengeResponse) -> Result<RegisterPublicKeyCredential, &'static str> {
+    let credentials = credentials().ok_or("Passkeys are not available in this browser")?;
+    let credential = prompt(credentials.create_with_options(&challenge.into())).await?;
+    Ok(RegisterPublicKeyCredential::from(credential))
+}
+
+async fn get_credential(challenge: RequestChallengeResponse) -> Result<PublicKeyCredential, &'static str> {
+    let credentials = credentials().ok_or("Passkeys are not available in this browser")?;
+    let credential = prompt(credentials.get_with_options(&challenge.into())).await?;
+    Ok(PublicKeyCredential::from(credential))
+}
+
+/// Signs in with a passkey the browser holds for this site, instead of the phone number. Problems other than
+/// an inactive account or missing two-factor set-up, which have pages of their own, go to `on_error`.
+#[component]
+pub fn PasskeySignInButton(on_error: EventHandler<String>) -> Element {
+    let mut is_loading = use_signal(|| false);
+    let mut is_available = use_signal(|| false);
+    let navigator = use_navigator();
+    use_effect(move || is_available.set(is_supported()));
+    let sign_in = move |_| async move {
+        is_loading.set(true);
+        let result = match auth::start_passkey_login().await {
+            Ok(challenge) => match get_credential(challenge).await {
+                Ok(credential) => auth::finish_passkey_login(PasskeyLoginForm { credential }).await.map_err(|e| match e {
+                    ServerFnError::WrappedServerError(AuthError::AccountInactive(status)) => {
+                        *ACCOUNT_STATUS.write() = Some(status);
+                        navigator.push("/account-status");
+                        None
+                    },
+                    ServerFnError::WrappedServerError(AuthError::TwoFactorRequired(TwoFactorStep::Enroll)) => Some("Two-factor authentication has to be set up before you can sign in. Ask an administrator to enroll you".to_string()),
+                    e => Some(error_message(&e).to_string()),
+                }),
+                Err(message) => Err(Some(message.to_string())),
+            },
+            Err(e) => Err(Some(error_message(&e).to_string())),
+        };
+        is_loading.set(false);
+        match result {
+            Ok(_) => {
+                navigator.push("/admin");
+            },
+            Err(Some(message)) => on_error.call(message),
+            Err(None) => {},
+        }
+    };
+    rsx! {
+        if is_available() {
+            button {
+                r#type: "button",
+                class: "btn btn-outline w-full",
+                disabled: is_loading(),
+                onclick: sign_in,
+                if is_loading() {
+                    span { class: "loading loading-spinner" }
+                    "Waiting for your passkey"
+                } else {
+                    "Sign in with passkey"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PasskeyRow(passkey: PasskeySummary, on_removed: EventHandler<()>) -> Element {
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+    let id = passkey.id.clone();
+    let remove = move |_| {
+        let id = id.clone();
+        async move {
+            match auth::remove_passkey(id).await {
+                Ok(_) => on_removed.call(()),
+                Err(e) => error.set(Some(error_message(&e).to_string())),
+            }
+        }
+    };
+    let last_used = passkey.last_used
+        .map(|date| format!("last used {}", date.format("%d %b %Y")))
+        .unwrap_or_else(|| String::from("never used"));
+    rsx! {
+        li { class: "flex flex-row items-center justify-between bg-base-200 rounded-box px-3 py-2",
+            div {
+                p { class: "font-semibold", "{passkey.name}" }
+                p { class: "text-sm text-[var(--color-neutral)]",
+                    "Added {passkey.created.format(\"%d %b %Y\")}, {last_used}"
+                }
+                if let Some(error) = error() {
+                    p { class: "text-sm text-[var(--color-error)]", "{error}" }
+                }
+            }
+            button { class: "btn btn-ghost btn-sm", onclick: remove, "Remove" }
+        }
+    }
+}
+
+/// Passkeys of the logged in member: adding one on this device, and removing those no longer used.
+#[component]
+pub fn PasskeysPage() -> Element {
+    let mut passkeys = use_resource(|| async move { auth::list_passkeys().await });
+    let mut name = use_signal(String::new);
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+    let mut is_loading = use_signal(|| false);
+    let mut is_available = use_signal(|| false);
+    use_effect(move || is_available.set(is_supported()));
+    let add = move |evt: Event<FormData>| async move {
+        evt.prevent_default();
+        is_loading.set(true);
+        let result = match auth::start_passkey_registration().await {
+            Ok(challenge) => match create_credential(challenge).await {
+                Ok(credential) => auth::finish_passkey_registration(NewPasskeyForm { name: name(), credential }).await
+                    .map_err(|e| error_message(&e).to_string()),
+                Err(message) => Err(message.to_string()),
+            },
+            Err(e) => Err(error_message(&e).to_string()),
+        };
+        is_loading.set(false);
+        match result {
+            Ok(_) => {
+                name.set(String::new());
+                error.set(None);
+                passkeys.restart();
+            },
+            Err(message) => error.set(Some(message)),
+        }
+    };
+    rsx! {
+        div { "data-theme": "light", class: "w-screen min-h-screen flex flex-col items-center justify-center p-8",
+            div { class: "card bg-base-100 shadow-sm w-full max-w-xl",
+                div { class: "card-body space-y-4",
+                    p { class: "text-2xl font-semibold", "Passkeys" }
+                    p {
+                        "Sign in with your fingerprint, face or screen lock instead of your mobile number."
+                    }
+                    match &*passkeys.read() {
+                        Some(Ok(list)) => rsx! {
+                            ul { class: "flex flex-col space-y-2",
+                                for passkey in list.clone() {
+                                    PasskeyRow {
+                                        key: "{passkey.id}",
+                                        passkey,
+                                        on_removed: move |_| passkeys.restart(),
+                                    }
+                                }
+                            }
+                        },
+                        Some(Err(e)) => rsx! {
+                            div { role: "alert", class: "alert alert-soft alert-error",
+                                span { "{error_message(e)}" }
+                            }
+                            Link { class: "btn btn-ghost", to: "/signin", "Back to sign in" }
+                        },
+                        None => rsx! {
+                            span { class: "loading loading-spinner self-center" }
+                        },
+                    }
+                    if is_available() {
+                        form { class: "flex flex-col space-y-2", onsubmit: add,
+                            input {
+                                class: "input w-full",
+                                placeholder: "Name, e.g. Work laptop",
+                                r#type: "text",
+                                maxlength: 64,
+                                value: name(),
+                                oninput: move |evt| name.set(evt.value()),
+                            }
+                            if let Some(error) = error() {
+                                p { class: "label text-right text-[var(--color-error)]", "{error}" }
+                            }
+                            button {
+                                r#type: "submit",
+                                class: "btn btn-secondary w-full",
+                                disabled: is_loading() || name().trim().is_empty(),
+                                if is_loading() {
+                                    span { class: "loading loading-spinner" }
+                                    "Waiting for your passkey"
+                                } else {
+                                    "Add a passkey"
+                                }
+                            }
+                        }
+                    } else {
+                        p { class: "text-[var(--color-neutral)]", "This browser does not support passkeys." }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use dioxus::prelude::*;
+
+use crate::{backend::{api::auth, forms::{AuthError, ConfirmPhoneChangeForm, PhoneChangeChallenge, PhoneChangeForm}, phone}, frontend::{form_builder::{use_leave_warning, AsyncValidator, FormControl, Validator}, signin::PhoneNumberInput}};
+
+fn error_message(error: &ServerFnError<AuthError>) -> &'static str {
+    match error {
+        ServerFnError::WrappedServerError(AuthError::InvalidForm) => "Enter a different, valid phone number and the codes you received",
+        ServerFnError::WrappedServerError(AuthError::AlreadyRegistered) => "This phone number is already registered",
+        ServerFnError::WrappedServerError(AuthError::InvalidVerificationCode) => "A code is not valid or has expired, please try again",
+        ServerFnError::WrappedServerError(AuthError::Unauthenticated) => "Your sign-in has expired, please sign in again",
+        ServerFnError::WrappedServerError(AuthError::TooManyPhoneChanges) => "Codes were sent too often, please wait a few minutes and try again",
+        ServerFnError::WrappedServerError(_) => "Something went wrong, please try again",
+        _ => "Could not reach the server",
+    }
+}
+
+/// A code sent by text message, which is six digits. An empty one only fails `required`.
+fn sms_code() -> FormControl {
+    FormControl::control(String::new(), vec![
+        Validator::new("required", Validator::required()),
+        Validator::new("notACode", Validator::pattern(r"^(\s*\d{6}\s*)?$")),
+    ])
+}
+
+fn code_error(error: &str) -> &'static str {
+    match error {
+        "required" => "Enter the code you received",
+        "notACode" => "Codes sent by text message are 6 digits",
+        _ => "",
+    }
+}
+
+#[component]
+fn CodeField(label: String, mut control: FormControl) -> Element {
+    let error = control.errors().first().map(|error| code_error(error));
+    rsx! {
+        label { class: "flex flex-col w-full space-y-1",
+            span { "{label}" }
+            input {
+                class: "input w-full tracking-widest",
+                placeholder: "123456",
+                r#type: "text",
+                autocomplete: "one-time-code",
+                value: *control.get_raw_value(),
+                oninput: move |evt| control.handle_input(evt.value()),
+                onfocusout: move |_| control.handle_blur(),
+            }
+            if let Some(error) = error {
+                span { class: "label text-sm text-[var(--color-error)]", "{error}" }
+            }
+        }
+    }
+}
+
+/// Moves the logged in member to a new phone number: a code is sent to the new number and to the current one, or a
+/// recovery code stands in for the current one when it is lost.
+#[component]
+pub fn PhoneChangePage() -> Element {
+    let navigator = use_navigator();
+    let phone_number = use_hook(|| FormControl::control(String::new(), vec![
+        Validator::new("required", Validator::required()),
+        Validator::new("invalidPhoneNumber", Validator::phone_number()),
+    ]).with_async_validators(vec![
+        AsyncValidator::new("alreadyRegistered", Duration::from_millis(400), |value: String| async move {
+            let form = PhoneChangeForm { phone_number: phone::normalize(&value) };
+            matches!(auth::phone_number_available(form).await, Ok(false))
+        }),
+    ]));
+    // A recovery code can stand in for the one sent to the current number, so only the new one has to be digits.
+    let mut current_code = use_hook(|| FormControl::control(String::new(), vec![Validator::new("required", Validator::required())]));
+    let mut new_code = use_hook(sms_code);
+    let mut challenge: Signal<Option<PhoneChangeChallenge>> = use_signal(|| None);
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+    let mut is_loading = use_signal(|| false);
+    let mut is_done = use_signal(|| false);
+    use_leave_warning(move || !*is_done.peek() && (phone_number.is_dirty() || challenge.peek().is_some()));
+    let start = move |evt: Event<FormData>| async move {
+        evt.prevent_default();
+        let mut control = phone_number;
+        control.mark_touched();
+        if !control.validate().is_empty() {
+            return;
+        }
+        is_loading.set(true);
+        let form = PhoneChangeForm { phone_number: phone::normalize(&phone_number.get_value()) };
+        match auth::start_phone_change(form).await {
+            Ok(sent) => {
+                error.set(None);
+                challenge.set(Some(sent));
+            },
+            Err(e) => error.set(Some(error_message(&e).to_string())),
+        }
+        is_loading.set(false);
+    };
+    let confirm = move |evt: Event<FormData>| async move {
+        evt.prevent_default();
+        new_code.mark_touched();
+        current_code.mark_touched();
+        if !new_code.validate().is_empty() || !current_code.validate().is_empty() {
+            return;
+        }
+        is_loading.set(true);
+        let form = ConfirmPhoneChangeForm { current_code: current_code.get_value().trim().to_string(), new_code: new_code.get_value().trim().to_string() };
+        match auth::confirm_phone_change(form).await {
+            Ok(_) => is_done.set(true),
+            Err(e) => {
+                error.set(Some(error_message(&e).to_string()));
+                current_code.reset();
+                new_code.reset();
+            },
+        }
+        is_loading.set(false);
+    };
+    rsx! {
+        div { "data-theme": "light", class: "w-screen min-h-screen flex flex-col items-center justify-center p-8",
+            div { class: "card bg-base-100 shadow-sm w-full max-w-xl",
+                div { class: "card-body space-y-4",
+                    p { class: "text-2xl font-semibold", "Change your phone number" }
+                    if is_done() {
+                        p { "Your account now uses your new number. Other devices have been signed out." }
+                        button {
+                            class: "btn btn-secondary w-full",
+                            onclick: move |_| {
+                                navigator.push("/");
+                            },
+                            "Done"
+                        }
+                    } else if let Some(sent) = challenge() {
+                        form { class: "flex flex-col space-y-4", onsubmit: confirm,
+                            p { "We sent a code to {sent.new_phone_number} and one to your current number {sent.current_phone_number}." }
+                            CodeField { label: format!("Code sent to {}", sent.new_phone_number), control: new_code }
+                            CodeField {
+                                label: if sent.recovery_code_allowed { format!("Code sent to {}, or a recovery code if you lost it", sent.current_phone_number) } else { format!("Code sent to {}", sent.current_phone_number) },
+                                control: current_code,
+                            }
+                            if !sent.recovery_code_allowed {
+                                p { class: "text-sm text-[var(--color-neutral)]",
+                                    "Lost your current number? Contact support to have it replaced."
+                                }
+                            }
+                            if let Some(error) = error() {
+                                p { class: "label text-right text-[var(--color-error)]", "{error}" }
+                            }
+                            button {
+                                r#type: "submit",
+                                class: "btn btn-secondary w-full",
+                                disabled: is_loading() || new_code.get_value().trim().is_empty() || current_code.get_value().trim().is_empty(),
+                                if is_loading() {
+                                    span { class: "loading loading-spinner" }
+                                    "Verifying"
+                                } else {
+                                    "Change number"
+                                }
+                            }
+                        }
+                    } else {
+                        form { class: "flex flex-col space-y-4", onsubmit: start,
+                            PhoneNumberInput { form_control: phone_number }
+                            if let Some(error) = error() {
+                                p { class: "label text-right text-[var(--color-error)]", "{error}" }
+                            }
+                            button {
+                                r#type: "submit",
+                                class: "btn btn-secondary w-full",
+                                disabled: is_loading() || phone_number.pending() || !phone_number.errors().is_empty() || phone_number.get_value().trim().is_empty(),
+                                if is_loading() {
+                                    span { class: "loading loading-spinner" }
+                                    "Sending codes"
+                                } else {
+                                    "Send codes"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `f` where signals can be created, as inside a component.
+    fn in_runtime(f: impl FnOnce()) {
+        let dom = VirtualDom::new(|| rsx! {});
+        dom.in_runtime(|| ScopeId::ROOT.in_runtime(f));
+    }
+
+    #[test]
+    fn codes_sent_by_text_message_are_six_digits() {
+        in_runtime(|| {
+            let mut code = sms_code();
+            for (text, errors) in [("", vec!["required"]), ("12345", vec!["notACode"]), ("12a456", vec!["notACode"]), (" 123456 ", vec![])] {
+                code.set_value(text.to_string());
+                assert_eq!(code.validate(), errors, "{:?}", text);
+            }
+        });
+    }
+}
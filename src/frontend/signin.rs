@@ -1,17 +1,13 @@
-
-use std::{cell::RefCell, rc::Rc, sync::{Arc, Mutex}};
+use std::{str::FromStr, sync::{Arc, Mutex}};
 
 use dioxus::{dioxus_core::SpawnIfAsync, logger::tracing::instrument::WithSubscriber, prelude::*};
 use dioxus_free_icons::{icons::bs_icons::BsX, Icon};
+use phonenumber::country::Id;
 use dioxus_query::{prelude::{use_mutation, Captured, Mutation, MutationCapability, MutationReader, MutationStateData}, query::QueryCapability};
 use validator::ValidateRequired;
 
-use crate::{backend::{forms::{ResourceValues, Token}, utils}, frontend::form_builder::{FormControl, FormControlProps, FormGroup, Validator}};
+use crate::{backend::{api::auth, forms::{AuthError, AuthUserForm, ResourceValues, Token, TwoFactorStep, UserView}, phone::{self, NumberKind}}, frontend::account_status::ACCOUNT_STATUS, frontend::form_builder::{FormControl, FormControlProps, FormGroup, Validator}, frontend::passkeys::PasskeySignInButton, frontend::two_factor::TwoFactorVerify};
 
-use gloo_net::http::Request;
-use http::StatusCode;
-use web_sys::RequestCredentials;
-use serde_json::Value;
 use serde_json::Map;
 
 
@@ -19,27 +15,15 @@ use serde_json::Map;
 
 
 #[derive(Clone,Default)]
-struct AuthRequests(Rc<RefCell<Value>>);
+struct AuthRequests;
 
 impl AuthRequests {
-    async fn login(&self,body:& Value) -> Result<Value,ServerFnError> {
-        let url = "http://127.0.0.1:8080/api/login";
-        let result = Request::post(url)
-        .credentials(RequestCredentials::Include)
-        .json(body)?
-        .send()
-        .await?;
-        let status = StatusCode::from_u16(result.status())
-        .map_err(|_| ServerFnError::<String>::ServerError(StatusCode::INTERNAL_SERVER_ERROR.to_string())).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-        match status {
-            StatusCode::OK => {
-                match result.json::<Value>().await {
-                    std::result::Result::Ok(value) => std::result::Result::Ok(value),
-                    Err(_) => Err(ServerFnError::ServerError(StatusCode::INTERNAL_SERVER_ERROR.to_string()))
-                }
-            },
-            _ => Err(ServerFnError::ServerError(String::from(status.as_str())))
-        }    
+    async fn login(&self, form: &AuthUserForm) -> Result<UserView, ServerFnError<AuthError>> {
+        let result = auth::login(form.clone()).await;
+        if let Err(ServerFnError::WrappedServerError(AuthError::AccountInactive(status))) = &result {
+            *ACCOUNT_STATUS.write() = Some(status.clone());
+        }
+        result
     }
 }
 
@@ -47,12 +31,12 @@ impl AuthRequests {
 struct SignInRequest(Captured<AuthRequests>);
 
 impl MutationCapability for SignInRequest {
-    type Ok = Value;
-    type Err = ServerFnError;
-    type Keys = Value;
+    type Ok = UserView;
+    type Err = ServerFnError<AuthError>;
+    type Keys = AuthUserForm;
     
-    async fn run(&self, body: &Self::Keys) -> Result<Self::Ok, Self::Err> {
-        self.0.login(body).await
+    async fn run(&self, form: &Self::Keys) -> Result<Self::Ok, Self::Err> {
+        self.0.login(form).await
     }
     
 }
@@ -61,39 +45,106 @@ fn create_sign_in_form() -> FormGroup{
     let mut form = FormGroup::builder();
     form.add_control("password", FormControl::control(String::new(), vec![
         Validator::new("required", Validator::required()),
-        Validator::new("invalidPhoneNumber", Validator::pattern(r"^\+255\s[67]\d{2}\s\d{3}\s\d{3}$"))
+        Validator::new("invalidPhoneNumber", Validator::phone_number())
     ]));
     form
 }
 
+/// Country of a phone number input. Picking another one moves the national number typed so far to it.
+#[component]
+pub fn CountrySelect(country: Id, on_change: EventHandler<Id>) -> Element {
+    rsx! {
+        select {
+            class: "select w-fit",
+            aria_label: "Country",
+            onchange: move |evt| {
+                if let Ok(id) = Id::from_str(&evt.value()) {
+                    on_change.call(id);
+                }
+            },
+            for option in phone::COUNTRIES.iter() {
+                option {
+                    value: option.id.as_ref(),
+                    selected: option.id == country,
+                    "{option.name} (+{option.dial_code})"
+                }
+            }
+        }
+    }
+}
+
+/// Network and kind of the number typed, once it is a valid one.
+#[component]
+pub fn PhoneNumberHint(phone_number: String, country: Id) -> Element {
+    let hint = phone::details(&phone_number, country).ok().map(|details| {
+        let kind = match details.kind {
+            NumberKind::Mobile => "Mobile",
+            NumberKind::FixedLine => "Landline, cannot receive text messages",
+            NumberKind::FixedLineOrMobile => "Mobile or landline",
+            NumberKind::Other => "Not a mobile number",
+        };
+        match details.carrier {
+            Some(carrier) => format!("{} · {}", carrier, kind),
+            None => kind.to_string(),
+        }
+    });
+    rsx! {
+        if let Some(hint) = hint {
+            p { class: "label text-sm text-[var(--color-neutral)]", "{hint}" }
+        }
+    }
+}
+
 #[component]
 pub fn PhoneNumberInput(form_control: FormControl) -> Element {
-    let format_phone_number = move |evt: Event<FormData>,control: &mut FormControl| {
-        let value = utils::format_phone_number(&evt.value());
-        control.set_value(value);
+    let mut country = use_signal(|| phone::DEFAULT_COUNTRY);
+    let mut format_phone_number = move |evt: Event<FormData>,control: &mut FormControl| {
+        let value = evt.value();
+        if let Some(id) = phone::country_of(&value) {
+            country.set(id);
+        }
+        control.handle_input(phone::format_international(&value, country()));
+    };
+    let mut change_country = move |id: Id,control: &mut FormControl| {
+        control.handle_input(phone::with_country(&control.get_value(), country(), id));
+        country.set(id);
     };
     let validate_phone_number = move |_,control: &mut FormControl| {
-        control.validate();
+        control.handle_blur();
     };
     let error_message = |error:&str| {
         match error {
             "required" => "Enter your mobile number",
             "invalidPhoneNumber" => "The mobile number you have entered is invalid",
+            "alreadyRegistered" => "This phone number is already registered",
             _ => ""
         }
     };
     let error = form_control.errors().first().map(|v| error_message(v.as_str()));
+    let placeholder = phone::find_country(country()).map(|c| format!("(+{}) XXX-XXX-XXX", c.dial_code)).unwrap_or_default();
     rsx! {
         div { class: "flex flex-col w-full",
-            input {
-                class: "input w-full",
-                placeholder: "(+255) XXX-XXX-XXX",
-                r#type: "text",
-                value: *form_control.get_raw_value(),
-                oninput: move |evt| format_phone_number(evt, &mut form_control),
-                onfocusout: move |evt| validate_phone_number(evt, &mut form_control),
+            div { class: "flex flex-row w-full space-x-2",
+                CountrySelect {
+                    country: country(),
+                    on_change: move |id| change_country(id, &mut form_control),
+                }
+                input {
+                    class: "input w-full",
+                    placeholder,
+                    r#type: "tel",
+                    autocomplete: "tel",
+                    value: *form_control.get_raw_value(),
+                    oninput: move |evt| format_phone_number(evt, &mut form_control),
+                    onfocusout: move |evt| validate_phone_number(evt, &mut form_control),
+                }
             }
-            div { class: "transition-all duration-200 relative flex flex-row justify-end items-center w-full h-fit",
+            div { class: "transition-all duration-200 relative flex flex-row justify-between items-center w-full h-fit",
+                if form_control.pending() {
+                    p { class: "label text-sm text-[var(--color-neutral)]", "Checking…" }
+                } else {
+                    PhoneNumberHint { phone_number: form_control.get_value(), country: country() }
+                }
                 p {
                     class: format!(
                         "label text-right text-[var(--color-error)] transition-all duration-300 {}",
@@ -106,26 +157,23 @@ pub fn PhoneNumberInput(form_control: FormControl) -> Element {
     }
 }
 
-fn clean_login_body(value:&mut Map<String,serde_json::Value>) -> Map<String,serde_json::Value> {
-    let key = String::from("password");
+fn login_form(value: &Map<String,serde_json::Value>) -> AuthUserForm {
     let password = value
-    .remove(&key)
-    .filter(|p| p.is_string())
-    .and_then(|f| f.as_str().map(|s| s.chars().filter(|c| !c.is_whitespace()).collect::<String>()));                
-    
-    let password = password.unwrap_or(String::new());
-    let password = serde_json::Value::String(password);
-    value.insert(key, password);
-    value.to_owned()
+    .get("password")
+    .and_then(|p| p.as_str())
+    .map(phone::normalize)
+    .unwrap_or_default();
+    AuthUserForm::new(password)
 }
 
 #[component]
 pub fn SignPage() -> Element{
-    let sign_in = use_mutation(Mutation::new(SignInRequest(Captured(AuthRequests::default()))));
+    let sign_in = use_mutation(Mutation::new(SignInRequest(Captured(AuthRequests))));
     let mut form: Signal<Option<FormGroup>> = use_signal(|| None);
     let mut password: Signal<Option<Arc<Mutex<FormControl>>>> = use_signal(|| None);
     let mut alert_text: Signal<String> = use_signal(|| String::new());
     let mut alert_text_state: Signal<String> = use_signal(|| String::new());
+    let mut needs_two_factor_code = use_signal(|| false);
     let navigator = use_navigator();
     let close_alert = move |evt: Event<MouseData>| {
         evt.prevent_default();
@@ -137,8 +185,7 @@ pub fn SignPage() -> Element{
         let form_data = form().unwrap();
         let errors = form_data.validate_all();
         if errors.is_empty() {
-            let body = serde_json::to_value(clean_login_body(&mut form_data.to_json())).unwrap();
-            let sign_in_state = sign_in.mutate_async(body).await;
+            let sign_in_state = sign_in.mutate_async(login_form(&form_data.to_json())).await;
             let sign_in_state = sign_in_state.state();
             match sign_in_state.unwrap() {
                 Ok(val) => {
@@ -149,16 +196,19 @@ pub fn SignPage() -> Element{
                 },
                 Err(e) => {
                     match e {
-                        ServerFnError::ServerError(code_str) => {
-                            let status = code_str.parse::<u16>().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR.as_u16());
-                            match StatusCode::from_u16(status) {
-                                Ok(StatusCode::BAD_REQUEST) => alert_text.set("400 Bad Request".to_string()),
-                                Ok(StatusCode::NOT_FOUND) => alert_text.set("404 Not Found".to_string()),
-                                Ok(StatusCode::INTERNAL_SERVER_ERROR) => alert_text.set("500 Internal Server Error".to_string()),
-                                _ => alert_text.set(format!("Other error: {}", status)),
-                            }
+                        ServerFnError::WrappedServerError(AuthError::AccountInactive(_)) => {
+                            navigator.push("/account-status");
+                            return;
+                        },
+                        ServerFnError::WrappedServerError(AuthError::TwoFactorRequired(TwoFactorStep::Verify)) => {
+                            needs_two_factor_code.set(true);
+                            return;
                         },
-                        _ => alert_text.set("Unknown error".to_string()),
+                        ServerFnError::WrappedServerError(AuthError::TwoFactorRequired(TwoFactorStep::Enroll)) => alert_text.set("Two-factor authentication has to be set up before you can sign in. Ask an administrator to enroll you".to_string()),
+                        ServerFnError::WrappedServerError(AuthError::InvalidForm) => alert_text.set("The mobile number you have entered is invalid".to_string()),
+                        ServerFnError::WrappedServerError(AuthError::NotFound) => alert_text.set("No account uses this mobile number".to_string()),
+                        ServerFnError::WrappedServerError(_) => alert_text.set("Something went wrong, please try again".to_string()),
+                        _ => alert_text.set("Could not reach the server".to_string()),
                     }
                     alert_text_state.set(String::from("alert-error"));
                 },
@@ -188,72 +238,87 @@ pub fn SignPage() -> Element{
                 div { class: "flex flex-row justify-start",
                     p { class: "uppercase text-2xl font-extrabold", "Freelancer" }
                 }
-                form {
-                    id: "sign-in-form",
-                    class: "flex flex-col space-y-4",
-                    onsubmit: move |evt| async move {
-                        on_submit().await;
-                    },
-                    div {
-                        p { class: "text-4xl font-semibold", "Sign in or create an account." }
-                        p { class: "text-lg", "Welcome back! Please enter your details." }
+                if needs_two_factor_code() {
+                    TwoFactorVerify {
+                        on_verified: move |_| {
+                            navigator.push("/admin");
+                        },
                     }
-                    div {
-                        class: format!(
-                            "w-full duration-300 transition-all overflow-hidden {} relative",
-                            if alert_text_state().is_empty() {
-                                String::from("max-h-0")
-                            } else {
-                                String::from("max-h-40")
-                            },
-                        ),
+                } else {
+                    form {
+                        id: "sign-in-form",
+                        class: "flex flex-col space-y-4",
+                        onsubmit: move |evt| async move {
+                            on_submit().await;
+                        },
+                        div {
+                            p { class: "text-4xl font-semibold", "Sign in or create an account." }
+                            p { class: "text-lg", "Welcome back! Please enter your details." }
+                        }
                         div {
                             class: format!(
-                                "alert alert-soft {} relative flex flex-row items-center justify-between w-full",
-                                alert_text_state(),
+                                "w-full duration-300 transition-all overflow-hidden {} relative",
+                                if alert_text_state().is_empty() {
+                                    String::from("max-h-0")
+                                } else {
+                                    String::from("max-h-40")
+                                },
                             ),
-                            role: "alert",
-                            span { "{alert_text()}" }
-                            button {
-                                class: "btn btn-circle btn-sm btn-ghost",
-                                onclick: close_alert,
-                                r#type: "button",
-                                Icon {
-                                    width: 24,
-                                    height: 24,
-                                    fill: "var(--color-secondary)",
-                                    icon: BsX,
+                            div {
+                                class: format!(
+                                    "alert alert-soft {} relative flex flex-row items-center justify-between w-full",
+                                    alert_text_state(),
+                                ),
+                                role: "alert",
+                                span { "{alert_text()}" }
+                                button {
+                                    class: "btn btn-circle btn-sm btn-ghost",
+                                    onclick: close_alert,
+                                    r#type: "button",
+                                    Icon {
+                                        width: 24,
+                                        height: 24,
+                                        fill: "var(--color-secondary)",
+                                        icon: BsX,
+                                    }
                                 }
                             }
                         }
-                    }
-                    {
-                        match password() {
-                            Some(value) => {
-                                rsx! {
-                                    PhoneNumberInput { form_control: *value.lock().unwrap() }
+                        {
+                            match password() {
+                                Some(value) => {
+                                    rsx! {
+                                        PhoneNumberInput { form_control: *value.lock().unwrap() }
+                                    }
+                                }
+                                None => {
+                                    rsx! {}
                                 }
-                            }
-                            None => {
-                                rsx! {}
                             }
                         }
-                    }
-                    button {
-                        form: "sign-in-form",
-                        r#type: "submit",
-                        class: "btn btn-secondary w-full",
-                        disabled: is_fetching_loading(),
-                        match is_fetching_loading() {
-                            true => {
-                                rsx! {
-                                    span { class: "loading loading-spinner" }
-                                    "Loading"
+                        button {
+                            form: "sign-in-form",
+                            r#type: "submit",
+                            class: "btn btn-secondary w-full",
+                            disabled: is_fetching_loading(),
+                            match is_fetching_loading() {
+                                true => {
+                                    rsx! {
+                                        span { class: "loading loading-spinner" }
+                                        "Loading"
+                                    }
+                                }
+                                false => {
+                                    rsx! { "Become a member" }
                                 }
                             }
-                            false => {
-                                rsx! { "Become a member" }
-                            }
+                        }
+                        div { class: "divider text-[var(--color-neutral)]", "or" }
+                        PasskeySignInButton {
+                            on_error: move |message: String| {
+                                alert_text.set(message);
+                                alert_text_state.set(String::from("alert-error"));
+                            },
                         }
                     }
                 }
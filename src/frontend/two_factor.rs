@@ -0,0 +1,178 @@
+use dioxus::prelude::*;
+use qrcode::{render::svg, QrCode};
+
+use crate::backend::{api::auth, forms::{AuthError, RecoveryCodes, TwoFactorCodeForm}};
+
+fn error_message(error: &ServerFnError<AuthError>) -> &'static str {
+    match error {
+        ServerFnError::WrappedServerError(AuthError::InvalidForm) => "Enter the 6-digit code from your authenticator app",
+        ServerFnError::WrappedServerError(AuthError::InvalidTwoFactorCode) => "This code is not valid, please try again",
+        ServerFnError::WrappedServerError(AuthError::TwoFactorLocked) => "Too many invalid codes, please wait 15 minutes and try again",
+        ServerFnError::WrappedServerError(AuthError::Unauthenticated) => "Your sign-in has expired, please sign in again",
+        ServerFnError::WrappedServerError(AuthError::Forbidden) => "Two-factor authentication is only available to sellers and admins",
+        ServerFnError::WrappedServerError(AuthError::TwoFactorAlreadyEnabled) => "Two-factor authentication is already turned on",
+        ServerFnError::WrappedServerError(_) => "Something went wrong, please try again",
+        _ => "Could not reach the server",
+    }
+}
+
+/// `otpauth://` URI drawn as an SVG QR code for authenticator apps to scan.
+#[component]
+pub fn TwoFactorQrCode(uri: String) -> Element {
+    let svg = QrCode::new(uri.as_bytes())
+        .map(|code| code.render::<svg::Color>().min_dimensions(200, 200).quiet_zone(true).build())
+        .unwrap_or_default();
+    rsx! {
+        div { class: "w-52 h-52 bg-white p-1 rounded-box", dangerous_inner_html: svg }
+    }
+}
+
+/// Field for a 6-digit code or a recovery code, with the error of the last attempt below it.
+#[component]
+fn CodeInput(code: Signal<String>, error: Option<String>) -> Element {
+    rsx! {
+        div { class: "flex flex-col w-full",
+            input {
+                class: "input w-full tracking-widest",
+                placeholder: "123456",
+                r#type: "text",
+                inputmode: "numeric",
+                autocomplete: "one-time-code",
+                value: code(),
+                oninput: move |evt| code.set(evt.value()),
+            }
+            if let Some(error) = error {
+                p { class: "label text-right text-[var(--color-error)]", "{error}" }
+            }
+        }
+    }
+}
+
+/// Second step of signing in, for members with two-factor authentication.
+#[component]
+pub fn TwoFactorVerify(on_verified: EventHandler<()>) -> Element {
+    let mut code = use_signal(String::new);
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+    let mut is_loading = use_signal(|| false);
+    let on_submit = move |evt: Event<FormData>| async move {
+        evt.prevent_default();
+        is_loading.set(true);
+        match auth::verify_two_factor(TwoFactorCodeForm { code: code() }).await {
+            Ok(_) => on_verified.call(()),
+            Err(e) => {
+                error.set(Some(error_message(&e).to_string()));
+                code.set(String::new());
+            },
+        }
+        is_loading.set(false);
+    };
+    rsx! {
+        form { id: "two-factor-form", class: "flex flex-col space-y-4", onsubmit: on_submit,
+            div {
+                p { class: "text-4xl font-semibold", "Two-factor authentication" }
+                p { class: "text-lg",
+                    "Enter the code from your authenticator app, or one of your recovery codes."
+                }
+            }
+            CodeInput { code, error: error() }
+            button {
+                r#type: "submit",
+                class: "btn btn-secondary w-full",
+                disabled: is_loading() || code().trim().is_empty(),
+                if is_loading() {
+                    span { class: "loading loading-spinner" }
+                    "Verifying"
+                } else {
+                    "Verify"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RecoveryCodesList(codes: RecoveryCodes) -> Element {
+    let navigator = use_navigator();
+    rsx! {
+        div { class: "flex flex-col space-y-4",
+            p { class: "text-2xl font-semibold", "Save your recovery codes" }
+            p {
+                "Each code signs you in once if you lose your phone. Keep them somewhere safe: they will not be shown again."
+            }
+            ul { class: "grid grid-cols-2 gap-2 font-mono",
+                for recovery_code in codes.codes {
+                    li { class: "bg-base-200 rounded-box px-3 py-1 text-center", "{recovery_code}" }
+                }
+            }
+            button {
+                class: "btn btn-secondary w-full",
+                onclick: move |_| {
+                    navigator.push("/admin");
+                },
+                "I have saved my codes"
+            }
+        }
+    }
+}
+
+/// Sets up two-factor authentication: scan the QR code, confirm with a first code, then save the recovery codes.
+/// Only for signed in sellers and admins; admins who cannot sign in without it are enrolled from the CLI.
+#[component]
+pub fn TwoFactorPage() -> Element {
+    let enrollment = use_resource(|| async move { auth::enroll_two_factor().await });
+    let mut code = use_signal(String::new);
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+    let mut recovery_codes: Signal<Option<RecoveryCodes>> = use_signal(|| None);
+    let on_submit = move |evt: Event<FormData>| async move {
+        evt.prevent_default();
+        match auth::confirm_two_factor(TwoFactorCodeForm { code: code() }).await {
+            Ok(codes) => recovery_codes.set(Some(codes)),
+            Err(e) => {
+                error.set(Some(error_message(&e).to_string()));
+                code.set(String::new());
+            },
+        }
+    };
+    rsx! {
+        div { "data-theme": "light", class: "w-screen min-h-screen flex flex-col items-center justify-center p-8",
+            div { class: "card bg-base-100 shadow-sm w-full max-w-xl",
+                div { class: "card-body",
+                    if let Some(codes) = recovery_codes() {
+                        RecoveryCodesList { codes }
+                    } else {
+                        match &*enrollment.read() {
+                            Some(Ok(enrollment)) => rsx! {
+                                form { class: "flex flex-col items-center space-y-4", onsubmit: on_submit,
+                                    p { class: "text-2xl font-semibold self-start", "Set up two-factor authentication" }
+                                    p { class: "self-start",
+                                        "Scan this code with an authenticator app such as Google Authenticator or 1Password, then enter the 6-digit code it shows."
+                                    }
+                                    TwoFactorQrCode { uri: enrollment.otpauth_uri.clone() }
+                                    p { class: "text-sm text-[var(--color-neutral)] break-all",
+                                        "Or enter this key: {enrollment.secret}"
+                                    }
+                                    CodeInput { code, error: error() }
+                                    button {
+                                        r#type: "submit",
+                                        class: "btn btn-secondary w-full",
+                                        disabled: code().trim().is_empty(),
+                                        "Turn on"
+                                    }
+                                }
+                            },
+                            Some(Err(e)) => rsx! {
+                                div { role: "alert", class: "alert alert-soft alert-error",
+                                    span { "{error_message(e)}" }
+                                }
+                                Link { class: "btn btn-ghost", to: "/signin", "Back to sign in" }
+                            },
+                            None => rsx! {
+                                span { class: "loading loading-spinner self-center" }
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,19 +1,22 @@
-mod backend;
+use freelance_test::backend;
 mod frontend;
 
 
 use chrono::round;
 use dioxus::{dioxus_core::SpawnIfAsync, prelude::*};
-#[cfg(feature = "server")]
-use tokio::task::AbortHandle;
 
 
-use crate::backend::forms::ResourceValues;
+use crate::backend::api::auth;
+use crate::backend::forms::{AuthError, ResourceValues};
 use crate::backend::utils;
-use crate::backend::{forms::Token};
 use crate::frontend::landing::Landing;
 use crate::frontend::signin::SignPage;
 use crate::frontend::admin_page::AdminPage;
+use crate::frontend::account_status::{AccountStatusPage, ACCOUNT_STATUS};
+use crate::frontend::two_factor::TwoFactorPage;
+use crate::frontend::passkeys::PasskeysPage;
+use crate::frontend::phone_change::PhoneChangePage;
+use crate::frontend::form_builder::guard_navigation;
 
 #[derive(Debug, Clone, Routable, PartialEq)]
 #[rustfmt::skip]
@@ -23,7 +26,15 @@ enum Route {
     #[route("/signin")]
     SignPage,
     #[route("/admin")]
-    AdminPage
+    AdminPage,
+    #[route("/account-status")]
+    AccountStatusPage,
+    #[route("/two-factor")]
+    TwoFactorPage,
+    #[route("/passkeys")]
+    PasskeysPage,
+    #[route("/account/phone-number")]
+    PhoneChangePage
 }
 
 const FAVICON: Asset = asset!("/assets/favicon.ico");
@@ -51,8 +62,18 @@ fn main() {
 async fn launch_server(component: fn() -> Element) {
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
-    use crate::backend::{api::api, auths};
+    use crate::backend::{api::{media, routes::Routes}, auths, migrations::runner::Runner, monitoring::Monitoring, security_headers::SecurityHeaders, shutdown::Shutdown, sms::Sms, telemetry::Telemetry};
+
+    let _telemetry = Telemetry::init();
+    Monitoring::install();
 
+    Runner::migrate_on_startup().await.expect("Failed to run database migrations");
+    if let Err(e) = Sms::check_configuration() {
+        tracing::error!(error = %e, "Phone number change codes cannot be sent");
+    }
+    if Monitoring::metrics_token().is_none() {
+        tracing::warn!("METRICS_TOKEN is not set, /metrics is not served");
+    }
     let session_data = auths::auth_session::AuthSession::create_app_session().await;
     // let ip = dioxus::cli_config::fullstack_address_or_localhost().ip().to_owned();
     // let port = dioxus::cli_config::server_port().unwrap_or(dioxus::cli_config::server_port().unwrap().to_owned());
@@ -62,24 +83,31 @@ async fn launch_server(component: fn() -> Element) {
     let address = SocketAddr::new(ip, port);
     let listener = tokio::net::TcpListener::bind(address).await.unwrap();
 
-    let rest_api = axum::Router::new()
-    .route("/register", axum::routing::post(api::Api::register_user))
-    .route("/login", axum::routing::post(api::Api::login_user))
-    .route("/is_authenticated", axum::routing::get(api::Api::is_authenticated));
-
-    let router = axum::Router::new()
-        .nest("/api", rest_api)
-        .layer(session_data.layer.to_owned())
+    let router = Routes::documented_api_router()
+        .merge(Monitoring::router())
+        .route("/media/*key", axum::routing::get(media::Media::serve_blob))
         .serve_dioxus_application(ServeConfigBuilder::default(), component)
-        .into_make_service();
-
-    axum::serve(listener, router)
-    .with_graceful_shutdown(shutdown_signal(session_data.deletion_task.abort_handle()))
-    .await.unwrap();
+        .layer(axum::middleware::from_fn(Monitoring::track_requests))
+        .layer(axum::middleware::from_fn(auths::csrf::Csrf::protect))
+        .layer(axum::middleware::from_fn(SecurityHeaders::apply))
+        .layer(session_data.layer.to_owned())
+        .layer(Routes::cors());
+    let router = Telemetry::trace_requests(router).into_make_service_with_connect_info::<SocketAddr>();
+    tracing::info!(%address, "Listening");
 
-    if let Err(err) = session_data.deletion_task.await {
-        print!("Deletion task failed: {:?}", err);
+    let server = axum::serve(listener, router).with_graceful_shutdown(Shutdown::signal());
+    tokio::select! {
+        result = server => {
+            if let Err(e) = result {
+                tracing::error!(error = %e, "Server failed");
+            }
+        },
+        _ = Shutdown::drain_deadline() => tracing::warn!("In-flight requests did not finish within the drain timeout"),
     }
+
+    Shutdown::begin();
+    Shutdown::join_task("session deletion", session_data.deletion_task, Shutdown::drain_timeout()).await;
+    tracing::info!("Shutdown complete");
 }
 
 #[component]
@@ -88,37 +116,27 @@ fn App() -> Element {
         document::Link { rel: "icon", href: FAVICON }
         document::Link { rel: "stylesheet", href: MAIN_CSS }
         document::Link { rel: "stylesheet", href: TAILWIND_CSS }
-        Router::<Route> {}
+        Router::<Route> { config: || RouterConfig::default().on_update(|_| guard_navigation::<Route>()) }
     }
 }
 
-use gloo_net::http::Request;
-use web_sys::{window, RequestCredentials};
-
 
 #[component]
 fn View() -> Element{
     let mut is_authenticated = use_resource(move || async move {
-        let url = "http://127.0.0.1:8080/api/is_authenticated";
-        let result = Request::get(url)
-        .credentials(RequestCredentials::Include)
-        .send()
-        .await;
-        match result {
-            Ok(resp) => {
-                match resp.json::<Token>().await {
-                    Ok(token) => Ok(token),
-                    Err(err) => Err(format!("Failed to parse JSON: {}", err)),
-                }
-            }
-            Err(err) => Err(format!("Request failed: {}", err)),
+        let result = auth::is_authenticated().await;
+        if let Err(ServerFnError::WrappedServerError(AuthError::AccountInactive(status))) = &result {
+            *ACCOUNT_STATUS.write() = Some(status.clone());
         }
+        result
     });
     let theme = utils::read_local_storage_value(utils::CURRENT_THEME);
     use_context_provider(|| ResourceValues { is_authenticated: is_authenticated, theme_mode: Signal::new(theme) });
     let mut context_resource = use_context::<ResourceValues>();
     rsx! {
-        if let Some(response) = &*context_resource.is_authenticated.read() {
+        if ACCOUNT_STATUS().is_some() {
+            AccountStatusPage {}
+        } else if let Some(response) = &*context_resource.is_authenticated.read() {
             match response {
                 Ok(value) => {
                     rsx! {
@@ -136,16 +154,3 @@ fn View() -> Element{
         }
     }
 }
-
-#[cfg(feature = "server")]
-async fn shutdown_signal(deletion_task_abort_handle: AbortHandle) {
-    let ctrl_c = async {
-        tokio::signal::ctrl_c().await.expect("Failed to install Ctrl+C handler")
-    };
-    
-    let terminate = std::future::pending::<()>();
-    tokio::select! {
-        _ = ctrl_c => {deletion_task_abort_handle.abort();}
-        _ = terminate => {deletion_task_abort_handle.abort();}
-    }
-}
\ No newline at end of file
@@ -0,0 +1,28 @@
+#![cfg(feature = "server")]
+
+use chrono::{Duration, TimeZone, Utc};
+use freelance_test::backend::forms::AccountStatus;
+
+#[test]
+fn active_accounts_can_sign_in() {
+    assert!(AccountStatus::Active.is_active_at(Utc::now()));
+    assert!(AccountStatus::default().is_active());
+}
+
+#[test]
+fn suspensions_lift_once_until_has_passed() {
+    let until = Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+    let status = AccountStatus::Suspended { until, reason: "Spam".to_string() };
+    assert!(!status.is_active_at(until - Duration::days(3)));
+    assert!(!status.is_active_at(until - Duration::seconds(1)));
+    assert!(status.is_active_at(until));
+    assert!(status.is_active_at(until + Duration::seconds(1)));
+}
+
+#[test]
+fn bans_never_lift() {
+    let status = AccountStatus::Banned { reason: "Fraud".to_string() };
+    assert!(!status.is_active_at(Utc::now()));
+    assert!(!status.is_active_at(Utc::now() + Duration::days(365 * 10)));
+    assert!(!status.is_active());
+}
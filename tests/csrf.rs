@@ -0,0 +1,43 @@
+#![cfg(feature = "server")]
+
+use axum::{body::Body, http::{header, Method, Request, StatusCode}, middleware, routing::{get, post}, Router};
+use freelance_test::backend::auths::csrf::{Csrf, CSRF_COOKIE, CSRF_HEADER};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route("/api/v1/items", post(|| async { "created" }))
+        .route("/page", get(|| async { "page" }))
+        .layer(middleware::from_fn(Csrf::protect))
+}
+
+fn post_items(cookie: Option<&str>, token: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder().method(Method::POST).uri("/api/v1/items");
+    if let Some(cookie) = cookie {
+        request = request.header(header::COOKIE, format!("{}={}", CSRF_COOKIE, cookie));
+    }
+    if let Some(token) = token {
+        request = request.header(CSRF_HEADER, token);
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn first_visit_receives_a_token_cookie() {
+    let response = app().oneshot(Request::builder().uri("/page").body(Body::empty()).unwrap()).await.unwrap();
+    let token = response.headers()[CSRF_HEADER].to_str().unwrap().to_string();
+    let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(cookie.starts_with(&format!("{}={}", CSRF_COOKIE, token)), "{}", cookie);
+}
+
+#[tokio::test]
+async fn state_changing_api_requests_need_a_matching_token() {
+    async fn status(request: Request<Body>) -> StatusCode {
+        app().oneshot(request).await.unwrap().status()
+    }
+    assert_eq!(status(post_items(None, None)).await, StatusCode::FORBIDDEN);
+    assert_eq!(status(post_items(Some("token-a"), None)).await, StatusCode::FORBIDDEN);
+    assert_eq!(status(post_items(None, Some("token-a"))).await, StatusCode::FORBIDDEN);
+    assert_eq!(status(post_items(Some("token-a"), Some("token-b"))).await, StatusCode::FORBIDDEN);
+    assert_eq!(status(post_items(Some("token-a"), Some("token-a"))).await, StatusCode::OK);
+}
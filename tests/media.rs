@@ -0,0 +1,78 @@
+#![cfg(feature = "server")]
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use freelance_test::backend::{
+    media::{blob_store::Blobs::{BlobStore, BlobStoreError, LocalBlobStore}, images::Images::{self, ImageError}},
+    mongo_models::Docs::ImageKind,
+};
+use image::{ImageFormat, RgbImage};
+
+/// An empty directory under the system temp dir, unique to `name`.
+fn media_root(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("freelance-media-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    root
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    RgbImage::new(width, height).write_to(&mut bytes, ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+#[tokio::test]
+async fn keys_stay_inside_the_root() {
+    let root = media_root("keys");
+    let store = LocalBlobStore::new(root.join("store"));
+    for key in ["../escaped.png", "a/../../escaped.png", "/tmp/escaped.png", "./a.png", "a\\..\\b.png", ""] {
+        assert!(matches!(store.put(key, b"x").await, Err(BlobStoreError::InvalidKeyError(_))), "put {key:?}");
+        assert!(matches!(store.get(key).await, Err(BlobStoreError::InvalidKeyError(_))), "get {key:?}");
+        assert!(matches!(store.delete(key).await, Err(BlobStoreError::InvalidKeyError(_))), "delete {key:?}");
+    }
+    assert!(!root.join("escaped.png").exists());
+    assert!(!root.exists() || std::fs::read_dir(&root).unwrap().next().is_none());
+}
+
+#[tokio::test]
+async fn nested_keys_round_trip() {
+    let root = media_root("round-trip");
+    let store = LocalBlobStore::new(&root);
+    store.put("listing/abc/thumb.png", b"bytes").await.unwrap();
+    assert_eq!(store.get("listing/abc/thumb.png").await.unwrap().as_deref(), Some(&b"bytes"[..]));
+    assert!(store.delete("listing/abc/thumb.png").await.unwrap());
+    assert_eq!(store.get("listing/abc/thumb.png").await.unwrap(), None);
+    assert!(!store.delete("listing/abc/thumb.png").await.unwrap());
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn oversize_uploads_are_refused_before_decoding() {
+    let avatar_limit = 2 * 1024 * 1024;
+    let bytes = vec![0u8; avatar_limit + 1];
+    assert!(matches!(Images::process(ImageKind::Avatar, &bytes), Err(ImageError::TooLargeError(limit)) if limit == avatar_limit));
+    let bytes = vec![0u8; Images::MAX_UPLOAD_BYTES + 1];
+    assert!(matches!(Images::process(ImageKind::Listing, &bytes), Err(ImageError::TooLargeError(Images::MAX_UPLOAD_BYTES))));
+}
+
+#[test]
+fn oversize_dimensions_are_refused() {
+    // Small on the wire, but wider than any image the decoder accepts.
+    let bytes = png(8001, 1);
+    assert!(bytes.len() < Images::MAX_UPLOAD_BYTES);
+    assert!(matches!(Images::process(ImageKind::Listing, &bytes), Err(ImageError::DecodeError(_))));
+}
+
+#[test]
+fn unsupported_formats_are_refused() {
+    assert!(matches!(Images::process(ImageKind::Listing, b"GIF89a\x01\x00\x01\x00"), Err(ImageError::UnsupportedTypeError)));
+    assert!(matches!(Images::process(ImageKind::Listing, b"not an image"), Err(ImageError::UnsupportedTypeError)));
+}
+
+#[test]
+fn small_images_get_one_size_in_both_formats() {
+    let variants = Images::process(ImageKind::Listing, &png(64, 48)).unwrap();
+    let formats = variants.iter().map(|v| (v.name.as_str(), v.content_type, v.width, v.height)).collect::<Vec<_>>();
+    assert_eq!(formats, [("original", "image/png", 64, 48), ("original", "image/webp", 64, 48)]);
+}
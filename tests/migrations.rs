@@ -0,0 +1,32 @@
+#![cfg(feature = "server")]
+
+use freelance_test::backend::migrations::runner::Runner::{self, Command, MigrationError};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn migrate_down_needs_a_version() {
+    assert_eq!(Runner::parse_cli(&args(&["down", "3"])).unwrap(), Command::Down(3));
+    assert_eq!(Runner::parse_cli(&args(&["down", "0"])).unwrap(), Command::Down(0));
+    assert!(matches!(Runner::parse_cli(&args(&["down"])), Err(MigrationError::UsageError(_))));
+    assert!(matches!(Runner::parse_cli(&args(&["down", "abc"])), Err(MigrationError::UsageError(_))));
+}
+
+#[test]
+fn unknown_commands_are_errors() {
+    assert_eq!(Runner::parse_cli(&[]).unwrap(), Command::Up);
+    assert_eq!(Runner::parse_cli(&args(&["status"])).unwrap(), Command::Status);
+    assert!(matches!(Runner::parse_cli(&args(&["sideways"])), Err(MigrationError::UsageError(_))));
+}
+
+#[test]
+fn stored_phone_numbers_move_to_e164_and_back() {
+    use freelance_test::backend::migrations::versions::Versions::{e164_phone_number, legacy_phone_number};
+
+    assert_eq!(e164_phone_number("+1650-253-0000").as_deref(), Some("+16502530000"));
+    assert_eq!(legacy_phone_number("+16502530000").as_deref(), Some("+1650-253-0000"));
+    assert_eq!(e164_phone_number("+255712345678"), None);
+    assert_eq!(legacy_phone_number("+255712345678"), None);
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "server")]
+
+use axum::{body::Body, http::{header, HeaderMap, HeaderValue, Request, StatusCode}, Router};
+use freelance_test::backend::monitoring::Monitoring;
+use tower::ServiceExt;
+
+async fn status(router: &Router, uri: &str, authorization: Option<&str>) -> StatusCode {
+    let mut request = Request::builder().uri(uri);
+    if let Some(authorization) = authorization {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn metrics_are_not_served_without_a_token() {
+    let router = Monitoring::router_with_metrics_token(None);
+    assert_eq!(status(&router, "/metrics", None).await, StatusCode::NOT_FOUND);
+    assert_eq!(status(&router, "/metrics", Some("Bearer anything")).await, StatusCode::NOT_FOUND);
+    assert_eq!(status(&router, "/healthz", None).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn metrics_need_the_token() {
+    let router = Monitoring::router_with_metrics_token(Some(String::from("s3cret")));
+    for authorization in [None, Some("Bearer wrong"), Some("Bearer s3cre"), Some("Bearer s3cret2"), Some("s3cret"), Some("Basic s3cret")] {
+        assert_eq!(status(&router, "/metrics", authorization).await, StatusCode::UNAUTHORIZED, "{:?}", authorization);
+    }
+    // Past the token, tests have no recorder installed, so there is nothing to render.
+    assert_eq!(status(&router, "/metrics", Some("Bearer s3cret")).await, StatusCode::NOT_FOUND);
+    assert_eq!(status(&router, "/healthz", None).await, StatusCode::OK);
+}
+
+#[test]
+fn only_the_exact_bearer_token_is_accepted() {
+    let headers = |value: &str| HeaderMap::from_iter([(header::AUTHORIZATION, HeaderValue::from_str(value).unwrap())]);
+    assert!(Monitoring::bears_token(&headers("Bearer s3cret"), "s3cret"));
+    assert!(!Monitoring::bears_token(&headers("Bearer S3CRET"), "s3cret"));
+    assert!(!Monitoring::bears_token(&headers("Bearer "), "s3cret"));
+    assert!(!Monitoring::bears_token(&HeaderMap::new(), "s3cret"));
+}
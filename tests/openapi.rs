@@ -0,0 +1,121 @@
+#![cfg(feature = "server")]
+
+use axum::{body::Body, http::{Method, Request, StatusCode}};
+use freelance_test::backend::api::routes::Routes;
+use utoipa::openapi::Deprecated;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use tower::ServiceExt;
+use tower_sessions::{MemoryStore, SessionManagerLayer};
+
+const OBJECT_ID: &str = "000000000000000000000000";
+
+#[test]
+fn committed_document_matches_routes() {
+    let generated = Routes::openapi().to_pretty_json().expect("OpenAPI document serializes");
+    let committed = include_str!("../docs/openapi.json");
+    assert!(
+        generated.trim() == committed.trim(),
+        "docs/openapi.json is out of date; regenerate it with `cargo run --features server --bin freelance-admin -- openapi > docs/openapi.json`"
+    );
+}
+
+/// Every documented operation must reach a handler: an unrouted path answers 404 and a wrong method 405.
+/// Requests carry no session, so handlers stop at authentication or body parsing without touching the database.
+#[tokio::test]
+async fn documented_operations_are_routed() {
+    let (router, openapi) = Routes::api_router().split_for_parts();
+    let router = router.layer(SessionManagerLayer::new(MemoryStore::default()));
+    for (path, item) in openapi.paths.paths {
+        let operations = [
+            (Method::GET, item.get.is_some()),
+            (Method::POST, item.post.is_some()),
+            (Method::PUT, item.put.is_some()),
+            (Method::DELETE, item.delete.is_some()),
+            (Method::PATCH, item.patch.is_some()),
+        ];
+        let uri = path.replace("{id}", OBJECT_ID).replace("{kind}", "avatar");
+        for (method, _) in operations.into_iter().filter(|(_, documented)| *documented) {
+            let request = Request::builder().method(method.clone()).uri(&uri).body(Body::empty()).unwrap();
+            let status = router.clone().oneshot(request).await.unwrap().status();
+            assert!(
+                status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED,
+                "{} {} is documented but not routed ({})", method, path, status
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn unversioned_paths_announce_their_sunset() {
+    let router = Routes::documented_api_router().layer(SessionManagerLayer::new(MemoryStore::default()));
+    let request = Request::builder().uri("/api/is_authenticated").body(Body::empty()).unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert!(response.headers().contains_key("deprecation"));
+    assert!(response.headers().contains_key("sunset"));
+
+    let request = Request::builder().uri("/api/v1/is_authenticated").body(Body::empty()).unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert!(!response.headers().contains_key("deprecation"));
+}
+
+#[test]
+fn every_operation_is_documented_once() {
+    let openapi = Routes::openapi();
+    let operations = openapi.paths.paths.values()
+        .flat_map(|item| [item.get.as_ref(), item.post.as_ref(), item.put.as_ref(), item.delete.as_ref(), item.patch.as_ref()])
+        .flatten()
+        .collect::<Vec<_>>();
+    let mut ids = operations.iter().filter_map(|operation| operation.operation_id.clone()).collect::<Vec<String>>();
+    assert_eq!(ids.len(), operations.len(), "every operation has an operation id");
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), operations.len(), "operation ids are unique");
+}
+
+#[test]
+fn unversioned_aliases_are_documented_as_deprecated() {
+    let openapi = Routes::openapi();
+    let alias = openapi.paths.paths["/api/is_authenticated"].get.as_ref().expect("alias documented");
+    assert!(matches!(alias.deprecated, Some(Deprecated::True)));
+    assert_eq!(alias.operation_id.as_deref(), Some("is_authenticated_unversioned"));
+    let current = openapi.paths.paths["/api/v1/is_authenticated"].get.as_ref().unwrap();
+    assert!(current.deprecated.is_none());
+}
+
+mod old {
+    #[utoipa::path(get, path = "/greeting", responses((status = 200, body = String)))]
+    pub async fn greeting() -> &'static str {
+        "old"
+    }
+}
+
+mod new {
+    #[utoipa::path(get, path = "/greeting", responses((status = 200, body = String)))]
+    pub async fn greeting() -> &'static str {
+        "new"
+    }
+}
+
+/// An older version keeps its own handlers next to its successor, the way v1 would once a v2 ships.
+#[tokio::test]
+async fn older_versions_keep_their_handlers() {
+    let api = OpenApiRouter::new()
+        .merge(Routes::version("v0", Routes::deprecate(OpenApiRouter::new().routes(routes!(old::greeting)), Routes::unversioned_deprecation())))
+        .merge(Routes::version(Routes::CURRENT_VERSION, OpenApiRouter::new().routes(routes!(new::greeting))));
+    let (router, openapi) = api.split_for_parts();
+
+    for (uri, body, deprecated) in [("/api/v0/greeting", "old", true), ("/api/v1/greeting", "new", false)] {
+        let response = router.clone().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        assert_eq!(response.headers().contains_key("deprecation"), deprecated, "{}", uri);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&bytes[..], body.as_bytes(), "{}", uri);
+    }
+
+    let old = openapi.paths.paths["/api/v0/greeting"].get.as_ref().unwrap();
+    assert!(matches!(old.deprecated, Some(Deprecated::True)));
+    assert_eq!(old.operation_id.as_deref(), Some("greeting_v0"));
+    let new = openapi.paths.paths["/api/v1/greeting"].get.as_ref().unwrap();
+    assert!(new.deprecated.is_none());
+    assert_eq!(new.operation_id.as_deref(), Some("greeting"));
+}
@@ -0,0 +1,13 @@
+#![cfg(feature = "server")]
+
+use freelance_test::backend::api::routes::Routes;
+
+/// The only test of its binary, as the cookie attributes are read from the environment once per process.
+#[test]
+fn the_session_scheme_uses_the_configured_cookie_name() {
+    std::env::set_var("SESSION_COOKIE_NAME", "freelance_session");
+    let openapi = serde_json::to_value(Routes::openapi()).unwrap();
+    let scheme = &openapi["components"]["securitySchemes"]["session"];
+    assert_eq!(scheme["in"], "cookie", "{}", scheme);
+    assert_eq!(scheme["name"], "freelance_session", "{}", scheme);
+}
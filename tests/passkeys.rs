@@ -0,0 +1,181 @@
+#![cfg(feature = "server")]
+
+use axum::{body::Body, http::{header, Method, Request, StatusCode}, Router};
+use bson::oid::ObjectId;
+use chrono::Utc;
+use freelance_test::backend::{
+    api::routes::Routes,
+    forms::{AccountStatus, Forms::UserRole},
+    mongo_models::Docs::{BaseUser, PasskeyCredential},
+    passkeys::Passkeys::{self, PasskeyError},
+    utils::server_utils::encrypt,
+};
+use tower::ServiceExt;
+use tower_sessions::{MemoryStore, SessionManagerLayer};
+use webauthn_authenticator_rs::{prelude::Url, softpasskey::SoftPasskey, WebauthnAuthenticator};
+use webauthn_rs::prelude::{Passkey, PublicKeyCredential, RequestChallengeResponse, Webauthn, WebauthnError};
+use webauthn_rs_proto::AllowCredentials;
+
+const ORIGIN: &str = "http://localhost:8080";
+
+fn relying_party() -> Webauthn {
+    Passkeys::relying_party("localhost", ORIGIN, "Freelancer").unwrap()
+}
+
+fn member() -> BaseUser {
+    std::env::set_var("ENCRYPTION_KEY", "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff");
+    BaseUser {
+        id: Some(ObjectId::new()),
+        password: encrypt("+255712345678"),
+        role: UserRole::Seller,
+        status: AccountStatus::Active,
+        created: Utc::now(),
+        modified: Utc::now(),
+    }
+}
+
+/// A software authenticator holding a passkey registered for `user`, the way a browser would. It cannot keep
+/// discoverable credentials, so it is not asked for one.
+fn register(webauthn: &Webauthn, authenticator: &mut WebauthnAuthenticator<SoftPasskey>, user: &BaseUser) -> Passkey {
+    let (mut challenge, state) = Passkeys::start_registration(webauthn, user, &[]).unwrap();
+    if let Some(selection) = challenge.public_key.authenticator_selection.as_mut() {
+        selection.require_resident_key = false;
+    }
+    let credential = authenticator.do_registration(Url::parse(ORIGIN).unwrap(), challenge).unwrap();
+    Passkeys::finish_registration(webauthn, &credential, &state).unwrap()
+}
+
+/// Answers a sign-in challenge with `passkey`. A browser finds the discoverable credential by itself; the
+/// software authenticator has to be told which one to use.
+fn assert(authenticator: &mut WebauthnAuthenticator<SoftPasskey>, origin: &str, mut challenge: RequestChallengeResponse, passkey: &Passkey) -> PublicKeyCredential {
+    challenge.public_key.allow_credentials = vec![AllowCredentials {
+        type_: String::from("public-key"),
+        id: passkey.cred_id().clone().into(),
+        transports: None,
+    }];
+    authenticator.do_authentication(Url::parse(origin).unwrap(), challenge).unwrap()
+}
+
+#[test]
+fn a_registered_passkey_signs_in() {
+    let webauthn = relying_party();
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+    let user = member();
+    let passkey = register(&webauthn, &mut authenticator, &user);
+
+    let (challenge, state) = Passkeys::start_authentication(&webauthn).unwrap();
+    assert!(challenge.mediation.is_none());
+    let credential = assert(&mut authenticator, ORIGIN, challenge, &passkey);
+    assert_eq!(Passkeys::encode_credential_id(credential.get_credential_id()), Passkeys::encode_credential_id(passkey.cred_id().as_ref()));
+
+    let result = Passkeys::finish_authentication(&webauthn, &credential, state, &passkey).unwrap();
+    assert!(result.user_verified());
+    assert!(result.counter() > 0);
+}
+
+#[test]
+fn registration_asks_for_a_discoverable_credential_tied_to_the_member() {
+    let webauthn = relying_party();
+    let user = member();
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+    let existing = register(&webauthn, &mut authenticator, &user);
+
+    let (challenge, _) = Passkeys::start_registration(&webauthn, &user, std::slice::from_ref(&existing)).unwrap();
+    let selection = challenge.public_key.authenticator_selection.unwrap();
+    assert!(selection.require_resident_key);
+    assert_eq!(challenge.public_key.user.id.as_ref(), Passkeys::user_handle(&user.id.unwrap()).as_bytes());
+    let excluded = challenge.public_key.exclude_credentials.unwrap();
+    assert_eq!(excluded.len(), 1);
+    assert_eq!(excluded[0].id.as_ref(), existing.cred_id().as_ref());
+}
+
+#[test]
+fn a_counter_that_did_not_increase_is_rejected() {
+    let webauthn = relying_party();
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+    let mut passkey = register(&webauthn, &mut authenticator, &member());
+
+    let (first_challenge, first_state) = Passkeys::start_authentication(&webauthn).unwrap();
+    let first = assert(&mut authenticator, ORIGIN, first_challenge, &passkey);
+    let (second_challenge, second_state) = Passkeys::start_authentication(&webauthn).unwrap();
+    let second = assert(&mut authenticator, ORIGIN, second_challenge, &passkey);
+
+    let result = Passkeys::finish_authentication(&webauthn, &second, second_state, &passkey).unwrap();
+    passkey.update_credential(&result);
+    // The earlier assertion now carries a lower counter than the one stored, as a cloned authenticator's would.
+    let error = Passkeys::finish_authentication(&webauthn, &first, first_state, &passkey).unwrap_err();
+    assert!(matches!(error, PasskeyError::Rejected(WebauthnError::CredentialPossibleCompromise)), "{:?}", error);
+}
+
+#[test]
+fn an_assertion_for_another_origin_or_passkey_is_rejected() {
+    let webauthn = relying_party();
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+    let passkey = register(&webauthn, &mut authenticator, &member());
+    let mut other_authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+    let other_passkey = register(&webauthn, &mut other_authenticator, &member());
+
+    let (challenge, state) = Passkeys::start_authentication(&webauthn).unwrap();
+    let phished = assert(&mut authenticator, "http://localhost:9999", challenge, &passkey);
+    assert!(matches!(Passkeys::finish_authentication(&webauthn, &phished, state, &passkey), Err(PasskeyError::Rejected(_))));
+
+    let (challenge, state) = Passkeys::start_authentication(&webauthn).unwrap();
+    let credential = assert(&mut other_authenticator, ORIGIN, challenge, &other_passkey);
+    assert!(matches!(Passkeys::finish_authentication(&webauthn, &credential, state, &passkey), Err(PasskeyError::UnknownCredential)));
+}
+
+#[test]
+fn stored_passkeys_keep_their_counter() {
+    let webauthn = relying_party();
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+    let user = member();
+    let mut passkey = register(&webauthn, &mut authenticator, &user);
+    let (challenge, state) = Passkeys::start_authentication(&webauthn).unwrap();
+    let credential = assert(&mut authenticator, ORIGIN, challenge, &passkey);
+    let result = Passkeys::finish_authentication(&webauthn, &credential, state, &passkey).unwrap();
+    passkey.update_credential(&result);
+
+    let stored = PasskeyCredential {
+        id: Some(ObjectId::new()),
+        user: user.id.unwrap(),
+        credential_id: Passkeys::encode_credential_id(passkey.cred_id().as_ref()),
+        name: String::from("Phone"),
+        passkey,
+        created: Utc::now(),
+        last_used: None,
+    };
+    let document = bson::to_document(&stored).unwrap();
+    // `Passkeys::record_use` only moves this field forwards.
+    let counter = document.get_document("passkey").unwrap().get_document("cred").unwrap().get("counter").unwrap();
+    assert_eq!(counter.as_i64().or(counter.as_i32().map(i64::from)), Some(i64::from(result.counter())));
+    let loaded: PasskeyCredential = bson::from_document(document).unwrap();
+    assert_eq!(loaded.passkey.cred_id(), stored.passkey.cred_id());
+}
+
+fn app() -> Router {
+    let (router, _) = Routes::api_router().split_for_parts();
+    router.layer(SessionManagerLayer::new(MemoryStore::default()))
+}
+
+fn request(method: Method, uri: &str) -> Request<Body> {
+    Request::builder().method(method).uri(uri).header(header::CONTENT_TYPE, "application/json").body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn managing_passkeys_needs_a_login() {
+    for (method, uri) in [(Method::POST, "/api/v1/passkeys/register/start"), (Method::GET, "/api/v1/passkeys")] {
+        let response = app().oneshot(request(method, uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn passkey_login_starts_without_a_phone_number() {
+    let response = app().oneshot(request(Method::POST, "/api/v1/login/passkey/start")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key(header::SET_COOKIE));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let challenge: RequestChallengeResponse = serde_json::from_slice(&body).unwrap();
+    assert!(challenge.public_key.allow_credentials.is_empty());
+    assert!(challenge.mediation.is_none());
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "server")]
+
+use axum::{body::Body, http::{header, Method, Request, StatusCode}};
+use bson::oid::ObjectId;
+use chrono::{Duration, Utc};
+use freelance_test::backend::{
+    api::{api::Api::auth_error_response, phone_change::PhoneChangeApi, routes::Routes},
+    forms::{ApiError, AuditAction, AuthError, PhoneChangeForm},
+    phone_change::PhoneChange::{self, PhoneChangeError},
+};
+use tower::ServiceExt;
+use tower_sessions::{MemoryStore, SessionManagerLayer};
+
+fn request(uri: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[test]
+fn only_the_code_sent_matches_its_hash() {
+    let hash = PhoneChange::hash_code("042917");
+    assert!(PhoneChange::code_matches(&hash, "042917"));
+    assert!(PhoneChange::code_matches(&hash, " 042917 "));
+    assert!(!PhoneChange::code_matches(&hash, "042918"));
+    assert!(!PhoneChange::code_matches(&hash, "42917"));
+}
+
+#[test]
+fn recovery_codes_are_not_taken_for_sent_codes() {
+    assert!(PhoneChange::is_code("123456"));
+    assert!(!PhoneChange::is_code("k7mqd-x3wpa"));
+    assert!(!PhoneChange::is_code("12345a"));
+}
+
+#[test]
+fn changes_and_recoveries_are_audited() {
+    assert!(AuditAction::ALL.contains(&AuditAction::PhoneNumberChanged));
+    assert!(AuditAction::ALL.contains(&AuditAction::PhoneNumberRecovered));
+}
+
+/// Requests carry no session, so they stop at authentication without touching the database or sending codes.
+#[tokio::test]
+async fn phone_number_changes_need_a_login() {
+    let (router, _) = Routes::api_router().split_for_parts();
+    let router = router.layer(SessionManagerLayer::new(MemoryStore::default()));
+    let requests = [
+        ("/api/v1/account/phone-number", r#"{"phone_number":"+255712345678"}"#),
+        ("/api/v1/account/phone-number/confirm", r#"{"current_code":"123456","new_code":"654321"}"#),
+        ("/api/v1/admin/users/000000000000000000000000/phone-number", r#"{"phone_number":"+255712345678","reason":"ID checked in person"}"#),
+    ];
+    for (uri, body) in requests {
+        let response = router.clone().oneshot(request(uri, body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ApiError = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.status, StatusCode::UNAUTHORIZED.as_u16());
+    }
+}
+
+#[tokio::test]
+async fn availability_checks_need_a_login() {
+    let session = tower_sessions::Session::new(None, std::sync::Arc::new(MemoryStore::default()), None);
+    let form = PhoneChangeForm { phone_number: "+255712345678".to_string() };
+    assert_eq!(PhoneChangeApi::is_available(&session, form).await, Err(AuthError::Unauthenticated));
+    let form = PhoneChangeForm { phone_number: "+25571".to_string() };
+    assert_eq!(PhoneChangeApi::is_available(&session, form).await, Err(AuthError::InvalidForm));
+}
+
+#[test]
+fn a_second_immediate_start_is_refused() {
+    let user = ObjectId::new();
+    let now = Utc::now();
+    assert!(PhoneChange::reserve_start(&user, now).is_ok());
+    let next = now + Duration::seconds(PhoneChange::START_COOLDOWN_SECONDS);
+    assert!(matches!(PhoneChange::reserve_start(&user, now), Err(PhoneChangeError::TooSoon(at)) if at == next));
+    assert!(PhoneChange::reserve_start(&ObjectId::new(), now).is_ok());
+    assert!(PhoneChange::reserve_start(&user, next).is_ok());
+}
+
+#[test]
+fn starts_are_capped_per_hour() {
+    let user = ObjectId::new();
+    let first = Utc::now();
+    let spaced = |n: i64| first + Duration::seconds(n * PhoneChange::START_COOLDOWN_SECONDS);
+    for n in 0..PhoneChange::MAX_STARTS_PER_HOUR as i64 {
+        assert!(PhoneChange::reserve_start(&user, spaced(n)).is_ok());
+    }
+    let later = spaced(PhoneChange::MAX_STARTS_PER_HOUR as i64);
+    assert!(matches!(PhoneChange::reserve_start(&user, later), Err(PhoneChangeError::TooSoon(at)) if at == first + Duration::hours(1)));
+    assert!(PhoneChange::reserve_start(&user, first + Duration::hours(1) + Duration::seconds(1)).is_ok());
+}
+
+#[test]
+fn refused_starts_answer_too_many_requests() {
+    let response = auth_error_response(AuthError::TooManyPhoneChanges);
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "server")]
+
+use freelance_test::backend::{forms::AuthUserForm, phone::{self, NumberKind, PhoneNumberError}, users::Users};
+use phonenumber::country::Id;
+use validator::Validate;
+
+#[test]
+fn numbers_typed_any_way_normalize_to_the_same_e164() {
+    for input in ["0712 345 678", "712345678", "+255 712 345 678", "+255712345678", "(+255) 712-345-678"] {
+        assert_eq!(phone::to_e164(input, Id::TZ).unwrap(), "+255712345678", "{input}");
+    }
+    assert_eq!(phone::to_e164("0712 345 678", Id::KE).unwrap(), "+254712345678");
+    assert_eq!(phone::to_e164("0772 123 456", Id::UG).unwrap(), "+256772123456");
+    assert_eq!(phone::to_e164("+250 788 123 456", Id::TZ).unwrap(), "+250788123456");
+    assert_eq!(phone::to_e164("+1 650-253-0000", Id::TZ).unwrap(), "+16502530000");
+    assert_eq!(phone::to_e164("+44 20.7946.0958", Id::TZ).unwrap(), "+442079460958");
+}
+
+#[test]
+fn server_normalization_matches_what_the_client_sends() {
+    let sent = phone::to_e164("0754 123 456", Id::TZ).unwrap();
+    assert_eq!(Users::normalize_phone_number(&sent), sent);
+    assert_eq!(Users::normalize_phone_number("+255 754 123 456"), sent);
+}
+
+#[test]
+fn invalid_numbers_are_rejected() {
+    assert_eq!(phone::to_e164("hello", Id::TZ), Err(PhoneNumberError::Unparseable));
+    assert_eq!(phone::to_e164("+255 12", Id::TZ), Err(PhoneNumberError::Invalid));
+    assert_eq!(phone::validate_phone_number("+255 22 211 1234"), Err(PhoneNumberError::NotMobile));
+    assert!(AuthUserForm::new("+25571234".to_string()).validate().is_err());
+    assert!(AuthUserForm::new("+254712345678".to_string()).validate().is_ok());
+    assert!(AuthUserForm::new("+255799012345".to_string()).validate().is_ok());
+}
+
+#[test]
+fn country_code_typed_selects_the_country() {
+    assert_eq!(phone::country_of("+254 712 345 678"), Some(Id::KE));
+    assert_eq!(phone::country_of("0712 345 678"), None);
+    assert_eq!(phone::with_country("+255 712 345 678", Id::TZ, Id::KE), "+254 712 345678");
+}
+
+#[test]
+fn carrier_and_kind_come_from_the_number() {
+    let vodacom = phone::details("+255 754 123 456", Id::TZ).unwrap();
+    assert_eq!(vodacom.kind, NumberKind::Mobile);
+    assert_eq!(vodacom.carrier.as_deref(), Some("Vodacom"));
+    assert_eq!(vodacom.country.as_deref(), Some("TZ"));
+    let safaricom = phone::details("0741 123 456", Id::KE).unwrap();
+    assert_eq!(safaricom.carrier.as_deref(), Some("Safaricom"));
+    let airtel = phone::details("0731 123 456", Id::KE).unwrap();
+    assert_eq!(airtel.carrier.as_deref(), Some("Airtel"));
+    let landline = phone::details("+255 22 211 1234", Id::TZ).unwrap();
+    assert_eq!(landline.kind, NumberKind::FixedLine);
+    assert_eq!(landline.carrier, None);
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "server")]
+
+use std::sync::Arc;
+
+use axum::{body::Body, http::{header, HeaderMap, Request}, middleware, routing::get, Extension, Router};
+use freelance_test::backend::{api::routes::Routes, security_headers::SecurityHeaders::{self, ContentSecurityPolicy, SecurityConfig}};
+use tower::ServiceExt;
+
+fn config(report_only: bool) -> SecurityConfig {
+    SecurityConfig { policy: ContentSecurityPolicy::app(), report_only, hsts_max_age: 3600 }
+}
+
+fn app(config: SecurityConfig) -> Router {
+    Router::new()
+        .route("/page", get(|| async { "page" }))
+        .route("/embed", get(|| async {
+            (Extension(ContentSecurityPolicy::app().with("frame-ancestors", &["https://partner.example"])), "embed")
+        }))
+        .route("/framed", get(|| async { ([(header::X_FRAME_OPTIONS, "SAMEORIGIN")], "framed") }))
+        .layer(middleware::from_fn_with_state(Arc::new(config), SecurityHeaders::apply_with))
+}
+
+async fn headers(app: Router, uri: &str) -> HeaderMap {
+    app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap().headers().clone()
+}
+
+#[tokio::test]
+async fn every_response_gets_the_default_headers() {
+    let headers = headers(app(config(false)), "/page").await;
+    assert_eq!(headers[header::CONTENT_SECURITY_POLICY].to_str().unwrap(), ContentSecurityPolicy::app().header_value());
+    assert!(!headers.contains_key(header::CONTENT_SECURITY_POLICY_REPORT_ONLY));
+    assert_eq!(headers[header::STRICT_TRANSPORT_SECURITY], "max-age=3600; includeSubDomains");
+    assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+    assert_eq!(headers[header::REFERRER_POLICY], "strict-origin-when-cross-origin");
+    assert_eq!(headers[header::X_FRAME_OPTIONS], "DENY");
+    assert!(headers.contains_key("reporting-endpoints"));
+}
+
+#[tokio::test]
+async fn routes_override_the_policy_and_headers_they_set() {
+    let policy = headers(app(config(false)), "/embed").await[header::CONTENT_SECURITY_POLICY].to_str().unwrap().to_string();
+    assert!(policy.contains("frame-ancestors https://partner.example"), "{}", policy);
+    assert!(!policy.contains("frame-ancestors 'none'"), "{}", policy);
+
+    let framed = headers(app(config(false)), "/framed").await;
+    assert_eq!(framed[header::X_FRAME_OPTIONS], "SAMEORIGIN");
+}
+
+#[tokio::test]
+async fn report_only_mode_blocks_nothing() {
+    let headers = headers(app(config(true)), "/page").await;
+    assert!(!headers.contains_key(header::CONTENT_SECURITY_POLICY));
+    let policy = headers[header::CONTENT_SECURITY_POLICY_REPORT_ONLY].to_str().unwrap();
+    assert!(policy.contains("report-uri /api/csp-report"), "{}", policy);
+}
+
+#[tokio::test]
+async fn hsts_can_be_turned_off() {
+    let headers = headers(app(SecurityConfig { hsts_max_age: 0, ..config(false) }), "/page").await;
+    assert!(!headers.contains_key(header::STRICT_TRANSPORT_SECURITY));
+}
+
+/// The only test here reading the environment's configuration, which is read once per process.
+#[tokio::test]
+async fn docs_page_keeps_the_configured_connect_origins() {
+    std::env::set_var("CSP_CONNECT_SRC", "https://api.example.com");
+    let response = Routes::documented_api_router()
+        .oneshot(Request::builder().uri("/api/docs").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let policy = response.extensions().get::<ContentSecurityPolicy>().unwrap().header_value();
+    assert!(policy.contains("connect-src 'self' https://api.example.com"), "{}", policy);
+    assert!(policy.contains("https://unpkg.com"), "{}", policy);
+}
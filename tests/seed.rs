@@ -0,0 +1,29 @@
+#![cfg(feature = "server")]
+
+use freelance_test::backend::seed::Seed::{self, SeedError, SeedOptions, MAX_MEMBERS};
+
+#[test]
+fn seeded_ids_are_stable_for_a_seed() {
+    assert_eq!(Seed::seeded_id(42, "listing", 3), Seed::seeded_id(42, "listing", 3));
+    assert_ne!(Seed::seeded_id(42, "listing", 3), Seed::seeded_id(42, "listing", 4));
+    assert_ne!(Seed::seeded_id(42, "listing", 3), Seed::seeded_id(42, "booking", 3));
+}
+
+#[test]
+fn seeded_ids_depend_on_the_seed() {
+    assert_ne!(Seed::seeded_id(42, "seller", 0), Seed::seeded_id(7, "seller", 0));
+}
+
+#[test]
+fn seeded_phone_numbers_differ_between_seeds_and_members() {
+    let number = Seed::seeded_phone_number("+2557990", 42, 0);
+    assert_eq!(number, "+255799042001");
+    assert_ne!(number, Seed::seeded_phone_number("+2557990", 7, 0));
+    assert_ne!(number, Seed::seeded_phone_number("+2557990", 42, 1));
+}
+
+#[tokio::test]
+async fn run_refuses_more_members_than_phone_numbers() {
+    let options = SeedOptions { buyers: MAX_MEMBERS + 1, ..SeedOptions::default() };
+    assert!(matches!(Seed::run(&options).await, Err(SeedError::LimitError(MAX_MEMBERS))));
+}
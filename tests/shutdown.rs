@@ -0,0 +1,21 @@
+#![cfg(feature = "server")]
+
+use std::time::{Duration, Instant};
+
+use freelance_test::backend::shutdown::Shutdown;
+
+/// The server keeps accepting connections for the whole readiness delay, so the drain only starts after it, even
+/// when the delay is the longer of the two.
+#[tokio::test]
+async fn drain_deadline_starts_after_the_readiness_delay() {
+    let (delay, drain) = (Duration::from_millis(300), Duration::from_millis(100));
+    let deadline = tokio::spawn(Shutdown::drain_deadline_after(delay, drain));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!deadline.is_finished(), "the deadline passed before shutdown began");
+
+    let began = Instant::now();
+    Shutdown::begin();
+    assert!(Shutdown::is_shutting_down());
+    deadline.await.unwrap();
+    assert!(began.elapsed() >= delay + drain);
+}
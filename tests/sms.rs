@@ -0,0 +1,50 @@
+#![cfg(feature = "server")]
+
+use std::sync::{Arc, Mutex};
+
+use axum::{extract::State, http::{HeaderMap, StatusCode}, routing::post, Json, Router};
+use freelance_test::backend::sms::Sms::{HttpGateway, SmsError, SmsGateway};
+use serde_json::{json, Value};
+
+type Received = Arc<Mutex<Vec<(Option<String>, Value)>>>;
+
+/// A provider on a local port answering `status`, with the requests it received.
+async fn provider(status: StatusCode) -> (String, Received) {
+    let received = Received::default();
+    let app = Router::new()
+        .route("/messages", post(move |State(received): State<Received>, headers: HeaderMap, Json(body): Json<Value>| async move {
+            let authorization = headers.get("authorization").and_then(|v| v.to_str().ok()).map(str::to_string);
+            received.lock().unwrap().push((authorization, body));
+            status
+        }))
+        .with_state(Arc::clone(&received));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{address}/messages"), received)
+}
+
+#[tokio::test]
+async fn http_gateway_posts_the_message_to_the_provider() {
+    let (url, received) = provider(StatusCode::ACCEPTED).await;
+    let gateway = HttpGateway::new(url, "secret-token", "Freelancer").unwrap();
+    gateway.send("+255712345678", "123456 is your code").await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0.as_deref(), Some("Bearer secret-token"));
+    assert_eq!(received[0].1, json!({ "from": "Freelancer", "to": "+255712345678", "text": "123456 is your code" }));
+}
+
+#[tokio::test]
+async fn refused_and_unreachable_messages_are_errors() {
+    let (url, _) = provider(StatusCode::PAYMENT_REQUIRED).await;
+    let refused = HttpGateway::new(url, "", "Freelancer").unwrap().send("+255712345678", "hello").await;
+    assert!(matches!(refused, Err(SmsError::Rejected(402))), "{:?}", refused);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed = format!("http://{}/messages", listener.local_addr().unwrap());
+    drop(listener);
+    let unreachable = HttpGateway::new(closed, "", "Freelancer").unwrap().send("+255712345678", "hello").await;
+    assert!(matches!(unreachable, Err(SmsError::Unreachable(_))), "{:?}", unreachable);
+}
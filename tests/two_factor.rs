@@ -0,0 +1,182 @@
+#![cfg(feature = "server")]
+
+use axum::{body::Body, http::{header, Method, Request, StatusCode}, routing::post, Router};
+use bson::oid::ObjectId;
+use chrono::{Duration, Utc};
+use freelance_test::backend::{
+    api::{api::Api::SessionUser, routes::Routes, two_factor::TwoFactorApi::{self, PendingLogin}},
+    forms::{AccountStatus, ApiError, TwoFactorStep, Forms::UserRole},
+    mongo_models::Docs::{BaseUser, TwoFactorCredential},
+    two_factor::TwoFactor,
+};
+use totp_rs::{Algorithm, Secret, TOTP};
+use tower::ServiceExt;
+use tower_sessions::{MemoryStore, Session, SessionManagerLayer};
+
+fn admin() -> BaseUser {
+    BaseUser {
+        id: Some(ObjectId::new()),
+        password: String::from("c2VjcmV0LWNpcGhlcnRleHQ="),
+        role: UserRole::Admin,
+        status: AccountStatus::Active,
+        created: Utc::now(),
+        modified: Utc::now(),
+    }
+}
+
+/// The API along with routes that leave the session halfway through a login, the way `/login` does once the
+/// phone number is accepted, and the way it used to for admins who had yet to set up two-factor authentication.
+fn app() -> Router {
+    let (router, _) = Routes::api_router().split_for_parts();
+    router
+        .route("/test/pending-login", post(|session: Session| async move {
+            TwoFactorApi::begin(&session, &admin(), TwoFactorStep::Verify).await.unwrap();
+        }))
+        .route("/test/pending-enrollment", post(|session: Session| async move {
+            TwoFactorApi::begin(&session, &admin(), TwoFactorStep::Enroll).await.unwrap();
+        }))
+        .layer(SessionManagerLayer::new(MemoryStore::default()))
+}
+
+fn request(method: Method, uri: &str, cookie: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder().method(method).uri(uri).header(header::CONTENT_TYPE, "application/json");
+    if let Some(cookie) = cookie {
+        request = request.header(header::COOKIE, cookie);
+    }
+    request.body(Body::from(r#"{"code":"123456"}"#)).unwrap()
+}
+
+#[test]
+fn admins_must_use_two_factor() {
+    assert!(TwoFactor::required_for(&UserRole::Admin));
+    assert!(!TwoFactor::required_for(&UserRole::Seller));
+    assert!(TwoFactor::available_for(&UserRole::Seller));
+    assert!(!TwoFactor::available_for(&UserRole::Buyer));
+}
+
+#[tokio::test]
+async fn a_pending_login_grants_no_access() {
+    let app = app();
+    let response = app.clone().oneshot(request(Method::POST, "/test/pending-login", None)).await.unwrap();
+    let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap().split(';').next().unwrap().to_string();
+
+    for uri in ["/api/v1/is_authenticated", "/api/v1/admin/users"] {
+        let status = app.clone().oneshot(request(Method::GET, uri, Some(&cookie))).await.unwrap().status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", uri);
+    }
+}
+
+async fn session_cookie(app: &Router, uri: &str) -> String {
+    let response = app.clone().oneshot(request(Method::POST, uri, None)).await.unwrap();
+    response.headers()[header::SET_COOKIE].to_str().unwrap().split(';').next().unwrap().to_string()
+}
+
+/// Knowing an admin's phone number must not be enough to pick their second factor.
+#[tokio::test]
+async fn a_pending_login_cannot_set_up_two_factor() {
+    let app = app();
+    for pending in ["/test/pending-login", "/test/pending-enrollment"] {
+        let cookie = session_cookie(&app, pending).await;
+        for uri in ["/api/v1/two-factor/enroll", "/api/v1/two-factor/confirm"] {
+            let status = app.clone().oneshot(request(Method::POST, uri, Some(&cookie))).await.unwrap().status();
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{} after {}", uri, pending);
+        }
+    }
+}
+
+#[tokio::test]
+async fn two_factor_endpoints_need_a_login_in_progress() {
+    for uri in ["/api/v1/login/two-factor", "/api/v1/two-factor/enroll", "/api/v1/two-factor/confirm"] {
+        let response = app().oneshot(request(Method::POST, uri, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", uri);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let error: ApiError = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.two_factor, None);
+    }
+}
+
+fn credential(failed_attempts: u32, locked_until: Option<chrono::DateTime<Utc>>) -> TwoFactorCredential {
+    TwoFactorCredential {
+        id: Some(ObjectId::new()),
+        user: ObjectId::new(),
+        secret: String::new(),
+        enabled: true,
+        recovery_codes: Vec::new(),
+        last_step: 0,
+        failed_attempts,
+        locked_until,
+        created: Utc::now(),
+        modified: Utc::now(),
+    }
+}
+
+#[test]
+fn wrong_codes_lock_the_member_out_for_a_while() {
+    let now = Utc::now();
+    for failed_attempts in 0..TwoFactor::MAX_FAILED_ATTEMPTS {
+        assert_eq!(TwoFactor::lockout_after(failed_attempts, now), None);
+    }
+    let until = TwoFactor::lockout_after(TwoFactor::MAX_FAILED_ATTEMPTS, now).unwrap();
+    assert_eq!(until, now + Duration::minutes(TwoFactor::LOCKOUT_MINUTES));
+
+    let locked = credential(0, Some(until));
+    assert!(TwoFactor::is_locked(&locked, now));
+    assert!(TwoFactor::is_locked(&locked, until - Duration::seconds(1)));
+    assert!(!TwoFactor::is_locked(&locked, until));
+    assert!(!TwoFactor::is_locked(&credential(TwoFactor::MAX_FAILED_ATTEMPTS - 1, None), now));
+}
+
+/// The count lives on the credential, so a fresh login, which only replaces the pending login in the session,
+/// starts with the wrong codes of the previous ones.
+#[test]
+fn failed_attempts_are_kept_with_the_credential() {
+    let pending = serde_json::to_value(PendingLogin { user: SessionUser::new(&admin()).unwrap(), step: TwoFactorStep::Verify, started: Utc::now() }).unwrap();
+    assert!(!pending.as_object().unwrap().keys().any(|key| key.contains("attempt")), "{}", pending);
+
+    let mut stored = bson::to_document(&credential(3, None)).unwrap();
+    assert!(stored.contains_key("failed_attempts"));
+    stored.remove("failed_attempts");
+    stored.remove("locked_until");
+    let loaded: TwoFactorCredential = bson::from_document(stored).unwrap();
+    assert_eq!(loaded.failed_attempts, 0);
+    assert_eq!(loaded.locked_until, None);
+}
+
+fn authenticator() -> TOTP {
+    TOTP::new(Algorithm::SHA1, 6, 0, 30, Secret::generate_secret().to_bytes().unwrap(), None, String::new()).unwrap()
+}
+
+#[test]
+fn wrong_codes_are_refused() {
+    let (totp, now) = (authenticator(), Utc::now());
+    let code = totp.generate(now.timestamp() as u64);
+    let wrong = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+    assert!(TwoFactor::accepted_step(&totp, &code, 0, now).is_some());
+    assert_eq!(TwoFactor::accepted_step(&totp, &wrong, 0, now), None);
+    assert_eq!(TwoFactor::accepted_step(&totp, &code, 0, now + Duration::minutes(5)), None);
+}
+
+#[test]
+fn codes_are_accepted_once() {
+    let (totp, now) = (authenticator(), Utc::now());
+    let code = totp.generate(now.timestamp() as u64);
+    let step = TwoFactor::accepted_step(&totp, &code, 0, now).unwrap();
+    assert_eq!(TwoFactor::accepted_step(&totp, &code, step, now), None);
+    let earlier = totp.generate(now.timestamp() as u64 - 30);
+    assert_eq!(TwoFactor::accepted_step(&totp, &earlier, step, now), None);
+    let next = totp.generate(now.timestamp() as u64 + 30);
+    assert_eq!(TwoFactor::accepted_step(&totp, &next, step, now), Some(step + 1));
+}
+
+#[test]
+fn recovery_codes_are_used_once() {
+    let (codes, mut hashes) = TwoFactor::new_recovery_codes();
+    assert!(TwoFactor::take_recovery_code(&mut hashes, &codes[0]));
+    assert!(!TwoFactor::take_recovery_code(&mut hashes, &codes[0]));
+    assert_eq!(hashes.len(), codes.len() - 1);
+    // Typed without the dash and in capitals, it is still the same code.
+    assert!(TwoFactor::take_recovery_code(&mut hashes, &codes[1].replace('-', "").to_uppercase()));
+    assert!(!TwoFactor::take_recovery_code(&mut hashes, &codes[1]));
+    assert!(!TwoFactor::take_recovery_code(&mut hashes, "aaaaa-aaaaa"));
+    assert_eq!(hashes.len(), codes.len() - 2);
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "server")]
+
+use std::future::Future;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bson::oid::ObjectId;
+use chrono::Utc;
+use dioxus::prelude::ServerFnError;
+use freelance_test::backend::{
+    api::{api::Api::SessionUser, auth, jwt::Jwt, routes::Routes},
+    forms::{AccountStatus, AuthError, Forms::UserRole, UserView},
+    mongo_models::Docs::BaseUser,
+    users::Users,
+};
+
+const STORED_CREDENTIAL: &str = "c2VjcmV0LWNpcGhlcnRleHQ=";
+
+fn stored_user() -> BaseUser {
+    BaseUser {
+        id: Some(ObjectId::new()),
+        password: STORED_CREDENTIAL.to_string(),
+        role: UserRole::Seller,
+        status: AccountStatus::Active,
+        created: Utc::now(),
+        modified: Utc::now(),
+    }
+}
+
+fn assert_no_secrets(json: &str) {
+    assert!(!json.contains("password"), "`password` field serialized: {}", json);
+    assert!(!json.contains(STORED_CREDENTIAL), "stored credential serialized: {}", json);
+}
+
+#[test]
+fn user_view_omits_the_stored_credential() {
+    let json = serde_json::to_string(&Users::to_view(&stored_user())).unwrap();
+    assert_no_secrets(&json);
+}
+
+#[test]
+fn session_user_omits_the_stored_credential() {
+    let session_user = SessionUser::new(&stored_user()).unwrap();
+    assert_no_secrets(&serde_json::to_string(&session_user).unwrap());
+    assert_no_secrets(&bson::to_document(&session_user).unwrap().to_string());
+}
+
+#[test]
+fn sessions_storing_the_whole_user_still_load() {
+    let user = stored_user();
+    let session_user = serde_json::from_value::<SessionUser>(serde_json::to_value(&user).unwrap()).unwrap();
+    assert_eq!(Some(session_user.id), user.id);
+}
+
+/// Compiles only when `server_fn` answers with a `UserView`.
+fn answers_user_view<F, A, Fut>(_server_fn: F)
+where
+    F: Fn(A) -> Fut,
+    Fut: Future<Output = Result<UserView, ServerFnError<AuthError>>>,
+{
+}
+
+#[test]
+fn every_login_and_registration_answers_a_user_view() {
+    answers_user_view(auth::register);
+    answers_user_view(auth::login);
+    answers_user_view(auth::verify_two_factor);
+    answers_user_view(auth::finish_passkey_login);
+}
+
+#[test]
+fn documented_responses_omit_the_stored_credential() {
+    let schemas = Routes::openapi().components.unwrap().schemas;
+    assert!(!schemas.contains_key("BaseUser"), "BaseUser is documented as a response body");
+    assert_no_secrets(&serde_json::to_string(&schemas["UserView"]).unwrap());
+}
+
+/// The session token is handed to the browser by `is_authenticated`, so it names the member by id only.
+#[test]
+fn session_token_omits_the_stored_credential() {
+    std::env::set_var("JWT_PASSCODE", "test-jwt-passcode");
+    let user = stored_user();
+    let id = user.id.unwrap();
+    let token = Jwt::get_jwt(&id).unwrap();
+    let payload = URL_SAFE_NO_PAD.decode(token.split('.').nth(1).unwrap()).unwrap();
+    let payload = String::from_utf8(payload).unwrap();
+    assert_no_secrets(&payload);
+    let claims: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&payload).unwrap();
+    let mut fields: Vec<&str> = claims.keys().map(String::as_str).collect();
+    fields.sort();
+    assert_eq!(fields, ["exp", "sub"]);
+    assert_eq!(Jwt::decode_jwt(&token).unwrap().sub, id.to_hex());
+}